@@ -0,0 +1,341 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use serde_json::{Value, json};
+
+use crate::config_writer;
+use crate::state::State;
+use crate::types::{ClientKind, McpServer};
+
+/// Rough characters per token for tool definitions. JSON schema is dense
+/// with punctuation, so real tokenizers land close to this; treat the
+/// result as an estimate, not a count.
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Estimated context tokens a single tool definition costs, based on the
+/// parts a client sends to the model: name, description and input schema
+pub fn estimate_tool_tokens(tool: &Value) -> usize {
+    let sent = json!({
+        "name": tool["name"],
+        "description": tool["description"],
+        "input_schema": tool["inputSchema"],
+    });
+    sent.to_string().chars().count().div_ceil(CHARS_PER_TOKEN)
+}
+
+pub fn estimate_tokens(tools: &[Value]) -> usize {
+    tools.iter().map(estimate_tool_tokens).sum()
+}
+
+// ---------------------------------------------------------------------------
+// Context cost per server and per client
+// ---------------------------------------------------------------------------
+
+/// Context cost of one configured server, from its last recorded tool list
+pub struct ServerCost<'a> {
+    pub server: &'a McpServer,
+    pub tool_count: Option<usize>,
+    pub tokens: Option<usize>,
+    pub over_budget: bool,
+}
+
+/// Sum over every server configured in one client
+pub struct ClientCost {
+    pub client: ClientKind,
+    pub tokens: usize,
+    /// Servers whose tools haven't been listed yet, so aren't in the sum
+    pub unknown: Vec<String>,
+}
+
+pub struct ContextReport<'a> {
+    pub servers: Vec<ServerCost<'a>>,
+    pub clients: Vec<ClientCost>,
+    pub budget: usize,
+}
+
+impl<'a> ContextReport<'a> {
+    pub fn build(servers: &'a [McpServer], state: &State, budget: usize) -> Self {
+        let costs: Vec<ServerCost> = servers
+            .iter()
+            .map(|server| {
+                let tools = state.tools(server);
+                let tokens = tools.map(estimate_tokens);
+                ServerCost {
+                    server,
+                    tool_count: tools.map(<[Value]>::len),
+                    tokens,
+                    over_budget: tokens.is_some_and(|t| t > budget),
+                }
+            })
+            .collect();
+
+        let mut clients: Vec<ClientCost> = Vec::new();
+        for cost in &costs {
+            let idx = match clients.iter().position(|c| c.client == cost.server.client) {
+                Some(i) => i,
+                None => {
+                    clients.push(ClientCost {
+                        client: cost.server.client.clone(),
+                        tokens: 0,
+                        unknown: Vec::new(),
+                    });
+                    clients.len() - 1
+                }
+            };
+            match cost.tokens {
+                Some(t) => clients[idx].tokens += t,
+                None => clients[idx].unknown.push(cost.server.name.clone()),
+            }
+        }
+
+        ContextReport {
+            servers: costs,
+            clients,
+            budget,
+        }
+    }
+
+    /// Highest known estimate among servers with this name — the same
+    /// server configured in several clients is shown once in the matrix
+    pub fn tokens_for_name(&self, name: &str) -> Option<usize> {
+        self.servers
+            .iter()
+            .filter(|c| c.server.name == name)
+            .filter_map(|c| c.tokens)
+            .max()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tool name collisions and length limits
+// ---------------------------------------------------------------------------
+
+/// Tools that end up with the same name in one application's session
+pub struct Collision {
+    pub app: &'static str,
+    /// Name as the client exposes it
+    pub name: String,
+    /// (server, tool) pairs sharing it
+    pub tools: Vec<(String, String)>,
+}
+
+/// A tool whose qualified name is longer than the client accepts
+pub struct LongName {
+    pub client: ClientKind,
+    pub server: String,
+    pub qualified: String,
+    pub limit: usize,
+}
+
+#[derive(Default)]
+pub struct ToolNameReport {
+    pub collisions: Vec<Collision>,
+    pub too_long: Vec<LongName>,
+}
+
+impl ToolNameReport {
+    /// Check every application's combined tool list, from the tool lists
+    /// recorded by health checks. Servers never checked are skipped.
+    pub fn build(servers: &[McpServer], state: &State) -> Self {
+        let mut report = ToolNameReport::default();
+        let mut by_name: BTreeMap<(&'static str, String), Vec<(String, String)>> = BTreeMap::new();
+        let mut seen: HashSet<(&'static str, &str)> = HashSet::new();
+
+        for server in servers {
+            let app = server.client.app_name();
+            // The same server in two of an app's configs is one server
+            if !seen.insert((app, server.name.as_str())) {
+                continue;
+            }
+            let Some(tools) = state.tools(server) else {
+                continue;
+            };
+            for tool in tools {
+                let tool_name = tool["name"].as_str().unwrap_or("");
+                let qualified = server.client.qualified_tool_name(&server.name, tool_name);
+                let limit = server.client.tool_name_limit();
+                if qualified.len() > limit {
+                    report.too_long.push(LongName {
+                        client: server.client.clone(),
+                        server: server.name.clone(),
+                        qualified: qualified.clone(),
+                        limit,
+                    });
+                }
+                by_name
+                    .entry((app, qualified))
+                    .or_default()
+                    .push((server.name.clone(), tool_name.to_string()));
+            }
+        }
+
+        report.collisions = by_name
+            .into_iter()
+            .filter(|(_, tools)| tools.len() > 1)
+            .map(|((app, name), tools)| Collision { app, name, tools })
+            .collect();
+        report
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.collisions.is_empty() && self.too_long.is_empty()
+    }
+
+    /// Whether this server in this client has a colliding or too-long tool name
+    pub fn affects(&self, server: &str, client: &ClientKind) -> bool {
+        self.collisions
+            .iter()
+            .any(|c| c.app == client.app_name() && c.tools.iter().any(|(s, _)| s == server))
+            || self
+                .too_long
+                .iter()
+                .any(|l| l.server == server && &l.client == client)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tool-count limits
+// ---------------------------------------------------------------------------
+
+/// An application loading more tools than it supports
+pub struct ToolLimitExcess {
+    pub app: &'static str,
+    pub limit: usize,
+    pub total: usize,
+    /// (server, tool count) in discovery order
+    pub servers: Vec<(String, usize)>,
+    /// Index of the first server whose tools no longer fit
+    pub over_from: usize,
+}
+
+impl ToolLimitExcess {
+    /// The servers that push the total over the limit, counting in
+    /// discovery order — the client's own load order isn't knowable
+    pub fn pushing_over(&self) -> &[(String, usize)] {
+        &self.servers[self.over_from..]
+    }
+
+    /// One-line description, e.g. "Cursor: 52/40 tools, over from github, slack"
+    pub fn summary(&self) -> String {
+        let names: Vec<&str> = self.pushing_over().iter().map(|(n, _)| n.as_str()).collect();
+        format!(
+            "{}: {}/{} tools, over from {}",
+            self.app,
+            self.total,
+            self.limit,
+            names.join(", ")
+        )
+    }
+}
+
+/// Applications whose servers together expose more tools than the client's
+/// known limit. Only servers with a recorded tool list are counted.
+pub fn tool_limit_excess(servers: &[McpServer], state: &State) -> Vec<ToolLimitExcess> {
+    let mut apps: Vec<ToolLimitExcess> = Vec::new();
+    for server in servers {
+        let Some(limit) = server.client.tool_limit() else {
+            continue;
+        };
+        let Some(tools) = state.tools(server) else {
+            continue;
+        };
+        let app = server.client.app_name();
+        let idx = match apps.iter().position(|a| a.app == app) {
+            Some(i) => i,
+            None => {
+                apps.push(ToolLimitExcess {
+                    app,
+                    limit,
+                    total: 0,
+                    servers: Vec::new(),
+                    over_from: 0,
+                });
+                apps.len() - 1
+            }
+        };
+        let entry = &mut apps[idx];
+        // The same server in two of an app's configs is loaded once
+        if !entry.servers.iter().any(|(name, _)| name == &server.name) {
+            entry.servers.push((server.name.clone(), tools.len()));
+            // This server is the one that crosses the limit
+            if entry.total <= limit && entry.total + tools.len() > limit {
+                entry.over_from = entry.servers.len() - 1;
+            }
+            entry.total += tools.len();
+        }
+    }
+    apps.retain(|a| a.total > a.limit);
+    apps
+}
+
+// ---------------------------------------------------------------------------
+// Config drift
+// ---------------------------------------------------------------------------
+
+/// A server defined differently in different clients
+pub struct ConfigDrift<'a> {
+    pub name: String,
+    /// Each distinct definition and the servers that use it
+    pub variants: Vec<Vec<&'a McpServer>>,
+    /// Fields that don't agree, e.g. `args` or `env.GITHUB_TOKEN`
+    pub fields: Vec<String>,
+}
+
+/// Servers whose same-named definitions disagree across clients
+pub fn config_drift(servers: &[McpServer]) -> Vec<ConfigDrift<'_>> {
+    let mut by_name: BTreeMap<&str, Vec<&McpServer>> = BTreeMap::new();
+    for server in servers {
+        by_name.entry(&server.name).or_default().push(server);
+    }
+
+    let mut drift = Vec::new();
+    for (name, group) in by_name {
+        let mut variants: Vec<(Value, Vec<&McpServer>)> = Vec::new();
+        for server in group {
+            let value = config_writer::server_value(server);
+            match variants.iter_mut().find(|(v, _)| *v == value) {
+                Some((_, users)) => users.push(server),
+                None => variants.push((value, vec![server])),
+            }
+        }
+        if variants.len() < 2 {
+            continue;
+        }
+        let values: Vec<&Value> = variants.iter().map(|(v, _)| v).collect();
+        drift.push(ConfigDrift {
+            name: name.to_string(),
+            fields: differing_fields(&values),
+            variants: variants.into_iter().map(|(_, users)| users).collect(),
+        });
+    }
+    drift
+}
+
+/// Top-level fields that differ, with `env` and `headers` broken down by key
+/// so the values themselves never need showing
+fn differing_fields(values: &[&Value]) -> Vec<String> {
+    let mut keys = BTreeSet::new();
+    for value in values {
+        if let Some(obj) = value.as_object() {
+            keys.extend(obj.keys().cloned());
+        }
+    }
+    let mut fields = Vec::new();
+    for key in keys {
+        if key == "env" || key == "headers" {
+            let mut inner = BTreeSet::new();
+            for value in values {
+                if let Some(obj) = value[&key].as_object() {
+                    inner.extend(obj.keys().cloned());
+                }
+            }
+            for k in inner {
+                if values.iter().any(|v| v[&key][&k] != values[0][&key][&k]) {
+                    fields.push(format!("{}.{}", key, k));
+                }
+            }
+        } else if values.iter().any(|v| v[&key] != values[0][&key]) {
+            fields.push(key);
+        }
+    }
+    fields
+}
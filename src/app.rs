@@ -1,24 +1,86 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::path::PathBuf;
 use std::sync::mpsc;
+use std::time::{Duration, Instant};
 
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 
+use crate::analysis::{ClientCost, ContextReport, ToolLimitExcess, ToolNameReport, tool_limit_excess};
+use crate::bridge;
 use crate::config_writer;
 use crate::discovery::discover;
 use crate::health;
+use crate::inspector::{InspectStep, Inspector};
+use crate::launch::LaunchOptions;
+use crate::pins;
+use crate::settings::Settings;
+use crate::state::{self, Outcome, State};
 use crate::types::{ClientKind, DiscoveryResult, HealthResult, HealthStatus, McpServer, Transport};
+use crate::vault::{self, Vault};
 use crate::wizard::*;
 
+/// Interval used when monitoring is toggled on without a configured one
+const DEFAULT_AUTO_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// What the client matrix shows, from the tool lists in the state
+#[derive(Default)]
+pub struct MatrixReport {
+    /// Highest token estimate per server name
+    pub tokens: HashMap<String, usize>,
+    pub clients: Vec<ClientCost>,
+    pub budget: usize,
+    pub names: ToolNameReport,
+    pub limits: Vec<ToolLimitExcess>,
+}
+
+impl MatrixReport {
+    fn build(servers: &[McpServer], state: &State, budget: usize) -> Self {
+        let context = ContextReport::build(servers, state, budget);
+        let tokens = servers
+            .iter()
+            .filter_map(|s| Some((s.name.clone(), context.tokens_for_name(&s.name)?)))
+            .collect();
+        MatrixReport {
+            tokens,
+            clients: context.clients,
+            budget,
+            names: ToolNameReport::build(servers, state),
+            limits: tool_limit_excess(servers, state),
+        }
+    }
+
+    pub fn client(&self, client: &ClientKind) -> Option<&ClientCost> {
+        self.clients.iter().find(|c| &c.client == client)
+    }
+}
+
 pub struct App {
     pub result: DiscoveryResult,
     pub selected: usize,
     pub scroll_offset: usize,
     pub show_errors: bool,
+    /// Show secret values in the detail panel instead of masking them
+    pub show_secrets: bool,
     pub cwd: PathBuf,
     pub health_tx: mpsc::Sender<HealthResult>,
     pub health_rx: mpsc::Receiver<HealthResult>,
+    /// Foreground checks currently running
     pub checking_count: usize,
+    /// Foreground checks waiting for a free slot
+    check_queue: VecDeque<usize>,
+    /// Persisted health history, saved after each batch of results
+    pub state: State,
+    /// Pin verdict per server, updated when discovery or state changes
+    pub pin_verdicts: Vec<pins::Verdict>,
+    /// Context and tool-name reports for the matrix, updated with the verdicts
+    pub matrix: MatrixReport,
+    pub settings: Settings,
+    /// Background monitoring interval; None when monitoring is off
+    pub auto_check: Option<Duration>,
+    next_auto_check: Option<Instant>,
+    auto_queue: VecDeque<usize>,
+    /// Servers with a background check in flight
+    auto_running: HashSet<usize>,
     pub mode: Mode,
     pub status_message: Option<String>,
     pub status_timer: u8, // frames to show status message
@@ -30,29 +92,71 @@ impl App {
     pub fn new(cwd: PathBuf) -> Self {
         let result = discover(&cwd);
         let (health_tx, health_rx) = mpsc::channel();
-        App {
+        let (settings, settings_error) = match Settings::load() {
+            Ok(s) => (s, None),
+            Err(e) => (Settings::default(), Some(e)),
+        };
+        let auto_check = settings.auto_check_interval_secs.map(Duration::from_secs);
+        let (state, state_warning) = State::load_or_warn();
+        let mut app = App {
             result,
             selected: 0,
             scroll_offset: 0,
             show_errors: false,
+            show_secrets: false,
             cwd,
             health_tx,
             health_rx,
             checking_count: 0,
+            check_queue: VecDeque::new(),
+            state,
+            pin_verdicts: Vec::new(),
+            matrix: MatrixReport::default(),
+            settings,
+            auto_check,
+            next_auto_check: auto_check.map(|_| Instant::now()),
+            auto_queue: VecDeque::new(),
+            auto_running: HashSet::new(),
             mode: Mode::Normal,
             status_message: None,
             status_timer: 0,
             detail_content_height: 0,
             detail_visible_height: 0,
+        };
+        if let Some(e) = settings_error {
+            app.set_status(format!("Ignoring invalid config: {}", e));
+        }
+        if let Some(w) = state_warning {
+            app.set_status(w);
         }
+        app.update_verdicts();
+        app.update_matrix();
+        app
+    }
+
+    fn update_verdicts(&mut self) {
+        self.pin_verdicts = self.result.servers.iter().map(|s| pins::verify(&self.state, s)).collect();
+    }
+
+    fn update_matrix(&mut self) {
+        self.matrix = MatrixReport::build(&self.result.servers, &self.state, self.settings.tool_token_budget());
     }
 
     pub fn refresh(&mut self) {
         self.result = discover(&self.cwd);
+        // Queued indices point into the old server list
+        self.check_queue.clear();
+        self.auto_queue.clear();
+        // So do running background checks; count them as foreground so
+        // their results still free a slot when they arrive
+        self.checking_count += self.auto_running.len();
+        self.auto_running.clear();
         if self.selected >= self.result.servers.len() {
             self.selected = self.result.servers.len().saturating_sub(1);
         }
         self.scroll_offset = 0;
+        self.update_verdicts();
+        self.update_matrix();
     }
 
     pub fn selected_server(&self) -> Option<&McpServer> {
@@ -91,39 +195,175 @@ impl App {
         if idx >= self.result.servers.len() {
             return;
         }
-        if !self.result.servers[idx].transport.is_stdio() {
+        if !self.result.servers[idx].transport.is_checkable() {
             return;
         }
-        let server = self.result.servers[idx].clone();
-        self.result.servers[idx].health = HealthStatus::Checking;
-        self.checking_count += 1;
-        health::spawn_health_check(idx, &server, self.health_tx.clone());
+        // An explicit single check jumps the queue
+        if self.enqueue_check(idx) {
+            self.check_queue.retain(|&i| i != idx);
+            self.check_queue.push_front(idx);
+        }
+        self.pump_checks();
     }
 
     pub fn check_all(&mut self) {
-        let servers: Vec<(usize, McpServer)> = self
+        let checkable: Vec<usize> = self
             .result
             .servers
             .iter()
             .enumerate()
-            .filter(|(_, s)| s.transport.is_stdio())
-            .map(|(i, s)| (i, s.clone()))
+            .filter(|(_, s)| s.transport.is_checkable())
+            .map(|(i, _)| i)
             .collect();
 
-        for (i, server) in &servers {
-            self.result.servers[*i].health = HealthStatus::Checking;
+        for i in checkable {
+            self.enqueue_check(i);
+        }
+        self.pump_checks();
+    }
+
+    /// Queue a foreground check unless one is already queued or running.
+    /// A background check already in flight is adopted instead.
+    fn enqueue_check(&mut self, idx: usize) -> bool {
+        match self.result.servers[idx].health {
+            HealthStatus::Checking => return false,
+            HealthStatus::Queued => return true,
+            _ => {}
+        }
+        if self.auto_running.remove(&idx) {
+            self.result.servers[idx].health = HealthStatus::Checking;
             self.checking_count += 1;
-            health::spawn_health_check(*i, server, self.health_tx.clone());
+            return false;
         }
+        self.result.servers[idx].health = HealthStatus::Queued;
+        self.check_queue.push_back(idx);
+        true
+    }
+
+    /// Start queued checks while fewer than the configured limit are running.
+    /// Foreground checks go first; background ones fill any spare slots.
+    fn pump_checks(&mut self) {
+        let limit = self.settings.check_limit();
+        let launch = LaunchOptions::for_checks(&self.settings);
+        while self.checking_count + self.auto_running.len() < limit {
+            if let Some(idx) = self.check_queue.pop_front() {
+                let Some(server) = self.result.servers.get_mut(idx) else {
+                    continue;
+                };
+                server.health = HealthStatus::Checking;
+                self.checking_count += 1;
+                health::spawn_health_check(idx, server, &launch, self.health_tx.clone());
+            } else if let Some(idx) = self.auto_queue.pop_front() {
+                let Some(server) = self.result.servers.get(idx) else {
+                    continue;
+                };
+                if matches!(server.health, HealthStatus::Checking | HealthStatus::Queued) {
+                    continue;
+                }
+                self.auto_running.insert(idx);
+                health::spawn_health_check(idx, server, &launch, self.health_tx.clone());
+            } else {
+                break;
+            }
+        }
+    }
+
+    pub fn queued_count(&self) -> usize {
+        self.check_queue.len()
     }
 
     pub fn poll_health(&mut self) {
+        let mut recorded = false;
+        let mut changes = Vec::new();
         while let Ok(result) = self.health_rx.try_recv() {
-            if let Some(server) = self.result.servers.get_mut(result.server_index) {
+            // A check started before a refresh may point at a different server now
+            let current = self
+                .result
+                .servers
+                .get(result.server_index)
+                .is_some_and(|s| state::server_key(s) == result.server_key);
+            let background = current && self.auto_running.remove(&result.server_index);
+            if current && let Some(server) = self.result.servers.get_mut(result.server_index) {
+                if background {
+                    let before = self.state.get(server).and_then(|h| h.last()).map(|e| e.outcome);
+                    let after = Outcome::from_status(&result.status);
+                    if before.is_some() && before != after {
+                        changes.push(describe_change(&server.name, &result.status));
+                    }
+                }
+                self.state.record(server, &result.status, result.checked_at);
+                if let Some(tools) = result.tools {
+                    self.state.record_tools(server, tools, result.checked_at);
+                }
+                let verdict = pins::verify(&self.state, server);
+                if let Some(cached) = self.pin_verdicts.get_mut(result.server_index) {
+                    let was_changed = matches!(cached, pins::Verdict::Changed(_));
+                    if !was_changed && matches!(verdict, pins::Verdict::Changed(_)) {
+                        changes.push(format!("⚠ {} changed since pinned", server.name));
+                    }
+                    *cached = verdict;
+                }
+                recorded = true;
                 server.health = result.status;
                 server.last_checked = Some(result.checked_at);
+                server.cleanup = result.cleanup;
+                server.metrics = result.metrics;
+            }
+            if !background {
+                self.checking_count = self.checking_count.saturating_sub(1);
+            }
+        }
+        if recorded {
+            if let Err(e) = self.state.save() {
+                self.set_status(format!("Failed to save health history: {}", e));
             }
-            self.checking_count = self.checking_count.saturating_sub(1);
+            // Saving pulls in pins accepted and checks recorded elsewhere
+            self.update_verdicts();
+            self.update_matrix();
+        }
+        if !changes.is_empty() {
+            self.notify(changes.join("  "));
+        }
+        self.pump_checks();
+    }
+
+    /// Turn background monitoring on (configured or default interval) or off
+    pub fn toggle_monitor(&mut self) {
+        if self.auto_check.take().is_some() {
+            self.next_auto_check = None;
+            self.auto_queue.clear();
+            self.set_status("Monitoring off".to_string());
+        } else {
+            let interval = self
+                .settings
+                .auto_check_interval_secs
+                .map(Duration::from_secs)
+                .unwrap_or(DEFAULT_AUTO_CHECK_INTERVAL);
+            self.auto_check = Some(interval);
+            self.next_auto_check = Some(Instant::now());
+            self.set_status(format!("Monitoring every {}s", interval.as_secs()));
+        }
+    }
+
+    /// Queue a background round when one is due. Background checks share
+    /// the check queue's concurrency limit and leave the displayed status
+    /// alone until their result arrives.
+    pub fn tick_monitor(&mut self) {
+        let Some(interval) = self.auto_check else {
+            return;
+        };
+        let due = self.next_auto_check.is_some_and(|t| Instant::now() >= t);
+        if due && self.auto_queue.is_empty() && self.auto_running.is_empty() {
+            self.auto_queue = self
+                .result
+                .servers
+                .iter()
+                .enumerate()
+                .filter(|(_, s)| s.transport.is_checkable())
+                .map(|(i, _)| i)
+                .collect();
+            self.next_auto_check = Some(Instant::now() + interval);
+            self.pump_checks();
         }
     }
 
@@ -132,6 +372,13 @@ impl App {
         self.status_timer = 15; // ~3 seconds at 200ms poll
     }
 
+    /// Like set_status, but stays up longer — for changes spotted while
+    /// nobody is looking at the screen
+    pub fn notify(&mut self, msg: String) {
+        self.status_message = Some(msg);
+        self.status_timer = 50; // ~10 seconds at 200ms poll
+    }
+
     pub fn tick_status(&mut self) {
         if self.status_timer > 0 {
             self.status_timer -= 1;
@@ -163,19 +410,14 @@ impl App {
 
     /// Build a server's JSON value from its transport + env
     pub fn server_to_value(&self, server: &McpServer) -> serde_json::Value {
-        let env = server.env.clone().unwrap_or_default();
-        match &server.transport {
-            Transport::Stdio { command, args } => {
-                config_writer::build_server_value(command, args, &env)
-            }
-            Transport::Http { url, headers } => {
-                config_writer::build_http_server_value(url, headers.as_ref(), &env)
-            }
-            Transport::Sse { url } => {
-                config_writer::build_sse_server_value(url, &env)
-            }
-            Transport::Unknown => serde_json::json!({}),
-        }
+        config_writer::server_value(server)
+    }
+}
+
+fn describe_change(name: &str, status: &HealthStatus) -> String {
+    match status {
+        HealthStatus::Healthy { .. } => format!("● {} recovered", name),
+        other => format!("{} {} {}", other.symbol(), name, other.label()),
     }
 }
 
@@ -183,21 +425,26 @@ impl App {
 /// When need_editor_path is Some, the caller should exit TUI, run editor, re-enter TUI.
 pub fn handle_event(app: &mut App) -> std::io::Result<(bool, Option<PathBuf>)> {
     app.poll_health();
+    if let Mode::Inspector(ref mut insp) = app.mode {
+        insp.poll();
+    }
+    app.tick_monitor();
     app.tick_status();
 
-    if event::poll(std::time::Duration::from_millis(200))? {
-        if let Event::Key(key @ KeyEvent { kind: KeyEventKind::Press, .. }) = event::read()? {
-            // Ctrl-C always exits
-            if key.modifiers == KeyModifiers::CONTROL && key.code == KeyCode::Char('c') {
-                return Ok((true, None));
-            }
+    if event::poll(std::time::Duration::from_millis(200))?
+        && let Event::Key(key @ KeyEvent { kind: KeyEventKind::Press, .. }) = event::read()?
+    {
+        // Ctrl-C always exits
+        if key.modifiers == KeyModifiers::CONTROL && key.code == KeyCode::Char('c') {
+            return Ok((true, None));
+        }
 
-            match &app.mode {
-                Mode::Normal => return handle_normal(app, key),
-                Mode::AddWizard(_) => handle_add_wizard(app, key),
-                Mode::RemoveConfirm(_) => handle_remove(app, key),
-                Mode::SyncSelect(_) => handle_sync(app, key),
-            }
+        match &app.mode {
+            Mode::Normal => return handle_normal(app, key),
+            Mode::AddWizard(_) => handle_add_wizard(app, key),
+            Mode::Inspector(_) => handle_inspector(app, key),
+            Mode::RemoveConfirm(_) => handle_remove(app, key),
+            Mode::SyncSelect(_) => handle_sync(app, key),
         }
     }
     Ok((false, None))
@@ -208,16 +455,30 @@ fn handle_normal(app: &mut App, key: KeyEvent) -> std::io::Result<(bool, Option<
         KeyCode::Char('q') => return Ok((true, None)),
         KeyCode::Char('r') => app.refresh(),
         KeyCode::Char('!') => app.show_errors = !app.show_errors,
+        KeyCode::Char('v') => {
+            app.show_secrets = !app.show_secrets;
+            app.set_status(if app.show_secrets { "Secrets shown" } else { "Secrets masked" }.to_string());
+        }
         KeyCode::Char('h') => {
             if let Some(server) = app.selected_server() {
-                if !server.transport.is_stdio() {
-                    app.set_status("Health checks only available for stdio servers".to_string());
+                if !server.transport.is_checkable() {
+                    app.set_status("Cannot health check a server with unknown transport".to_string());
                 } else {
                     app.check_selected();
                 }
             }
         }
+        KeyCode::Char('i') => {
+            if let Some(server) = app.selected_server() {
+                if !server.transport.is_stdio() {
+                    app.set_status("Inspector only available for stdio servers".to_string());
+                } else {
+                    app.mode = Mode::Inspector(Inspector::new(server));
+                }
+            }
+        }
         KeyCode::Char('c') => app.check_all(),
+        KeyCode::Char('m') => app.toggle_monitor(),
         KeyCode::Up | KeyCode::Char('k') => app.move_up(),
         KeyCode::Down | KeyCode::Char('j') => app.move_down(),
         KeyCode::PageUp => app.scroll_detail_up(),
@@ -249,11 +510,16 @@ fn handle_normal(app: &mut App, key: KeyEvent) -> std::io::Result<(bool, Option<
                 } else {
                     let name = server.name.clone();
                     let value = app.server_to_value(server);
+                    let bridged = if server.transport.is_stdio() {
+                        None
+                    } else {
+                        bridge::bridged_value(server).ok()
+                    };
                     let missing = app.clients_without_server(&name);
                     if missing.is_empty() {
                         app.set_status("Server already in all clients".to_string());
                     } else {
-                        app.mode = Mode::SyncSelect(SyncSelect::new(name, value, missing));
+                        app.mode = Mode::SyncSelect(SyncSelect::new(name, value, bridged, missing));
                     }
                 }
             }
@@ -319,16 +585,29 @@ fn handle_add_wizard(app: &mut App, key: KeyEvent) {
                 KeyCode::Up | KeyCode::Char('k') => {
                     wiz.transport_type = wiz.transport_type.saturating_sub(1);
                 }
-                KeyCode::Down | KeyCode::Char('j') => {
-                    if wiz.transport_type < 2 {
-                        wiz.transport_type += 1;
-                    }
+                KeyCode::Down | KeyCode::Char('j') if wiz.transport_type < 2 => {
+                    wiz.transport_type += 1;
                 }
                 KeyCode::Enter => {
                     wiz.advance();
                 }
                 _ => {}
             },
+            AddStep::SaveSecrets => match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => match Vault::open_from_env() {
+                    Ok(_) => {
+                        wiz.save_secrets = true;
+                        wiz.advance();
+                    }
+                    Err(e) => wiz.error = Some(e),
+                },
+                KeyCode::Char('n') | KeyCode::Char('N') => {
+                    wiz.save_secrets = false;
+                    wiz.error = None;
+                    wiz.advance();
+                }
+                _ => {}
+            },
             AddStep::Clients => match key.code {
                 KeyCode::Up | KeyCode::Char('k') => wiz.cursor_up(),
                 KeyCode::Down | KeyCode::Char('j') => wiz.cursor_down(),
@@ -358,7 +637,14 @@ fn execute_add(app: &mut App) {
 
     let name = wiz.name.trim().to_string();
     let args = wiz.parsed_args();
-    let env = wiz.parsed_env();
+    let mut env = wiz.parsed_env();
+    if wiz.save_secrets
+        && let Err(e) = save_to_vault(&name, &wiz.secret_keys(), &mut env)
+    {
+        app.set_status(format!("Error saving secrets: {}", e));
+        app.mode = Mode::Normal;
+        return;
+    }
     let server_value = match wiz.transport_type {
         1 => config_writer::build_http_server_value(&wiz.url, None, &env),
         2 => config_writer::build_sse_server_value(&wiz.url, &env),
@@ -368,6 +654,8 @@ fn execute_add(app: &mut App) {
 
     let mut errors = Vec::new();
     let mut success_count = 0;
+    // Adding under an existing name replaces that entry
+    let before = discover(&app.cwd);
 
     for client in &clients {
         match config_writer::add_server(client, &app.cwd, &name, &server_value) {
@@ -375,6 +663,10 @@ fn execute_add(app: &mut App) {
             Err(e) => errors.push(format!("{}: {}", client.label(), e)),
         }
     }
+    pins::follow_edits(&mut app.state, &before.servers, &app.cwd);
+    if let Err(e) = app.state.save() {
+        errors.push(format!("pins: {}", e));
+    }
 
     if errors.is_empty() {
         app.set_status(format!(
@@ -391,6 +683,58 @@ fn execute_add(app: &mut App) {
     app.refresh();
 }
 
+/// Move the given env values into the vault, leaving placeholders behind
+fn save_to_vault(server: &str, keys: &[String], env: &mut HashMap<String, String>) -> Result<(), String> {
+    let mut vault = Vault::open_from_env()?;
+    for key in keys {
+        if let Some(value) = env.get_mut(key) {
+            let name = vault.store(key, value, server);
+            *value = vault::placeholder(&name);
+        }
+    }
+    vault.save()
+}
+
+fn handle_inspector(app: &mut App, key: KeyEvent) {
+    let Mode::Inspector(ref mut insp) = app.mode else {
+        return;
+    };
+
+    match insp.step {
+        // Leaving the inspector drops the worker, which shuts the server down
+        InspectStep::Connecting | InspectStep::List | InspectStep::Failed => match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => app.mode = Mode::Normal,
+            KeyCode::Up | KeyCode::Char('k') => insp.cursor_up(),
+            KeyCode::Down | KeyCode::Char('j') => insp.cursor_down(),
+            KeyCode::Tab | KeyCode::Right | KeyCode::Char('l') => insp.next_tab(),
+            KeyCode::Enter if insp.step == InspectStep::List => insp.open_form(),
+            _ => {}
+        },
+        InspectStep::Form => match key.code {
+            KeyCode::Esc => insp.step = InspectStep::List,
+            KeyCode::Up | KeyCode::BackTab => insp.form.prev_field(),
+            KeyCode::Down | KeyCode::Tab => insp.form.next_field(),
+            KeyCode::Char(c) => insp.form.push_char(c),
+            KeyCode::Backspace => insp.form.pop_char(),
+            KeyCode::Enter => insp.call(),
+            _ => {}
+        },
+        InspectStep::Calling => {
+            if key.code == KeyCode::Esc {
+                insp.abandon_call();
+            }
+        }
+        InspectStep::Result => match key.code {
+            KeyCode::Esc | KeyCode::Enter => insp.back(),
+            KeyCode::Up | KeyCode::Char('k') => insp.scroll_up(),
+            KeyCode::Down | KeyCode::Char('j') => insp.scroll_down(),
+            KeyCode::PageUp => (0..10).for_each(|_| insp.scroll_up()),
+            KeyCode::PageDown => (0..10).for_each(|_| insp.scroll_down()),
+            _ => {}
+        },
+    }
+}
+
 fn handle_remove(app: &mut App, key: KeyEvent) {
     let Mode::RemoveConfirm(ref mut rm) = app.mode else {
         return;
@@ -501,24 +845,44 @@ fn execute_sync(app: &mut App) {
     };
 
     let name = sync.server_name.clone();
-    let value = sync.server_value.clone();
     let clients = sync.selected_clients();
     let mut errors = Vec::new();
     let mut success_count = 0;
+    let mut bridged_count = 0;
 
     for client in &clients {
-        match config_writer::add_server(client, &app.cwd, &name, &value) {
-            Ok(()) => success_count += 1,
+        let bridged = sync.needs_bridge(client);
+        let value = match (&sync.bridged_value, bridged) {
+            (Some(bridged_value), true) => bridged_value,
+            _ => &sync.server_value,
+        };
+        match config_writer::add_server(client, &app.cwd, &name, value) {
+            Ok(()) => {
+                success_count += 1;
+                if bridged {
+                    bridged_count += 1;
+                }
+            }
             Err(e) => errors.push(format!("{}: {}", client.label(), e)),
         }
     }
+    pins::follow_edits(&mut app.state, &app.result.servers, &app.cwd);
+    if let Err(e) = app.state.save() {
+        errors.push(format!("pins: {}", e));
+    }
 
     if errors.is_empty() {
+        let via = if bridged_count > 0 {
+            format!(" ({} via mcpm bridge)", bridged_count)
+        } else {
+            String::new()
+        };
         app.set_status(format!(
-            "Synced \"{}\" to {} client{}",
+            "Synced \"{}\" to {} client{}{}",
             name,
             success_count,
-            if success_count == 1 { "" } else { "s" }
+            if success_count == 1 { "" } else { "s" },
+            via
         ));
     } else {
         app.set_status(format!("Errors: {}", errors.join("; ")));
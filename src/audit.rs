@@ -0,0 +1,338 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::Path;
+
+use crate::secrets::{self, Kind};
+use crate::types::{McpServer, Transport};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+}
+
+impl Severity {
+    pub fn label(self) -> &'static str {
+        match self {
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+        }
+    }
+}
+
+/// One risky pattern in one server's config
+pub struct Finding<'a> {
+    pub server: &'a McpServer,
+    /// Stable check name, e.g. `plaintext-secret`
+    pub check: &'static str,
+    pub severity: Severity,
+    /// Where in the entry, e.g. `env.GITHUB_TOKEN` or `args[2]`
+    pub field: String,
+    /// What was found. Never contains the secret itself.
+    pub message: String,
+    pub remediation: &'static str,
+}
+
+/// Review every server, most severe findings first
+pub fn audit(servers: &[McpServer]) -> Vec<Finding<'_>> {
+    let mut findings = Vec::new();
+    for server in servers {
+        let mut add = |check, severity, field: String, message: String, remediation| {
+            findings.push(Finding {
+                server,
+                check,
+                severity,
+                field,
+                message,
+                remediation,
+            });
+        };
+        check_env(server.env.as_ref(), &mut add);
+        match &server.transport {
+            Transport::Stdio { command, args } => {
+                check_args(args, &mut add);
+                check_shell(command, args, &mut add);
+                check_package(command, args, &mut add);
+                check_filesystem(command, args, &mut add);
+            }
+            Transport::Http { url, headers } => {
+                let headers = headers.clone().unwrap_or_default();
+                check_headers(&headers, &mut add);
+                check_url(url, !headers.is_empty(), &mut add);
+            }
+            Transport::Sse { url } => check_url(url, false, &mut add),
+            Transport::Unknown => {}
+        }
+    }
+    findings.sort_by_key(|f| std::cmp::Reverse(f.severity));
+    findings
+}
+
+type Add<'a> = dyn FnMut(&'static str, Severity, String, String, &'static str) + 'a;
+
+// ---------------------------------------------------------------------------
+// Plaintext secrets
+// ---------------------------------------------------------------------------
+
+const ENV_SECRET_FIX: &str = "Reference the value from the environment (e.g. `${GITHUB_TOKEN}`) \
+    or the vault (`mcpm secret set`, then `${secret:name}`) instead of writing it into the config, \
+    and rotate it if the file was ever shared or committed.";
+const ARG_SECRET_FIX: &str =
+    "Pass it through `env` instead of the command line, where any local user can read it from the process list.";
+const URL_SECRET_FIX: &str =
+    "Send the credential in a header instead of the URL; URLs end up in logs and proxies.";
+
+/// Why `value` under `name` is a stored secret. Values that only look
+/// random are masked in output but not reported.
+fn secret_reason(name: &str, value: &str) -> Option<String> {
+    match secrets::classify(name, value)? {
+        Kind::Random => None,
+        kind => Some(kind.describe()),
+    }
+}
+
+fn check_env(env: Option<&HashMap<String, String>>, add: &mut Add) {
+    let Some(env) = env else { return };
+    let mut keys: Vec<&String> = env.keys().collect();
+    keys.sort();
+    for key in keys {
+        if let Some(reason) = secret_reason(key, &env[key]) {
+            let field = format!("env.{}", key);
+            let message = format!("{} {}", field, reason);
+            add("plaintext-secret", Severity::High, field, message, ENV_SECRET_FIX);
+        }
+    }
+}
+
+fn check_headers(headers: &HashMap<String, String>, add: &mut Add) {
+    let mut keys: Vec<&String> = headers.keys().collect();
+    keys.sort();
+    for key in keys {
+        if let Some(reason) = secret_reason(key, &headers[key]) {
+            let field = format!("headers.{}", key);
+            let message = format!("{} {}", field, reason);
+            add("plaintext-secret", Severity::High, field, message, ENV_SECRET_FIX);
+        }
+    }
+}
+
+fn check_args(args: &[String], add: &mut Add) {
+    for secret in secrets::arg_secrets(args) {
+        if secret.kind == Kind::Random {
+            continue;
+        }
+        let field = format!("args[{}]", secret.index);
+        let message = match &secret.flag {
+            Some(flag) => format!("{} (value of {}) {}", field, flag, secret.kind.describe()),
+            None => format!("{} {}", field, secret.kind.describe()),
+        };
+        add("plaintext-secret", Severity::High, field, message, ARG_SECRET_FIX);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Transport
+// ---------------------------------------------------------------------------
+
+fn check_url(url: &str, sends_headers: bool, add: &mut Add) {
+    let (scheme, rest) = url.split_once("://").unwrap_or(("", url));
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or("");
+    let host = authority.rsplit('@').next().unwrap_or(authority);
+    let host = match host.strip_prefix('[') {
+        Some(v6) => v6.split(']').next().unwrap_or(v6),
+        None => host.split(':').next().unwrap_or(host),
+    };
+
+    if scheme.eq_ignore_ascii_case("http") && !is_loopback(host) {
+        let (severity, message) = if sends_headers {
+            (Severity::High, format!("plain HTTP to {} sends its headers unencrypted", host))
+        } else {
+            (Severity::Medium, format!("plain HTTP to {}", host))
+        };
+        add(
+            "insecure-transport",
+            severity,
+            "url".to_string(),
+            message,
+            "Use https://; over plain HTTP anyone on the network path can read and alter the traffic.",
+        );
+    }
+
+    for (key, kind, _) in secrets::url_secrets(url) {
+        if kind != Kind::Random {
+            let message = format!("url query parameter {} {}", key, kind.describe());
+            add("plaintext-secret", Severity::High, "url".to_string(), message, URL_SECRET_FIX);
+        }
+    }
+    if secrets::has_url_password(url) {
+        let message = "url contains a password".to_string();
+        add("plaintext-secret", Severity::High, "url".to_string(), message, URL_SECRET_FIX);
+    }
+}
+
+/// `localhost` or a loopback IP; `127.example.com` is neither
+fn is_loopback(host: &str) -> bool {
+    host.eq_ignore_ascii_case("localhost")
+        || host
+            .parse::<IpAddr>()
+            .is_ok_and(|ip| ip.to_canonical().is_loopback())
+}
+
+// ---------------------------------------------------------------------------
+// Launch commands
+// ---------------------------------------------------------------------------
+
+/// File name of a command without directory or Windows extension
+fn program(command: &str) -> String {
+    let name = command.rsplit(['/', '\\']).next().unwrap_or(command).to_ascii_lowercase();
+    [".exe", ".cmd", ".bat"]
+        .iter()
+        .find_map(|ext| name.strip_suffix(ext))
+        .map(str::to_string)
+        .unwrap_or(name)
+}
+
+fn check_shell(command: &str, args: &[String], add: &mut Add) {
+    let shell = program(command);
+    let flag = match shell.as_str() {
+        "sh" | "bash" | "zsh" | "dash" | "ksh" | "fish" => {
+            args.iter().position(|a| a.starts_with('-') && !a.starts_with("--") && a.ends_with('c'))
+        }
+        "cmd" => args.iter().position(|a| a.eq_ignore_ascii_case("/c") || a.eq_ignore_ascii_case("/k")),
+        "powershell" | "pwsh" => args.iter().position(|a| {
+            let a = a.to_ascii_lowercase();
+            a == "-c" || a == "-command" || a == "-encodedcommand"
+        }),
+        _ => None,
+    };
+    if let Some(i) = flag {
+        add(
+            "shell-wrapper",
+            Severity::Medium,
+            format!("args[{}]", i),
+            format!("runs through `{} {}`", shell, args[i]),
+            "Put the server's own executable in `command` and its arguments in `args`; a shell line hides what actually runs and expands whatever it contains.",
+        );
+    }
+}
+
+/// Package run by `npx -y`, `bunx` or `uvx`, and its index in args
+fn launched_package(command: &str, args: &[String]) -> Option<(&'static str, usize)> {
+    let runner = match program(command).as_str() {
+        // Without -y npx asks before installing, and a client can't answer
+        "npx" if args.iter().any(|a| a == "-y" || a == "--yes") => "npx",
+        "bunx" => "bunx",
+        "uvx" => "uvx",
+        _ => return None,
+    };
+    let mut i = 0;
+    while i < args.len() {
+        let arg = &args[i];
+        if arg == "-p" || arg == "--package" || arg == "--from" {
+            return (i + 1 < args.len()).then_some((runner, i + 1));
+        }
+        if arg.starts_with("--package=") || arg.starts_with("--from=") {
+            return Some((runner, i));
+        }
+        if !arg.starts_with('-') {
+            return Some((runner, i));
+        }
+        i += 1;
+    }
+    None
+}
+
+fn check_package(command: &str, args: &[String], add: &mut Add) {
+    let Some((runner, i)) = launched_package(command, args) else {
+        return;
+    };
+    // --package=name or --from=name
+    let spec = match args[i].split_once('=') {
+        Some((_, value)) if args[i].starts_with("--") => value,
+        _ => args[i].as_str(),
+    };
+
+    if runner == "uvx" {
+        if !spec.contains("==") {
+            add(
+                "unpinned-package",
+                Severity::Medium,
+                format!("args[{}]", i),
+                format!("`uvx {}` runs whatever version is newest at each launch", spec),
+                "Pin an exact version (`package==1.2.3`) so an upstream release can't change what runs.",
+            );
+        }
+        return;
+    }
+
+    // @scope/name@version or name@version
+    let (name, version) = match spec.strip_prefix('@') {
+        Some(scoped) => match scoped.split_once('@') {
+            Some((name, version)) => (&spec[..name.len() + 1], Some(version)),
+            None => (spec, None),
+        },
+        None => match spec.split_once('@') {
+            Some((name, version)) => (name, Some(version)),
+            None => (spec, None),
+        },
+    };
+    if !version.is_some_and(is_exact_version) {
+        add(
+            "unpinned-package",
+            Severity::Medium,
+            format!("args[{}]", i),
+            format!("`{} {}` installs and runs whatever version is newest at each launch", runner, spec),
+            "Pin an exact version (`package@1.2.3`) so an upstream release can't change what runs.",
+        );
+    }
+    if !name.starts_with('@') {
+        add(
+            "unscoped-package",
+            Severity::Low,
+            format!("args[{}]", i),
+            format!("`{}` is an unscoped npm package", name),
+            "Check the name against the publisher's docs; unscoped names are the usual target of typosquatting.",
+        );
+    }
+}
+
+/// 1.2.3 or 1.2.3-beta.1, not a range or a tag like `latest`
+fn is_exact_version(version: &str) -> bool {
+    version.starts_with(|c: char| c.is_ascii_digit())
+        && version.split(['-', '+']).next().unwrap_or("").split('.').count() == 3
+        && version.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+'))
+}
+
+fn check_filesystem(command: &str, args: &[String], add: &mut Add) {
+    let is_filesystem = std::iter::once(command)
+        .chain(args.iter().map(String::as_str))
+        .any(|a| a.contains("filesystem"));
+    if !is_filesystem {
+        return;
+    }
+    let home = dirs::home_dir();
+    for (i, arg) in args.iter().enumerate() {
+        if arg.is_empty() {
+            continue;
+        }
+        let trimmed = arg.trim_end_matches(['/', '\\']);
+        let what = if trimmed.is_empty() || (trimmed.len() == 2 && trimmed.ends_with(':')) {
+            "the whole filesystem"
+        } else if matches!(trimmed, "~" | "$HOME" | "${HOME}" | "%USERPROFILE%")
+            || home.as_deref().is_some_and(|h| Path::new(trimmed) == h)
+        {
+            "your entire home directory"
+        } else {
+            continue;
+        };
+        add(
+            "broad-filesystem",
+            Severity::High,
+            format!("args[{}]", i),
+            format!("filesystem server can read and write {}", what),
+            "Give the server only the project directories it needs.",
+        );
+    }
+}
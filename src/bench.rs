@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::health::Session;
+
+/// Timings and failures collected over repeated cold starts of one server
+#[derive(Debug, Default)]
+pub struct BenchReport {
+    pub runs: usize,
+    pub failures: usize,
+    /// Launch until the `initialize` response — what a client waits for
+    pub cold_start: Vec<Duration>,
+    /// `tools/list` round-trip once initialized — the rest of the handshake a
+    /// client does before it can use the server. Only from runs that listed tools.
+    pub handshake: Vec<Duration>,
+    pub last_error: Option<String>,
+}
+
+/// p50/p95/max of a set of samples
+#[derive(Debug, Clone, Copy)]
+pub struct Summary {
+    pub p50: Duration,
+    pub p95: Duration,
+    pub max: Duration,
+}
+
+impl BenchReport {
+    pub fn cold_start_summary(&self) -> Option<Summary> {
+        summarize(&self.cold_start)
+    }
+
+    pub fn handshake_summary(&self) -> Option<Summary> {
+        summarize(&self.handshake)
+    }
+
+    pub fn failure_rate(&self) -> f64 {
+        if self.runs == 0 {
+            0.0
+        } else {
+            self.failures as f64 / self.runs as f64
+        }
+    }
+}
+
+/// Spawn the server `runs` times, one after another, through a full
+/// initialize → tools/list → shutdown cycle. `on_run` is called after each
+/// run with whether it succeeded, for progress output.
+pub fn run(
+    command: &str,
+    args: &[String],
+    env: &Option<HashMap<String, String>>,
+    runs: usize,
+    mut on_run: impl FnMut(bool),
+) -> BenchReport {
+    let mut report = BenchReport {
+        runs,
+        ..BenchReport::default()
+    };
+
+    for _ in 0..runs {
+        match run_once(command, args, env) {
+            Ok((cold_start, handshake)) => {
+                report.cold_start.push(cold_start);
+                report.handshake.extend(handshake);
+                on_run(true);
+            }
+            Err(e) => {
+                report.failures += 1;
+                report.last_error = Some(e);
+                on_run(false);
+            }
+        }
+    }
+    report
+}
+
+fn run_once(
+    command: &str,
+    args: &[String],
+    env: &Option<HashMap<String, String>>,
+) -> Result<(Duration, Option<Duration>), String> {
+    let started = Instant::now();
+    let mut session = Session::spawn(command, args, env).map_err(|e| e.to_string())?;
+    let outcome = session.initialize().and_then(|init| {
+        let cold_start = started.elapsed();
+        if init["capabilities"].get("tools").is_some() {
+            session.list_tools()?;
+        }
+        Ok(cold_start)
+    });
+    let (_, metrics) = session.close();
+    let cold_start = outcome.map_err(|e| e.to_string())?;
+    // None when the server has no tools to list; not a 0s sample
+    Ok((cold_start, metrics.tools_list))
+}
+
+fn summarize(samples: &[Duration]) -> Option<Summary> {
+    if samples.is_empty() {
+        return None;
+    }
+    let mut sorted = samples.to_vec();
+    sorted.sort();
+    Some(Summary {
+        p50: percentile(&sorted, 50.0),
+        p95: percentile(&sorted, 95.0),
+        max: *sorted.last()?,
+    })
+}
+
+/// Nearest-rank percentile of an already sorted slice
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
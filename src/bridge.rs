@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::sync::{Arc, Mutex};
+
+use serde_json::{Value, json};
+
+use crate::endpoint::{self, Handler, Reply};
+use crate::health::{CALL_TIMEOUT, Session};
+use crate::jsonrpc::{self, FrameDecoder};
+use crate::remote::RemoteSession;
+use crate::types::{McpServer, Transport};
+
+/// Bridged entries pass each header as `MCPM_BRIDGE_HEADER_<N>="Name: value"`,
+/// numbered from 1, so tokens stay out of the process list
+const HEADER_ENV_PREFIX: &str = "MCPM_BRIDGE_HEADER_";
+
+/// The command line a client should run to reach `server` over stdio through
+/// `mcpm bridge`. Used when writing an HTTP/SSE server to a client that only
+/// launches local processes.
+pub fn bridged_value(server: &McpServer) -> Result<Value, String> {
+    let (url, headers, legacy_sse) = match &server.transport {
+        Transport::Http { url, headers } => (url, headers.clone().unwrap_or_default(), false),
+        Transport::Sse { url } => (url, HashMap::new(), true),
+        _ => return Err("only HTTP and SSE servers need bridging".to_string()),
+    };
+    let exe = std::env::current_exe().map_err(|e| format!("cannot locate mcpm: {}", e))?;
+
+    let mut args = vec!["bridge".to_string(), "--url".to_string(), url.clone()];
+    if legacy_sse {
+        args.push("--sse".to_string());
+    }
+    let mut env = server.env.clone().unwrap_or_default();
+    let mut names: Vec<&String> = headers.keys().collect();
+    names.sort();
+    for (n, name) in names.into_iter().enumerate() {
+        env.insert(format!("{}{}", HEADER_ENV_PREFIX, n + 1), format!("{}: {}", name, headers[name]));
+    }
+
+    let mut value = json!({ "command": exe.to_string_lossy(), "args": args });
+    if !env.is_empty() {
+        value["env"] = json!(env);
+    }
+    Ok(value)
+}
+
+/// Headers a bridged entry passed in its environment, as "Name: value"
+pub fn env_headers() -> Vec<String> {
+    (1..)
+        .map_while(|n| std::env::var(format!("{}{}", HEADER_ENV_PREFIX, n)).ok())
+        .collect()
+}
+
+/// Parse `--header "Name: value"` arguments
+pub fn parse_headers(raw: &[String]) -> Result<HashMap<String, String>, String> {
+    raw.iter()
+        .map(|h| {
+            let (name, value) = h
+                .split_once(':')
+                .ok_or_else(|| format!("invalid header (expected \"Name: value\"): {}", h))?;
+            Ok((name.trim().to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// HTTP/SSE server → stdio
+// ---------------------------------------------------------------------------
+
+/// Relay stdin/stdout to a remote server until stdin closes. Each message is
+/// sent on its own thread, so a slow tool call doesn't hold up the rest.
+pub fn http_to_stdio(url: &str, headers: &HashMap<String, String>, legacy_sse: bool) -> Result<(), String> {
+    let stdout = Arc::new(Mutex::new(std::io::stdout()));
+    let out = stdout.clone();
+    let write = move |msg: Value| {
+        if let Ok(mut out) = out.lock() {
+            let _ = out.write_all(jsonrpc::encode(&msg).as_bytes());
+            let _ = out.flush();
+        }
+    };
+    let remote = Arc::new(if legacy_sse {
+        let on_close = || {
+            eprintln!("mcpm bridge: event stream closed");
+            std::process::exit(1);
+        };
+        RemoteSession::legacy_sse(url, headers, CALL_TIMEOUT, write, on_close).map_err(|e| e.to_string())?
+    } else {
+        RemoteSession::streamable(url, headers, CALL_TIMEOUT, write)
+    });
+
+    let mut stdin = std::io::stdin().lock();
+    let mut decoder = FrameDecoder::default();
+    let mut buf = vec![0u8; 8192];
+    loop {
+        let n = stdin.read(&mut buf).map_err(|e| e.to_string())?;
+        if n == 0 {
+            return Ok(());
+        }
+        decoder.push(&buf[..n]);
+        while let Some(msg) = decoder.next_message() {
+            // initialize must finish before anything else is sent, so the
+            // session id is known; notifications keep their order
+            let inline = msg.get("id").is_none() || msg["method"] == "initialize";
+            let remote = remote.clone();
+            let stdout = stdout.clone();
+            let forward = move || {
+                let Err(e) = remote.send(&msg) else { return };
+                let e = e.to_string();
+                eprintln!("mcpm bridge: {}", e);
+                // A request that never reached the server still gets an answer
+                if let (Some(id), Some(_)) = (msg.get("id"), msg.get("method"))
+                    && let Ok(mut out) = stdout.lock()
+                {
+                    let response = jsonrpc::error_response(id.clone(), -32603, &e);
+                    let _ = out.write_all(jsonrpc::encode(&response).as_bytes());
+                    let _ = out.flush();
+                }
+            };
+            if inline {
+                forward();
+            } else {
+                std::thread::spawn(forward);
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// stdio server → Streamable HTTP
+// ---------------------------------------------------------------------------
+
+/// A stdio server behind an HTTP endpoint. The client's own `initialize` is
+/// passed through, so the server sees the real client's capabilities.
+struct StdioBackend {
+    session: Mutex<Session>,
+}
+
+impl Handler for StdioBackend {
+    fn handle(&self, msg: &Value) -> Reply {
+        let Some(method) = msg["method"].as_str() else {
+            return Reply::None;
+        };
+        let Ok(mut session) = self.session.lock() else {
+            return Reply::Exit;
+        };
+        let Some(id) = msg.get("id").cloned() else {
+            session.notify(method, msg.get("params").cloned().unwrap_or(json!({})));
+            return Reply::None;
+        };
+
+        let params = msg.get("params").cloned().unwrap_or(json!({}));
+        match session.request_message(method, params, CALL_TIMEOUT) {
+            Ok(mut response) => {
+                // The server answered the id the session chose; hand back ours
+                response["id"] = id;
+                Reply::Send(response)
+            }
+            Err(e) => {
+                if !session.is_alive() {
+                    eprintln!("mcpm bridge: server exited");
+                    return Reply::Exit;
+                }
+                Reply::Send(jsonrpc::error_response(id, -32603, &e.to_string()))
+            }
+        }
+    }
+}
+
+/// Serve a stdio server as Streamable HTTP on 127.0.0.1:port
+pub fn stdio_to_http(
+    command: &str,
+    args: &[String],
+    env: &Option<HashMap<String, String>>,
+    port: u16,
+) -> Result<(), String> {
+    let session = Session::spawn(command, args, env).map_err(|e| e.to_string())?;
+    let backend = StdioBackend {
+        session: Mutex::new(session),
+    };
+    endpoint::serve_http(Arc::new(backend), port, |url| {
+        eprintln!("mcpm bridge listening on {}", url);
+    })
+}
@@ -5,7 +5,8 @@ use serde_json::{json, Map, Value};
 
 use std::path::PathBuf;
 
-use crate::types::ClientKind;
+use crate::types::{ClientKind, McpServer, Transport};
+use crate::vault::{self, Vault};
 
 /// Build a stdio server JSON value from wizard inputs
 pub fn build_server_value(
@@ -40,14 +41,14 @@ pub fn build_http_server_value(
     let mut obj = Map::new();
     obj.insert("type".to_string(), Value::String("http".to_string()));
     obj.insert("url".to_string(), Value::String(url.to_string()));
-    if let Some(h) = headers {
-        if !h.is_empty() {
-            let hdr_obj: Map<String, Value> = h
-                .iter()
-                .map(|(k, v)| (k.clone(), Value::String(v.clone())))
-                .collect();
-            obj.insert("headers".to_string(), Value::Object(hdr_obj));
-        }
+    if let Some(h) = headers
+        && !h.is_empty()
+    {
+        let hdr_obj: Map<String, Value> = h
+            .iter()
+            .map(|(k, v)| (k.clone(), Value::String(v.clone())))
+            .collect();
+        obj.insert("headers".to_string(), Value::Object(hdr_obj));
     }
     if !env.is_empty() {
         let env_obj: Map<String, Value> = env
@@ -77,6 +78,17 @@ pub fn build_sse_server_value(
     Value::Object(obj)
 }
 
+/// A discovered server's definition as it would be written to a config
+pub fn server_value(server: &McpServer) -> Value {
+    let env = server.env.clone().unwrap_or_default();
+    match &server.transport {
+        Transport::Stdio { command, args } => build_server_value(command, args, &env),
+        Transport::Http { url, headers } => build_http_server_value(url, headers.as_ref(), &env),
+        Transport::Sse { url } => build_sse_server_value(url, &env),
+        Transport::Unknown => json!({}),
+    }
+}
+
 /// Add a server to a client's config file
 pub fn add_server(
     client: &ClientKind,
@@ -87,6 +99,8 @@ pub fn add_server(
     let path = client
         .config_path(cwd)
         .ok_or("could not determine config path")?;
+    // ${secret:name} placeholders become env references or real values
+    let server_value = &vault::materialize(client, server_value, &Vault::open_from_env())?;
 
     // Create parent dirs
     if let Some(parent) = path.parent() {
@@ -191,6 +205,95 @@ fn remove_server_inner(
     write_atomic(&path, &root)
 }
 
+/// Point an existing server entry at a different command line, keeping its
+/// other fields (env, type, …) as they are
+pub fn set_command(
+    client: &ClientKind,
+    cwd: &Path,
+    name: &str,
+    command: &str,
+    args: &[String],
+) -> Result<(), String> {
+    edit_entry(client, cwd, name, |entry| {
+        entry.insert("command".to_string(), Value::String(command.to_string()));
+        if args.is_empty() {
+            entry.remove("args");
+        } else {
+            entry.insert(
+                "args".to_string(),
+                Value::Array(args.iter().map(|a| Value::String(a.clone())).collect()),
+            );
+        }
+        Ok(())
+    })
+}
+
+/// Set one HTTP header on an existing server entry, e.g. `Authorization`
+/// after an OAuth login
+pub fn set_header(
+    client: &ClientKind,
+    cwd: &Path,
+    name: &str,
+    header: &str,
+    value: &str,
+) -> Result<(), String> {
+    edit_entry(client, cwd, name, |entry| {
+        let headers = entry
+            .entry("headers")
+            .or_insert_with(|| Value::Object(Map::new()));
+        if !headers.is_object() {
+            *headers = Value::Object(Map::new());
+        }
+        headers[header] = Value::String(value.to_string());
+        Ok(())
+    })
+}
+
+/// Resolve the `${secret:name}` placeholders in an existing entry the way
+/// `add_server` would for this client, keeping its other fields
+pub fn materialize_entry(
+    client: &ClientKind,
+    cwd: &Path,
+    name: &str,
+    vault: &Result<Vault, String>,
+) -> Result<(), String> {
+    edit_entry(client, cwd, name, |entry| {
+        if let Value::Object(map) = vault::materialize(client, &Value::Object(entry.clone()), vault)? {
+            *entry = map;
+        }
+        Ok(())
+    })
+}
+
+/// Change one server entry in place, then back up and write the file
+fn edit_entry(
+    client: &ClientKind,
+    cwd: &Path,
+    name: &str,
+    edit: impl FnOnce(&mut Map<String, Value>) -> Result<(), String>,
+) -> Result<(), String> {
+    let path = client
+        .config_path(cwd)
+        .ok_or("could not determine config path")?;
+
+    let mut root = read_or_empty(&path)?;
+
+    let entry = match client {
+        ClientKind::ClaudeCodeProject if root.get("mcpServers").is_none() => root.get_mut(name),
+        ClientKind::ClaudeCodeProject | ClientKind::ClaudeCodeGlobal => {
+            root.get_mut("mcpServers").and_then(|s| s.get_mut(name))
+        }
+        _ => root.get_mut(client.servers_key()).and_then(|s| s.get_mut(name)),
+    };
+    let Some(entry) = entry.and_then(Value::as_object_mut) else {
+        return Err(format!("no server \"{}\" in {}", name, path.display()));
+    };
+    edit(entry)?;
+
+    backup(&path)?;
+    write_atomic(&path, &root)
+}
+
 /// Restore the most recent backup for a client's config file.
 /// Swaps current ↔ backup so the undo is itself undoable.
 pub fn restore_backup(client: &ClientKind, cwd: &Path) -> Result<(), String> {
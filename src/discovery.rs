@@ -5,25 +5,33 @@ use serde_json::Value;
 
 use crate::types::*;
 
-/// Scan all known MCP config locations and return discovered servers
+/// Scan all known MCP config locations and return discovered servers.
 /// For CC-Global, also scans top-level mcpServers and deduplicates by name.
-
-/// Scan all known MCP config locations and return discovered servers
 pub fn discover(cwd: &Path) -> DiscoveryResult {
     let mut result = DiscoveryResult::default();
 
-    scan_claude_code_global(&mut result);
-    scan_claude_code_plugins(&mut result);
-    scan_mcp_json(cwd, &mut result);
-    scan_wrapped(home(".cursor/mcp.json"), ClientKind::CursorGlobal, &mut result);
-    scan_wrapped(cwd.join(".cursor/mcp.json"), ClientKind::CursorProject, &mut result);
-    scan_vscode(cwd, &mut result);
-    scan_wrapped(
-        home(".codeium/windsurf/mcp_config.json"),
-        ClientKind::Windsurf,
-        &mut result,
-    );
-    scan_claude_desktop(&mut result);
+    for source in config_sources(cwd) {
+        let src = source.path.to_string_lossy().into_owned();
+        let root = match &source.root {
+            Ok(root) => root,
+            Err(e) => {
+                result.errors.push(format!("{}: {}", src, e));
+                continue;
+            }
+        };
+        // ~/.claude.json repeats servers across projects; first one wins
+        let mut seen: HashSet<String> = HashSet::new();
+        for pointer in &source.maps {
+            let Some(map) = root.pointer(pointer).and_then(Value::as_object) else {
+                continue;
+            };
+            for server in parse_server_map(map, source.client.clone(), &src) {
+                if seen.insert(server.name.clone()) {
+                    result.servers.push(server);
+                }
+            }
+        }
+    }
 
     // Build active_clients: only clients that contributed at least one server
     let seen: HashSet<ClientKind> = result.servers.iter().map(|s| s.client.clone()).collect();
@@ -36,6 +44,36 @@ pub fn discover(cwd: &Path) -> DiscoveryResult {
     result
 }
 
+/// A config file mcpm reads servers from
+pub struct ConfigSource {
+    pub client: ClientKind,
+    pub path: PathBuf,
+    pub text: String,
+    pub root: Result<Value, serde_json::Error>,
+    /// JSON pointers to the objects that map server names to entries
+    pub maps: Vec<String>,
+}
+
+/// Every config file that exists, in discovery order
+pub fn config_sources(cwd: &Path) -> Vec<ConfigSource> {
+    let mut sources = Vec::new();
+
+    scan_claude_code_global(&mut sources);
+    scan_claude_code_plugins(&mut sources);
+    scan_mcp_json(cwd, &mut sources);
+    scan_wrapped(home(".cursor/mcp.json"), ClientKind::CursorGlobal, &mut sources);
+    scan_wrapped(cwd.join(".cursor/mcp.json"), ClientKind::CursorProject, &mut sources);
+    scan_vscode(cwd, &mut sources);
+    scan_wrapped(
+        home(".codeium/windsurf/mcp_config.json"),
+        ClientKind::Windsurf,
+        &mut sources,
+    );
+    scan_claude_desktop(&mut sources);
+
+    sources
+}
+
 // ---------------------------------------------------------------------------
 // Helpers
 // ---------------------------------------------------------------------------
@@ -46,19 +84,22 @@ fn home(rel: &str) -> PathBuf {
         .join(rel)
 }
 
-fn read_json_with_errors(path: &Path, errors: &mut Vec<String>) -> Option<(Value, String)> {
-    let text = match std::fs::read_to_string(path) {
-        Ok(t) => t,
-        Err(_) => return None, // file absent — silent
-    };
-    let src = path.to_string_lossy().into_owned();
-    match serde_json::from_str(&text) {
-        Ok(val) => Some((val, src)),
-        Err(e) => {
-            errors.push(format!("{}: {}", src, e));
-            None
-        }
-    }
+/// Read a config file; an absent file is silently skipped. Unparseable
+/// files are kept so the parse error can be reported.
+fn read_source(path: &Path, client: ClientKind) -> Option<ConfigSource> {
+    let text = std::fs::read_to_string(path).ok()?;
+    Some(ConfigSource {
+        client,
+        path: path.to_path_buf(),
+        root: serde_json::from_str(&text),
+        text,
+        maps: Vec::new(),
+    })
+}
+
+/// Escape one reference token of a JSON pointer (RFC 6901)
+pub fn pointer_token(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
 }
 
 fn parse_transport(obj: &Value) -> Transport {
@@ -119,6 +160,8 @@ fn parse_server_map(
             env: parse_string_map(obj.get("env")),
             health: HealthStatus::Unchecked,
             last_checked: None,
+            cleanup: None,
+            metrics: HealthMetrics::default(),
         })
         .collect()
 }
@@ -128,39 +171,32 @@ fn parse_server_map(
 // ---------------------------------------------------------------------------
 
 /// ~/.claude.json → top-level mcpServers + projects["<path>"].mcpServers (deduplicated)
-fn scan_claude_code_global(result: &mut DiscoveryResult) {
-    let path = home(".claude.json");
-    let Some((root, src)) = read_json_with_errors(&path, &mut result.errors) else {
+fn scan_claude_code_global(sources: &mut Vec<ConfigSource>) {
+    let Some(mut source) = read_source(&home(".claude.json"), ClientKind::ClaudeCodeGlobal) else {
         return;
     };
-
-    let mut seen: HashSet<String> = HashSet::new();
-
-    // Top-level mcpServers (global servers)
-    if let Some(mcp) = root["mcpServers"].as_object() {
-        for server in parse_server_map(mcp, ClientKind::ClaudeCodeGlobal, &src) {
-            seen.insert(server.name.clone());
-            result.servers.push(server);
+    if let Ok(root) = &source.root {
+        // Top-level mcpServers (global servers)
+        if root["mcpServers"].is_object() {
+            source.maps.push("/mcpServers".to_string());
         }
-    }
-
-    // Per-project mcpServers (deduplicate by name)
-    if let Some(projects) = root["projects"].as_object() {
-        for (_project_path, project_val) in projects {
-            if let Some(mcp) = project_val["mcpServers"].as_object() {
-                for server in parse_server_map(mcp, ClientKind::ClaudeCodeGlobal, &src) {
-                    if seen.insert(server.name.clone()) {
-                        result.servers.push(server);
-                    }
+        // Per-project mcpServers
+        if let Some(projects) = root["projects"].as_object() {
+            for (project_path, project_val) in projects {
+                if project_val["mcpServers"].is_object() {
+                    source
+                        .maps
+                        .push(format!("/projects/{}/mcpServers", pointer_token(project_path)));
                 }
             }
         }
     }
+    sources.push(source);
 }
 
 /// ~/.claude/plugins/marketplaces/*/external_plugins/*/.mcp.json
 /// Only scans plugins that are actually installed (have a matching plugins/ entry).
-fn scan_claude_code_plugins(result: &mut DiscoveryResult) {
+fn scan_claude_code_plugins(sources: &mut Vec<ConfigSource>) {
     let plugins_dir = home(".claude/plugins/marketplaces");
     let Ok(marketplaces) = std::fs::read_dir(&plugins_dir) else {
         return;
@@ -178,71 +214,62 @@ fn scan_claude_code_plugins(result: &mut DiscoveryResult) {
                 continue;
             }
             let mcp_json = plugin.path().join(".mcp.json");
-            let Some((root, src)) = read_json_with_errors(&mcp_json, &mut result.errors) else {
+            let Some(mut source) = read_source(&mcp_json, ClientKind::ClaudeCodePlugin) else {
                 continue;
             };
-            if let Some(obj) = root.as_object() {
-                result
-                    .servers
-                    .extend(parse_server_map(obj, ClientKind::ClaudeCodePlugin, &src));
+            if source.root.as_ref().is_ok_and(Value::is_object) {
+                source.maps.push(String::new());
             }
+            sources.push(source);
         }
     }
 }
 
 /// ./.mcp.json — supports both flat (top-level server keys) and wrapped (mcpServers key)
-fn scan_mcp_json(cwd: &Path, result: &mut DiscoveryResult) {
-    let path = cwd.join(".mcp.json");
-    let Some((root, src)) = read_json_with_errors(&path, &mut result.errors) else {
+fn scan_mcp_json(cwd: &Path, sources: &mut Vec<ConfigSource>) {
+    let Some(mut source) = read_source(&cwd.join(".mcp.json"), ClientKind::ClaudeCodeProject) else {
         return;
     };
-
-    // Try wrapped first
-    if let Some(mcp) = root["mcpServers"].as_object() {
-        result
-            .servers
-            .extend(parse_server_map(mcp, ClientKind::ClaudeCodeProject, &src));
-    } else if let Some(obj) = root.as_object() {
-        // Flat: every top-level key that has an object value is a server
-        result
-            .servers
-            .extend(parse_server_map(obj, ClientKind::ClaudeCodeProject, &src));
+    if let Ok(root) = &source.root {
+        // Try wrapped first
+        if root["mcpServers"].is_object() {
+            source.maps.push("/mcpServers".to_string());
+        } else if root.is_object() {
+            // Flat: every top-level key that has an object value is a server
+            source.maps.push(String::new());
+        }
     }
+    sources.push(source);
 }
 
 /// Generic scanner for configs that use { "mcpServers": { ... } }
-fn scan_wrapped(path: PathBuf, client: ClientKind, result: &mut DiscoveryResult) {
-    let Some((root, src)) = read_json_with_errors(&path, &mut result.errors) else {
+fn scan_wrapped(path: PathBuf, client: ClientKind, sources: &mut Vec<ConfigSource>) {
+    let Some(mut source) = read_source(&path, client) else {
         return;
     };
-
-    if let Some(mcp) = root["mcpServers"].as_object() {
-        result
-            .servers
-            .extend(parse_server_map(mcp, client, &src));
+    if source.root.as_ref().is_ok_and(|root| root["mcpServers"].is_object()) {
+        source.maps.push("/mcpServers".to_string());
     }
+    sources.push(source);
 }
 
 /// VS Code uses "servers" key (not "mcpServers"), also check "mcpServers" as fallback
-fn scan_vscode(cwd: &Path, result: &mut DiscoveryResult) {
-    let path = cwd.join(".vscode/mcp.json");
-    let Some((root, src)) = read_json_with_errors(&path, &mut result.errors) else {
+fn scan_vscode(cwd: &Path, sources: &mut Vec<ConfigSource>) {
+    let Some(mut source) = read_source(&cwd.join(".vscode/mcp.json"), ClientKind::VsCodeProject) else {
         return;
     };
-
-    let map = root["servers"]
-        .as_object()
-        .or_else(|| root["mcpServers"].as_object());
-
-    if let Some(mcp) = map {
-        result
-            .servers
-            .extend(parse_server_map(mcp, ClientKind::VsCodeProject, &src));
+    if let Ok(root) = &source.root {
+        if root["servers"].is_object() {
+            source.maps.push("/servers".to_string());
+        } else if root["mcpServers"].is_object() {
+            source.maps.push("/mcpServers".to_string());
+        }
     }
+    sources.push(source);
 }
 
 /// Claude Desktop — try platform-specific paths: Windows (APPDATA + MSIX), macOS, Linux
-fn scan_claude_desktop(result: &mut DiscoveryResult) {
+fn scan_claude_desktop(sources: &mut Vec<ConfigSource>) {
     let mut candidates = Vec::new();
 
     // Windows: %APPDATA%\Claude\claude_desktop_config.json
@@ -263,7 +290,7 @@ fn scan_claude_desktop(result: &mut DiscoveryResult) {
 
     for path in &candidates {
         if path.exists() {
-            scan_wrapped(path.clone(), ClientKind::ClaudeDesktop, result);
+            scan_wrapped(path.clone(), ClientKind::ClaudeDesktop, sources);
             return;
         }
     }
@@ -0,0 +1,300 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde_json::Value;
+
+use crate::jsonrpc::{self, FrameDecoder};
+
+/// What the transport should do with an incoming message
+pub enum Reply {
+    Send(Value),
+    /// Notification or a response to us — nothing to send
+    None,
+    /// Never answer (mock-server failure injection)
+    Hang,
+    /// Write these bytes instead of a frame, then exit (mock-server failure
+    /// injection)
+    Malformed(&'static str),
+    /// Exit without answering
+    Exit,
+}
+
+/// The server side of an MCP endpoint, shared by mcpm's own servers
+pub trait Handler: Send + Sync + 'static {
+    fn handle(&self, msg: &Value) -> Reply;
+
+    /// Id to hand out in `Mcp-Session-Id` when serving HTTP
+    fn session_id(&self) -> &str {
+        "mcpm-session"
+    }
+
+    /// Answer an HTTP request before it is taken as JSON-RPC, e.g. to
+    /// require a token or serve OAuth endpoints (mock-server `--oauth`)
+    fn intercept(&self, _request: &HttpRequest) -> Option<HttpResponse> {
+        None
+    }
+}
+
+fn hang_forever() -> ! {
+    loop {
+        std::thread::sleep(Duration::from_secs(3600));
+    }
+}
+
+// ---------------------------------------------------------------------------
+// stdio
+// ---------------------------------------------------------------------------
+
+/// Serve over stdin/stdout until stdin closes. Each request is handled on
+/// its own thread, so a slow one doesn't hold up the rest.
+pub fn serve_stdio<H: Handler>(handler: Arc<H>) -> Result<(), String> {
+    let mut stdin = std::io::stdin().lock();
+    let stdout = Arc::new(Mutex::new(std::io::stdout()));
+    let mut decoder = FrameDecoder::default();
+    let mut buf = vec![0u8; 8192];
+
+    loop {
+        let n = stdin.read(&mut buf).map_err(|e| e.to_string())?;
+        if n == 0 {
+            return Ok(());
+        }
+        decoder.push(&buf[..n]);
+        while let Some(msg) = decoder.next_message() {
+            // Notifications are handled in order; `initialized` must not
+            // overtake the `initialize` it follows
+            let inline = msg.get("id").is_none() || msg["method"] == "initialize";
+            let handler = handler.clone();
+            let stdout = stdout.clone();
+            let respond = move || {
+                let bytes = match handler.handle(&msg) {
+                    Reply::Send(response) => jsonrpc::encode(&response),
+                    Reply::None => return,
+                    Reply::Hang => hang_forever(),
+                    Reply::Malformed(bytes) => {
+                        if let Ok(mut out) = stdout.lock() {
+                            let _ = out.write_all(bytes.as_bytes());
+                            let _ = out.flush();
+                        }
+                        std::process::exit(1);
+                    }
+                    Reply::Exit => std::process::exit(1),
+                };
+                if let Ok(mut out) = stdout.lock() {
+                    let _ = out.write_all(bytes.as_bytes());
+                    let _ = out.flush();
+                }
+            };
+            if inline {
+                respond();
+            } else {
+                std::thread::spawn(respond);
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Streamable HTTP
+// ---------------------------------------------------------------------------
+
+/// Serve Streamable HTTP on 127.0.0.1:port. Every POST gets a plain JSON
+/// response; there is no SSE stream, so GET is refused as the spec allows.
+/// `on_listen` is told the bound address (useful with port 0).
+pub fn serve_http<H: Handler>(
+    handler: Arc<H>,
+    port: u16,
+    on_listen: impl FnOnce(&str),
+) -> Result<(), String> {
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .map_err(|e| format!("failed to listen on 127.0.0.1:{}: {}", port, e))?;
+    let addr = listener.local_addr().map_err(|e| e.to_string())?;
+    on_listen(&format!("http://{}/mcp", addr));
+
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        let handler = handler.clone();
+        std::thread::spawn(move || {
+            let _ = serve_connection(handler.as_ref(), stream, addr.port());
+        });
+    }
+    Ok(())
+}
+
+/// Names the listening address goes by
+const LOCAL_HOSTS: &[&str] = &["127.0.0.1", "localhost"];
+
+/// Refuse what a web page could send through DNS rebinding: the Host must
+/// name the address we listen on, and an Origin, if any, must be local
+fn is_local_request(request: &HttpRequest, port: u16) -> bool {
+    let host_ok = request.header("Host").is_some_and(|host| {
+        LOCAL_HOSTS
+            .iter()
+            .any(|local| host.eq_ignore_ascii_case(&format!("{}:{}", local, port)))
+    });
+    let origin_ok = request.header("Origin").is_none_or(|origin| {
+        let authority = origin
+            .strip_prefix("http://")
+            .or_else(|| origin.strip_prefix("https://"))
+            .unwrap_or("");
+        let host = match authority.rsplit_once(':') {
+            Some((host, port)) if port.bytes().all(|b| b.is_ascii_digit()) => host,
+            _ => authority,
+        };
+        host == "[::1]" || LOCAL_HOSTS.iter().any(|local| host.eq_ignore_ascii_case(local))
+    });
+    host_ok && origin_ok
+}
+
+fn serve_connection(handler: &impl Handler, stream: TcpStream, port: u16) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut stream = stream;
+    loop {
+        let Some(request) = read_http_request(&mut reader)? else {
+            return Ok(());
+        };
+        if request.too_large {
+            // The body is left unread, so the connection can't be reused
+            return write_http(&mut stream, "413 Content Too Large", &[], "request body too large");
+        }
+        if !is_local_request(&request, port) {
+            return write_http(&mut stream, "403 Forbidden", &[], "only local clients may connect");
+        }
+        if let Some(response) = handler.intercept(&request) {
+            let headers: Vec<(&str, &str)> = response.headers.iter().map(|(k, v)| (*k, v.as_str())).collect();
+            write_http(&mut stream, response.status, &headers, &response.body)?;
+            continue;
+        }
+        match request.method.as_str() {
+            "POST" => {}
+            "DELETE" => {
+                write_http(&mut stream, "200 OK", &[], "")?;
+                continue;
+            }
+            _ => {
+                write_http(&mut stream, "405 Method Not Allowed", &[("Allow", "POST, DELETE")], "")?;
+                continue;
+            }
+        }
+        let Ok(msg) = serde_json::from_slice::<Value>(&request.body) else {
+            let body = jsonrpc::error_response(Value::Null, -32700, "parse error").to_string();
+            write_http(&mut stream, "400 Bad Request", &[("Content-Type", "application/json")], &body)?;
+            continue;
+        };
+        let is_initialize = msg["method"] == "initialize";
+        match handler.handle(&msg) {
+            Reply::Send(response) => {
+                let mut headers = vec![("Content-Type", "application/json")];
+                if is_initialize {
+                    headers.push(("Mcp-Session-Id", handler.session_id()));
+                }
+                write_http(&mut stream, "200 OK", &headers, &response.to_string())?;
+            }
+            Reply::None => write_http(&mut stream, "202 Accepted", &[], "")?,
+            Reply::Hang => hang_forever(),
+            Reply::Malformed(bytes) => {
+                write_http(&mut stream, "200 OK", &[("Content-Type", "application/json")], bytes)?;
+            }
+            Reply::Exit => std::process::exit(1),
+        }
+    }
+}
+
+pub struct HttpRequest {
+    pub method: String,
+    /// Path and query, as sent
+    pub target: String,
+    headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    /// Content-Length over MAX_BODY; the body was not read
+    too_large: bool,
+}
+
+impl HttpRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn path(&self) -> &str {
+        self.target.split('?').next().unwrap_or("")
+    }
+
+    pub fn query(&self) -> &str {
+        self.target.split_once('?').map(|(_, q)| q).unwrap_or("")
+    }
+}
+
+pub struct HttpResponse {
+    pub status: &'static str,
+    pub headers: Vec<(&'static str, String)>,
+    pub body: String,
+}
+
+impl HttpResponse {
+    pub fn json(status: &'static str, body: &Value) -> HttpResponse {
+        HttpResponse {
+            status,
+            headers: vec![("Content-Type", "application/json".to_string())],
+            body: body.to_string(),
+        }
+    }
+}
+
+/// Largest request body accepted; JSON-RPC messages are far smaller
+const MAX_BODY: usize = 16 * 1024 * 1024;
+
+fn read_http_request(reader: &mut impl BufRead) -> std::io::Result<Option<HttpRequest>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    let mut parts = line.split_whitespace();
+    let method = parts.next().unwrap_or("").to_string();
+    let target = parts.next().unwrap_or("/").to_string();
+    let mut headers = Vec::new();
+    let mut content_length = 0;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    let too_large = content_length > MAX_BODY;
+    let mut body = Vec::new();
+    if !too_large {
+        body.resize(content_length, 0);
+        reader.read_exact(&mut body)?;
+    }
+    Ok(Some(HttpRequest {
+        method,
+        target,
+        headers,
+        body,
+        too_large,
+    }))
+}
+
+fn write_http(stream: &mut TcpStream, status: &str, headers: &[(&str, &str)], body: &str) -> std::io::Result<()> {
+    let mut out = format!("HTTP/1.1 {}\r\nContent-Length: {}\r\n", status, body.len());
+    for (name, value) in headers {
+        out.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    out.push_str("\r\n");
+    out.push_str(body);
+    stream.write_all(out.as_bytes())?;
+    stream.flush()
+}
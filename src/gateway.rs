@@ -0,0 +1,380 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde_json::{Value, json};
+
+use crate::endpoint::{Handler, Reply};
+use crate::health::{CALL_TIMEOUT, PROTOCOL_VERSION, Session, SessionError, TIMEOUT};
+use crate::jsonrpc;
+use crate::types::{McpServer, Transport};
+
+/// Joins a server's prefix to the names of its tools and prompts
+pub const SEPARATOR: &str = "__";
+
+/// Why a forwarded request failed
+enum Failure {
+    /// The backend's JSON-RPC error object, passed on as it is
+    Backend(Value),
+    /// The gateway's own: routing, or a session that couldn't be reached
+    Gateway(i64, String),
+}
+
+impl Failure {
+    fn is_not_found(&self) -> bool {
+        match self {
+            Failure::Backend(error) => error["code"].as_i64() == Some(RESOURCE_NOT_FOUND),
+            Failure::Gateway(code, _) => *code == RESOURCE_NOT_FOUND,
+        }
+    }
+}
+
+/// MCP's error code for an unknown resource URI
+const RESOURCE_NOT_FOUND: i64 = -32002;
+
+/// One aggregated server and its live session
+struct Backend {
+    name: String,
+    prefix: String,
+    command: String,
+    args: Vec<String>,
+    env: Option<HashMap<String, String>>,
+    session: Mutex<Option<Session>>,
+    capabilities: Value,
+    /// Resource URIs and template prefixes seen in its lists, for routing reads
+    uris: Mutex<Vec<String>>,
+    template_prefixes: Mutex<Vec<String>>,
+}
+
+impl Backend {
+    fn start(server: &McpServer) -> Result<Backend, String> {
+        let Transport::Stdio { command, args } = &server.transport else {
+            return Err("only stdio servers can be aggregated".to_string());
+        };
+        let mut session = Session::spawn(command, args, &server.env).map_err(|e| e.to_string())?;
+        let init = match session.initialize() {
+            Ok(init) => init,
+            Err(e) => {
+                session.close();
+                return Err(e.to_string());
+            }
+        };
+        Ok(Backend {
+            name: server.name.clone(),
+            prefix: prefix_for(&server.name),
+            command: command.clone(),
+            args: args.clone(),
+            env: server.env.clone(),
+            session: Mutex::new(Some(session)),
+            capabilities: init["capabilities"].clone(),
+            uris: Mutex::new(Vec::new()),
+            template_prefixes: Mutex::new(Vec::new()),
+        })
+    }
+
+    fn has(&self, capability: &str) -> bool {
+        self.capabilities.get(capability).is_some()
+    }
+
+    /// Run `f` against the live session, restarting the server first if it
+    /// has exited since the last request
+    fn with_session<T>(
+        &self,
+        f: impl FnOnce(&mut Session) -> Result<T, SessionError>,
+    ) -> Result<T, String> {
+        let mut guard = self.session.lock().map_err(|_| "session lock poisoned".to_string())?;
+        if !guard.as_mut().is_some_and(Session::is_alive) {
+            if let Some(dead) = guard.take() {
+                dead.close();
+            }
+            eprintln!("mcpm serve: restarting {}", self.name);
+            let mut session =
+                Session::spawn(&self.command, &self.args, &self.env).map_err(|e| e.to_string())?;
+            if let Err(e) = session.initialize() {
+                session.close();
+                return Err(e.to_string());
+            }
+            *guard = Some(session);
+        }
+        let session = guard.as_mut().expect("session started above");
+        f(session).map_err(|e| e.to_string())
+    }
+
+    /// Send a request; the server's own error comes back untouched, and
+    /// only a failed session becomes an internal error
+    fn request(&self, method: &str, params: Value, timeout: Duration) -> Result<Value, Failure> {
+        let msg = self
+            .with_session(|s| s.request_message(method, params, timeout))
+            .map_err(|e| Failure::Gateway(-32603, format!("{}: {}", self.name, e)))?;
+        match msg.get("error") {
+            Some(error) => Err(Failure::Backend(error.clone())),
+            None => Ok(msg.get("result").cloned().unwrap_or(Value::Null)),
+        }
+    }
+
+    fn list(&self, method: &str, key: &str) -> Result<Vec<Value>, String> {
+        self.with_session(|s| s.list_all(method, key))
+    }
+}
+
+/// Characters clients accept in tool names; anything else becomes `_`
+fn prefix_for(server_name: &str) -> String {
+    server_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+/// Give backends whose names sanitise to the same prefix (`my.server` and
+/// `my_server`) a numeric suffix, so each tool name routes to one server
+fn disambiguate(backends: &mut [Backend]) {
+    let mut taken: HashSet<String> = backends.iter().map(|b| b.prefix.clone()).collect();
+    let mut seen: HashSet<String> = HashSet::new();
+    for backend in backends.iter_mut() {
+        if seen.insert(backend.prefix.clone()) {
+            continue;
+        }
+        let prefix = (2..)
+            .map(|n| format!("{}_{}", backend.prefix, n))
+            .find(|p| !taken.contains(p))
+            .unwrap_or_default();
+        eprintln!(
+            "mcpm serve: {} has the same prefix as another server; its tools are named {}{}<name>",
+            backend.name, prefix, SEPARATOR
+        );
+        taken.insert(prefix.clone());
+        backend.prefix = prefix;
+    }
+}
+
+/// A single MCP server in front of several configured ones. Tools and
+/// prompts are renamed `<server>__<name>`; resources keep their URIs and are
+/// routed by the lists they came from.
+pub struct Gateway {
+    backends: Vec<Backend>,
+}
+
+impl Gateway {
+    /// Start every server in parallel. `on_start` hears how each one went;
+    /// the ones that fail to start are left out.
+    pub fn start(servers: &[&McpServer], mut on_start: impl FnMut(&str, Result<(), &str>)) -> Gateway {
+        let results: Vec<Result<Backend, String>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = servers
+                .iter()
+                .map(|server| scope.spawn(|| Backend::start(server)))
+                .collect();
+            handles
+                .into_iter()
+                .map(|h| h.join().unwrap_or_else(|_| Err("startup panicked".to_string())))
+                .collect()
+        });
+
+        let mut backends = Vec::new();
+        for (server, result) in servers.iter().zip(results) {
+            match result {
+                Ok(backend) => {
+                    on_start(&server.name, Ok(()));
+                    backends.push(backend);
+                }
+                Err(e) => on_start(&server.name, Err(&e)),
+            }
+        }
+        disambiguate(&mut backends);
+        Gateway { backends }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.backends.is_empty()
+    }
+
+    /// Shut every server down
+    pub fn close(&self) {
+        for backend in &self.backends {
+            if let Some(session) = backend.session.lock().ok().and_then(|mut s| s.take()) {
+                session.close();
+            }
+        }
+    }
+
+    fn capabilities(&self) -> Value {
+        let mut caps = json!({});
+        for capability in ["tools", "resources", "prompts"] {
+            if self.backends.iter().any(|b| b.has(capability)) {
+                caps[capability] = json!({});
+            }
+        }
+        caps
+    }
+
+    /// The backend a prefixed name belongs to, and the name it knows it by
+    fn route<'a>(&self, qualified: &'a str) -> Option<(&Backend, &'a str)> {
+        self.backends
+            .iter()
+            .filter_map(|b| {
+                let rest = qualified.strip_prefix(&b.prefix)?.strip_prefix(SEPARATOR)?;
+                Some((b, rest))
+            })
+            .max_by_key(|(b, _)| b.prefix.len())
+    }
+
+    /// Merge one list method across backends, renaming items by prefix.
+    /// A backend whose list fails is skipped rather than failing the lot.
+    fn merged_list(&self, capability: &str, method: &str, key: &str, rename: bool) -> Vec<(usize, Value)> {
+        let mut merged = Vec::new();
+        for (i, backend) in self.backends.iter().enumerate() {
+            if !backend.has(capability) {
+                continue;
+            }
+            match backend.list(method, key) {
+                Ok(items) => {
+                    for mut item in items {
+                        if rename && let Some(name) = item["name"].as_str() {
+                            item["name"] = json!(format!("{}{}{}", backend.prefix, SEPARATOR, name));
+                        }
+                        merged.push((i, item));
+                    }
+                }
+                Err(e) => eprintln!("mcpm serve: {} {}: {}", backend.name, method, e),
+            }
+        }
+        merged
+    }
+
+    fn list_resources(&self) -> Value {
+        let merged = self.merged_list("resources", "resources/list", "resources", false);
+        for backend in &self.backends {
+            if let Ok(mut uris) = backend.uris.lock() {
+                uris.clear();
+            }
+        }
+        for (i, item) in &merged {
+            if let (Some(uri), Ok(mut uris)) = (item["uri"].as_str(), self.backends[*i].uris.lock()) {
+                uris.push(uri.to_string());
+            }
+        }
+        json!({ "resources": merged.into_iter().map(|(_, item)| item).collect::<Vec<_>>() })
+    }
+
+    fn list_templates(&self) -> Value {
+        let merged = self.merged_list("resources", "resources/templates/list", "resourceTemplates", false);
+        for backend in &self.backends {
+            if let Ok(mut prefixes) = backend.template_prefixes.lock() {
+                prefixes.clear();
+            }
+        }
+        for (i, item) in &merged {
+            let Some(template) = item["uriTemplate"].as_str() else { continue };
+            let fixed = template.split('{').next().unwrap_or("");
+            if let Ok(mut prefixes) = self.backends[*i].template_prefixes.lock() {
+                prefixes.push(fixed.to_string());
+            }
+        }
+        json!({ "resourceTemplates": merged.into_iter().map(|(_, item)| item).collect::<Vec<_>>() })
+    }
+
+    /// The backend a resource URI came from: an exact match from its list,
+    /// else the longest matching template prefix
+    fn route_uri(&self, uri: &str) -> Option<&Backend> {
+        let listed = self
+            .backends
+            .iter()
+            .find(|b| b.uris.lock().is_ok_and(|uris| uris.iter().any(|u| u == uri)));
+        if listed.is_some() {
+            return listed;
+        }
+        self.backends
+            .iter()
+            .filter_map(|b| {
+                let longest = b
+                    .template_prefixes
+                    .lock()
+                    .ok()?
+                    .iter()
+                    .filter(|p| uri.starts_with(p.as_str()))
+                    .map(String::len)
+                    .max()?;
+                Some((b, longest))
+            })
+            .max_by_key(|(_, len)| *len)
+            .map(|(b, _)| b)
+    }
+
+    /// Read a resource from the backend it belongs to. URIs no list has
+    /// mentioned yet are tried on every backend with resources.
+    fn read_resource(&self, params: &Value) -> Result<Value, Failure> {
+        let uri = params["uri"].as_str().unwrap_or("");
+        if let Some(backend) = self.route_uri(uri) {
+            return backend.request("resources/read", params.clone(), TIMEOUT);
+        }
+        // Report the first backend that failed for another reason than not
+        // knowing the URI
+        let mut failure = Failure::Gateway(RESOURCE_NOT_FOUND, format!("resource not found: {}", uri));
+        for backend in self.backends.iter().filter(|b| b.has("resources")) {
+            match backend.request("resources/read", params.clone(), TIMEOUT) {
+                Ok(result) => return Ok(result),
+                Err(e) if failure.is_not_found() && !e.is_not_found() => failure = e,
+                Err(_) => {}
+            }
+        }
+        Err(failure)
+    }
+
+    /// Forward a call addressed by prefixed name
+    fn forward_named(&self, method: &str, params: &Value, kind: &str, timeout: Duration) -> Result<Value, Failure> {
+        let qualified = params["name"].as_str().unwrap_or("");
+        let (backend, name) = self
+            .route(qualified)
+            .ok_or_else(|| Failure::Gateway(-32602, format!("unknown {}: {}", kind, qualified)))?;
+        let mut params = params.clone();
+        params["name"] = json!(name);
+        backend.request(method, params, timeout)
+    }
+}
+
+impl Handler for Gateway {
+    fn handle(&self, msg: &Value) -> Reply {
+        let Some(method) = msg["method"].as_str() else {
+            return Reply::None;
+        };
+        let Some(id) = msg.get("id").cloned() else {
+            return Reply::None;
+        };
+
+        let params = &msg["params"];
+        let result = match method {
+            "initialize" => {
+                let names: Vec<&str> = self.backends.iter().map(|b| b.name.as_str()).collect();
+                Ok(json!({
+                    "protocolVersion": params["protocolVersion"].as_str().unwrap_or(PROTOCOL_VERSION),
+                    "capabilities": self.capabilities(),
+                    "serverInfo": { "name": "mcpm", "version": env!("CARGO_PKG_VERSION") },
+                    "instructions": format!(
+                        "Tools and prompts from {} are named <server>{}<name>.",
+                        names.join(", "),
+                        SEPARATOR
+                    ),
+                }))
+            }
+            "ping" => Ok(json!({})),
+            "tools/list" => {
+                let tools = self.merged_list("tools", "tools/list", "tools", true);
+                Ok(json!({ "tools": tools.into_iter().map(|(_, t)| t).collect::<Vec<_>>() }))
+            }
+            "tools/call" => self.forward_named(method, params, "tool", CALL_TIMEOUT),
+            "prompts/list" => {
+                let prompts = self.merged_list("prompts", "prompts/list", "prompts", true);
+                Ok(json!({ "prompts": prompts.into_iter().map(|(_, p)| p).collect::<Vec<_>>() }))
+            }
+            "prompts/get" => self.forward_named(method, params, "prompt", TIMEOUT),
+            "resources/list" => Ok(self.list_resources()),
+            "resources/templates/list" => Ok(self.list_templates()),
+            "resources/read" => self.read_resource(params),
+            _ => Err(Failure::Gateway(-32601, format!("method not found: {}", method))),
+        };
+        Reply::Send(match result {
+            Ok(result) => jsonrpc::response(id, result),
+            Err(Failure::Backend(error)) => json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+            Err(Failure::Gateway(code, message)) => jsonrpc::error_response(id, code, &message),
+        })
+    }
+}
@@ -1,30 +1,56 @@
 use std::collections::HashMap;
 use std::io::{Read, Write};
-use std::process::{Command, Stdio};
+use std::process::{Child, ChildStdin, Command, Stdio};
 use std::sync::mpsc;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
 
-use crate::types::{HealthResult, HealthStatus, McpServer, Transport};
+#[cfg(unix)]
+use std::os::unix::process::CommandExt;
 
-const TIMEOUT: Duration = Duration::from_secs(5);
+use serde_json::{Value, json};
 
-fn initialize_msg() -> String {
-    format!(
-        r#"{{"jsonrpc":"2.0","id":1,"method":"initialize","params":{{"protocolVersion":"2025-11-05","capabilities":{{}},"clientInfo":{{"name":"mcpm","version":"{}"}}}}}}"#,
-        env!("CARGO_PKG_VERSION")
-    )
-}
+use crate::jsonrpc::{self, FrameDecoder};
+use crate::launch::LaunchOptions;
+use crate::oauth;
+#[cfg(target_os = "linux")]
+use crate::procfs;
+use crate::remote::{RemoteClient, RemoteError};
+use crate::sandbox;
+use crate::state;
+use crate::types::{Cleanup, HealthMetrics, HealthResult, HealthStatus, McpServer, Transport};
+
+pub const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Tool calls can legitimately take a while; give them more room than a handshake
+pub const CALL_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How long to wait at each shutdown stage (stdin close, SIGTERM) before escalating
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(1);
+
+pub const PROTOCOL_VERSION: &str = "2025-11-05";
 
-/// Run a health check synchronously. Returns the HealthResult.
-pub fn check_server(index: usize, server: &McpServer) -> HealthResult {
-    let status = match &server.transport {
-        Transport::Stdio { command, args } => check_stdio(command, args, &server.env),
-        _ => HealthStatus::Error("health check only supports stdio servers".to_string()),
+/// Run a health check synchronously, launching the server with the given
+/// options. Returns the HealthResult.
+pub fn check_server_with(index: usize, server: &McpServer, launch: &LaunchOptions) -> HealthResult {
+    let (status, cleanup, metrics, tools) = match &server.transport {
+        Transport::Stdio { command, args } => check_stdio(command, args, &server.env, launch),
+        Transport::Http { url, headers } => check_remote(url, &headers.clone().unwrap_or_default(), false),
+        Transport::Sse { url } => check_remote(url, &HashMap::new(), true),
+        Transport::Unknown => (
+            HealthStatus::Error("unknown transport".to_string()),
+            None,
+            HealthMetrics::default(),
+            None,
+        ),
     };
     HealthResult {
         server_index: index,
+        server_key: state::server_key(server),
         status,
-        checked_at: Instant::now(),
+        checked_at: SystemTime::now(),
+        cleanup,
+        metrics,
+        tools,
     }
 }
 
@@ -32,20 +58,13 @@ pub fn check_server(index: usize, server: &McpServer) -> HealthResult {
 pub fn spawn_health_check(
     index: usize,
     server: &McpServer,
+    launch: &LaunchOptions,
     tx: mpsc::Sender<HealthResult>,
 ) {
-    let transport = server.transport.clone();
-    let env = server.env.clone();
+    let server = server.clone();
+    let launch = launch.clone();
     std::thread::spawn(move || {
-        let status = match &transport {
-            Transport::Stdio { command, args } => check_stdio(command, args, &env),
-            _ => HealthStatus::Error("health check only supports stdio servers".to_string()),
-        };
-        let _ = tx.send(HealthResult {
-            server_index: index,
-            status,
-            checked_at: Instant::now(),
-        });
+        let _ = tx.send(check_server_with(index, &server, &launch));
     });
 }
 
@@ -53,131 +72,500 @@ fn check_stdio(
     command: &str,
     args: &[String],
     env: &Option<HashMap<String, String>>,
-) -> HealthStatus {
-    // Spawn the server process
-    let mut cmd = Command::new(command);
-    cmd.args(args)
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped());
-
-    if let Some(env_map) = env {
-        cmd.envs(env_map);
-    }
-
-    let mut child = match cmd.spawn() {
-        Ok(c) => c,
-        Err(e) => {
-            return if e.kind() == std::io::ErrorKind::NotFound {
-                HealthStatus::Error(format!("command not found: {}", command))
+    launch: &LaunchOptions,
+) -> (HealthStatus, Option<Cleanup>, HealthMetrics, Option<Vec<Value>>) {
+    let mut session = match Session::spawn_with(command, args, env, launch) {
+        Ok(s) => s,
+        Err(e) => return (e.into_status(), None, HealthMetrics::default(), None),
+    };
+
+    let mut tools = None;
+    let status = match session.initialize() {
+        Ok(result) => {
+            // A failing tools/list doesn't make the server unhealthy; the
+            // list is kept for context-cost and collision analysis
+            if result["capabilities"].get("tools").is_some() {
+                tools = session.list_tools().ok();
             } else {
-                HealthStatus::Error(e.to_string())
-            };
+                tools = Some(Vec::new());
+            }
+            healthy_status(&result)
         }
+        Err(e) => e.into_status(),
     };
 
-    // Write initialize message to stdin
-    let _stdin_handle = child.stdin.take().and_then(|mut stdin| {
-        // Send bare JSON with trailing newline — this is the most compatible
-        // format. Content-Length framing can cause issues with some SDK
-        // implementations that use line-based stdin readers.
-        let msg = format!("{}\n", initialize_msg());
-        let _ = stdin.write_all(msg.as_bytes());
-        let _ = stdin.flush();
-        // Keep stdin alive — dropping it sends EOF which causes many MCP
-        // servers (e.g. @modelcontextprotocol/sdk) to shut down immediately.
-        Some(stdin)
-    });
+    let (cleanup, metrics) = session.close();
+    (status, Some(cleanup), metrics, tools)
+}
+
+/// The same handshake over HTTP. Launch options don't apply; nothing is
+/// spawned, so there's no cleanup to report.
+fn check_remote(
+    url: &str,
+    headers: &HashMap<String, String>,
+    legacy_sse: bool,
+) -> (HealthStatus, Option<Cleanup>, HealthMetrics, Option<Vec<Value>>) {
+    let mut metrics = HealthMetrics::default();
+    let mut client = match RemoteClient::connect(url, headers, legacy_sse, TIMEOUT) {
+        Ok(client) => client,
+        Err(e) => return (remote_status(url, e), None, metrics, None),
+    };
 
-    // Read stdout with timeout
-    let stdout = match child.stdout.take() {
-        Some(s) => s,
-        None => {
-            let _ = child.kill();
-            let _ = child.wait();
-            return HealthStatus::Error("failed to capture stdout".to_string());
+    let sent = Instant::now();
+    let mut tools = None;
+    let status = match client.request("initialize", initialize_params(), TIMEOUT) {
+        Ok(result) => {
+            metrics.initialize = Some(sent.elapsed());
+            client.notify("notifications/initialized", json!({}));
+            if result["capabilities"].get("tools").is_some() {
+                let sent = Instant::now();
+                tools = client.list_all("tools/list", "tools", TIMEOUT).ok();
+                metrics.tools_list = tools.is_some().then(|| sent.elapsed());
+                metrics.tool_count = tools.as_ref().map(Vec::len);
+            } else {
+                tools = Some(Vec::new());
+            }
+            healthy_status(&result)
         }
+        Err(e) => remote_status(url, e),
     };
+    client.close();
+    (status, None, metrics, tools)
+}
 
-    let (read_tx, read_rx) = mpsc::channel();
-    std::thread::spawn(move || {
-        let mut stdout = stdout;
-        let mut buf = vec![0u8; 8192];
-        let mut output = Vec::new();
+/// A 401 is followed through OAuth discovery, so the status can say where
+/// a token comes from rather than just that the request failed
+fn remote_status(url: &str, error: RemoteError) -> HealthStatus {
+    match error {
+        RemoteError::Unauthorized(challenge) => match oauth::discover(url, challenge.as_deref()) {
+            Ok(info) => HealthStatus::AuthRequired {
+                issuer: info.issuer,
+                scopes: info.scopes,
+            },
+            Err(e) => HealthStatus::Error(format!("unauthorized (HTTP 401); OAuth discovery failed: {}", e)),
+        },
+        RemoteError::Timeout => HealthStatus::Timeout,
+        RemoteError::Failed(e) => HealthStatus::Error(e),
+    }
+}
+
+pub fn initialize_params() -> Value {
+    json!({
+        "protocolVersion": PROTOCOL_VERSION,
+        "capabilities": {},
+        "clientInfo": { "name": "mcpm", "version": env!("CARGO_PKG_VERSION") },
+    })
+}
+
+fn healthy_status(init_result: &Value) -> HealthStatus {
+    let info = &init_result["serverInfo"];
+    HealthStatus::Healthy {
+        server_name: info["name"].as_str().unwrap_or("unknown").to_string(),
+        server_version: info["version"].as_str().unwrap_or("unknown").to_string(),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Session
+// ---------------------------------------------------------------------------
+
+/// Why a session request didn't produce a result
+#[derive(Debug)]
+pub enum SessionError {
+    Timeout,
+    Failed(String),
+}
+
+impl SessionError {
+    pub fn into_status(self) -> HealthStatus {
+        match self {
+            SessionError::Timeout => HealthStatus::Timeout,
+            SessionError::Failed(e) => HealthStatus::Error(e),
+        }
+    }
+}
+
+impl std::fmt::Display for SessionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SessionError::Timeout => write!(f, "timeout ({}s)", TIMEOUT.as_secs()),
+            SessionError::Failed(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+enum ReadEvent {
+    FirstByte(Instant),
+    Message(Value),
+    /// stdout closed; carries any non-JSON output seen
+    Closed(String),
+}
+
+/// A live stdio connection to an MCP server: spawn, `initialize`, then any
+/// number of requests, then a spec-compliant shutdown of the process group.
+/// Records startup latency as it goes.
+pub struct Session {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    events: mpsc::Receiver<ReadEvent>,
+    next_id: u64,
+    spawned_at: Instant,
+    closed: Option<String>,
+    metrics: HealthMetrics,
+    #[cfg(target_os = "linux")]
+    sampler: procfs::GroupSampler,
+    /// Keeps the sandbox's proxy up while the server runs
+    _sandbox: Option<sandbox::Wrapped>,
+}
+
+impl Session {
+    pub fn spawn(
+        command: &str,
+        args: &[String],
+        env: &Option<HashMap<String, String>>,
+    ) -> Result<Session, SessionError> {
+        Self::spawn_with(command, args, env, &LaunchOptions::default())
+    }
+
+    pub fn spawn_with(
+        command: &str,
+        args: &[String],
+        env: &Option<HashMap<String, String>>,
+        launch: &LaunchOptions,
+    ) -> Result<Session, SessionError> {
+        let wrapped = match &launch.sandbox {
+            Some(sb) => Some(sb.wrap(command, args).map_err(|e| SessionError::Failed(format!("sandbox: {}", e)))?),
+            None => None,
+        };
+        let (program, argv) = match &wrapped {
+            Some(w) => (w.program.as_str(), w.args.as_slice()),
+            None => (command, args),
+        };
+        let mut cmd = Command::new(program);
+        cmd.args(argv)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        // Own process group, so shutdown reaches whatever `npx`/`uvx` launched
+        #[cfg(unix)]
+        cmd.process_group(0);
+
+        launch.apply(&mut cmd);
+        if let Some(env_map) = env {
+            cmd.envs(env_map);
+        }
+
+        let spawned_at = Instant::now();
+        let mut child = cmd.spawn().map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                SessionError::Failed(format!("command not found: {}", command))
+            } else {
+                SessionError::Failed(e.to_string())
+            }
+        })?;
+        let mut metrics = HealthMetrics {
+            spawn: Some(spawned_at.elapsed()),
+            ..HealthMetrics::default()
+        };
+
+        #[cfg(target_os = "linux")]
+        let sampler = procfs::GroupSampler::start(child.id() as i32);
+
+        // Nobody reads stderr, so drain it — a full pipe would block the server
+        if let Some(mut stderr) = child.stderr.take() {
+            std::thread::spawn(move || {
+                let _ = std::io::copy(&mut stderr, &mut std::io::sink());
+            });
+        }
+
+        let (tx, events) = mpsc::channel();
+        match child.stdout.take() {
+            Some(stdout) => {
+                std::thread::spawn(move || read_stdout(stdout, tx));
+            }
+            None => {
+                metrics.spawn = None;
+                let _ = tx.send(ReadEvent::Closed("failed to capture stdout".to_string()));
+            }
+        }
+
+        Ok(Session {
+            stdin: child.stdin.take(),
+            child,
+            events,
+            next_id: 1,
+            spawned_at,
+            closed: None,
+            metrics,
+            #[cfg(target_os = "linux")]
+            sampler,
+            _sandbox: wrapped,
+        })
+    }
+
+    /// Perform the `initialize` handshake and send `notifications/initialized`.
+    /// Returns the server's initialize result.
+    pub fn initialize(&mut self) -> Result<Value, SessionError> {
+        let sent = Instant::now();
+        let result = self.request("initialize", initialize_params(), TIMEOUT)?;
+        self.metrics.initialize = Some(sent.elapsed());
+        self.notify("notifications/initialized", json!({}));
+        Ok(result)
+    }
+
+    /// Fetch every page of `tools/list`
+    pub fn list_tools(&mut self) -> Result<Vec<Value>, SessionError> {
+        let sent = Instant::now();
+        let tools = self.list_all("tools/list", "tools")?;
+        self.metrics.tools_list = Some(sent.elapsed());
+        self.metrics.tool_count = Some(tools.len());
+        Ok(tools)
+    }
+
+    /// Follow `nextCursor` through a paginated list method, collecting `key`
+    pub fn list_all(&mut self, method: &str, key: &str) -> Result<Vec<Value>, SessionError> {
+        let mut items = Vec::new();
+        let mut cursor: Option<String> = None;
         loop {
-            match stdout.read(&mut buf) {
-                Ok(0) => break,
-                Ok(n) => {
-                    output.extend_from_slice(&buf[..n]);
-                    // Check if we have a complete JSON response yet
-                    if let Some(status) = try_parse_response(&output) {
-                        let _ = read_tx.send(Ok(status));
-                        return;
-                    }
-                }
-                Err(e) => {
-                    let _ = read_tx.send(Err(e.to_string()));
-                    return;
-                }
+            let params = match &cursor {
+                Some(c) => json!({ "cursor": c }),
+                None => json!({}),
+            };
+            let result = self.request(method, params, TIMEOUT)?;
+            if let Some(page) = result[key].as_array() {
+                items.extend(page.iter().cloned());
+            }
+            match result["nextCursor"].as_str() {
+                Some(next) if cursor.as_deref() != Some(next) => cursor = Some(next.to_string()),
+                _ => return Ok(items),
             }
         }
-        // EOF reached — try to parse whatever we got
-        match try_parse_response(&output) {
-            Some(status) => {
-                let _ = read_tx.send(Ok(status));
+    }
+
+    /// Send a request and wait for its response
+    pub fn request(
+        &mut self,
+        method: &str,
+        params: Value,
+        timeout: Duration,
+    ) -> Result<Value, SessionError> {
+        let msg = self.request_message(method, params, timeout)?;
+        if let Some(err) = msg.get("error") {
+            let text = err["message"].as_str().unwrap_or("unknown error");
+            return Err(SessionError::Failed(format!("server error: {}", text)));
+        }
+        Ok(msg.get("result").cloned().unwrap_or(Value::Null))
+    }
+
+    /// Send a request and return the whole response message, error or not
+    pub fn request_message(
+        &mut self,
+        method: &str,
+        params: Value,
+        timeout: Duration,
+    ) -> Result<Value, SessionError> {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.send(&jsonrpc::request(id, method, params));
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            let msg = self.next_message(deadline)?;
+            if msg.get("method").is_some() {
+                self.answer_server_message(&msg);
+                continue;
             }
-            None if output.is_empty() => {
-                let _ = read_tx.send(Err("no response from server".to_string()));
+            if msg["id"].as_u64() == Some(id) {
+                return Ok(msg);
             }
-            None => {
-                let preview = String::from_utf8_lossy(&output[..output.len().min(200)]);
-                let _ = read_tx.send(Err(format!("invalid response: {}", preview)));
+        }
+    }
+
+    /// Whether the server is still running and its stdout open
+    pub fn is_alive(&mut self) -> bool {
+        self.closed.is_none() && matches!(self.child.try_wait(), Ok(None))
+    }
+
+    pub fn notify(&mut self, method: &str, params: Value) {
+        self.send(&jsonrpc::notification(method, params));
+    }
+
+    /// Shut the server down and return how that went plus the final metrics
+    pub fn close(mut self) -> (Cleanup, HealthMetrics) {
+        #[cfg(target_os = "linux")]
+        if let Some(usage) = self.sampler.finish() {
+            self.metrics.peak_rss_bytes = Some(usage.peak_rss_bytes);
+            self.metrics.cpu_time = Some(usage.cpu_time);
+        }
+        let cleanup = shutdown(&mut self.child, self.stdin.take());
+        (cleanup, self.metrics)
+    }
+
+    fn send(&mut self, msg: &Value) {
+        // Write errors surface as a closed stdout on the next read
+        if let Some(stdin) = self.stdin.as_mut() {
+            let _ = stdin.write_all(jsonrpc::encode(msg).as_bytes());
+            let _ = stdin.flush();
+        }
+    }
+
+    fn next_message(&mut self, deadline: Instant) -> Result<Value, SessionError> {
+        loop {
+            if let Some(reason) = &self.closed {
+                return Err(SessionError::Failed(reason.clone()));
+            }
+            let wait = deadline.saturating_duration_since(Instant::now());
+            match self.events.recv_timeout(wait) {
+                Ok(ReadEvent::FirstByte(at)) => {
+                    self.metrics.first_byte = Some(at.duration_since(self.spawned_at));
+                }
+                Ok(ReadEvent::Message(msg)) => return Ok(msg),
+                Ok(ReadEvent::Closed(noise)) => {
+                    self.closed = Some(if self.metrics.first_byte.is_none() {
+                        "no response from server".to_string()
+                    } else if !noise.is_empty() {
+                        format!("invalid response: {}", noise)
+                    } else {
+                        "server closed the connection".to_string()
+                    });
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => return Err(SessionError::Timeout),
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    self.closed = Some("server closed the connection".to_string());
+                }
             }
         }
-    });
+    }
 
-    let result = match read_rx.recv_timeout(TIMEOUT) {
-        Ok(Ok(status)) => status,
-        Ok(Err(e)) => HealthStatus::Error(e),
-        Err(_) => HealthStatus::Timeout,
-    };
+    /// Answer pings; refuse any other server-initiated request so the server
+    /// isn't left waiting. Notifications are ignored.
+    fn answer_server_message(&mut self, msg: &Value) {
+        let Some(id) = msg.get("id").cloned() else {
+            return;
+        };
+        let reply = if msg["method"] == "ping" {
+            jsonrpc::response(id, json!({}))
+        } else {
+            jsonrpc::error_response(id, -32601, "method not supported by mcpm")
+        };
+        self.send(&reply);
+    }
+}
+
+fn read_stdout(mut stdout: impl Read, tx: mpsc::Sender<ReadEvent>) {
+    let mut decoder = FrameDecoder::default();
+    let mut buf = vec![0u8; 8192];
+    let mut seen_output = false;
+    loop {
+        match stdout.read(&mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => {
+                if !seen_output {
+                    seen_output = true;
+                    let _ = tx.send(ReadEvent::FirstByte(Instant::now()));
+                }
+                decoder.push(&buf[..n]);
+                while let Some(msg) = decoder.next_message() {
+                    // Batches are rare from servers; unpack them in order
+                    let batch = match msg {
+                        Value::Array(items) => items,
+                        other => vec![other],
+                    };
+                    for item in batch {
+                        if tx.send(ReadEvent::Message(item)).is_err() {
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    let _ = tx.send(ReadEvent::Closed(decoder.noise_preview()));
+}
+
+// ---------------------------------------------------------------------------
+// Shutdown
+// ---------------------------------------------------------------------------
+
+/// Stop a spawned server the way the MCP spec describes for stdio: close
+/// stdin, wait, SIGTERM, then SIGKILL. Signals go to the whole process group,
+/// and the result says whether anything in the group survived.
+#[cfg(unix)]
+fn shutdown(child: &mut Child, stdin: Option<ChildStdin>) -> Cleanup {
+    drop(stdin);
+    // process_group(0) makes the child's pid its group id
+    let pgid = child.id() as libc::pid_t;
+
+    if wait_for_group(child, pgid, SHUTDOWN_GRACE) {
+        return Cleanup::Exited;
+    }
+    signal_group(pgid, libc::SIGTERM);
+    if wait_for_group(child, pgid, SHUTDOWN_GRACE) {
+        return Cleanup::Terminated;
+    }
+    signal_group(pgid, libc::SIGKILL);
+    if wait_for_group(child, pgid, SHUTDOWN_GRACE) {
+        Cleanup::Killed
+    } else {
+        Cleanup::Leaked
+    }
+}
 
+/// No process groups off Unix — fall back to killing the direct child.
+#[cfg(not(unix))]
+fn shutdown(child: &mut Child, stdin: Option<ChildStdin>) -> Cleanup {
+    drop(stdin);
+    let deadline = Instant::now() + SHUTDOWN_GRACE;
+    while Instant::now() < deadline {
+        if let Ok(Some(_)) = child.try_wait() {
+            return Cleanup::Exited;
+        }
+        std::thread::sleep(Duration::from_millis(25));
+    }
     let _ = child.kill();
     let _ = child.wait();
+    Cleanup::Killed
+}
 
-    result
-}
-
-/// Try to extract a valid initialize response from the accumulated output.
-/// Handles both bare JSON and Content-Length framed responses.
-fn try_parse_response(data: &[u8]) -> Option<HealthStatus> {
-    let text = std::str::from_utf8(data).ok()?;
-
-    // Try to find JSON in the output — skip any Content-Length headers
-    let json_start = text.find('{')?;
-    let json_text = &text[json_start..];
-
-    // Try to parse as JSON
-    let val: serde_json::Value = serde_json::from_str(json_text).ok()?;
-
-    // Check for JSON-RPC response with result
-    if val.get("result").is_some() {
-        let server_name = val["result"]["serverInfo"]["name"]
-            .as_str()
-            .unwrap_or("unknown")
-            .to_string();
-        let server_version = val["result"]["serverInfo"]["version"]
-            .as_str()
-            .unwrap_or("unknown")
-            .to_string();
-        Some(HealthStatus::Healthy {
-            server_name,
-            server_version,
-        })
-    } else if let Some(err) = val.get("error") {
-        let msg = err["message"].as_str().unwrap_or("unknown error");
-        Some(HealthStatus::Error(format!("server error: {}", msg)))
-    } else {
-        None
+/// Poll until every process in the group is gone, reaping the direct child
+/// along the way. Returns false if the group outlived `timeout`.
+#[cfg(unix)]
+fn wait_for_group(child: &mut Child, pgid: libc::pid_t, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    loop {
+        // Reap the leader so it doesn't linger as a zombie group member
+        let _ = child.try_wait();
+        if !group_alive(pgid) {
+            return true;
+        }
+        if Instant::now() >= deadline {
+            return false;
+        }
+        std::thread::sleep(Duration::from_millis(25));
+    }
+}
+
+#[cfg(unix)]
+fn signal_group(pgid: libc::pid_t, signal: libc::c_int) {
+    // SAFETY: kill(2) has no memory-safety preconditions; a negative pid
+    // addresses the process group.
+    unsafe {
+        libc::kill(-pgid, signal);
     }
 }
+
+/// Whether any non-zombie process is left in the group. On Linux this scans
+/// /proc, since reparented zombies still count for kill(2) until init reaps them.
+#[cfg(target_os = "linux")]
+fn group_alive(pgid: libc::pid_t) -> bool {
+    !procfs::group_stats(pgid).is_empty()
+}
+
+/// kill(-pgid, 0) succeeds while any member exists; EPERM still means "exists".
+#[cfg(all(unix, not(target_os = "linux")))]
+fn group_alive(pgid: libc::pid_t) -> bool {
+    // SAFETY: signal 0 only performs the existence/permission check.
+    let rc = unsafe { libc::kill(-pgid, 0) };
+    rc == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
@@ -0,0 +1,769 @@
+use std::sync::mpsc;
+
+use serde_json::{Map, Value, json};
+
+use crate::health::{CALL_TIMEOUT, Session, TIMEOUT};
+use crate::types::{McpServer, Transport};
+
+// ---------------------------------------------------------------------------
+// Session worker — owns a live Session on a background thread
+// ---------------------------------------------------------------------------
+
+pub enum SessionRequest {
+    /// Paginated list method, collecting `key` from every page
+    List { method: String, key: String },
+    /// Any other request
+    Call { method: String, params: Value },
+}
+
+pub enum SessionEvent {
+    /// Handshake done; carries the initialize result
+    Ready(Value),
+    /// Answer to a request, tagged with the request's method
+    Response {
+        method: String,
+        result: Result<Value, String>,
+    },
+    /// Spawn or handshake failed; the worker has exited
+    Failed(String),
+}
+
+/// Handle to a server session running on its own thread. Requests are
+/// answered in order; dropping the handle shuts the server down.
+pub struct SessionWorker {
+    requests: mpsc::Sender<SessionRequest>,
+    events: mpsc::Receiver<SessionEvent>,
+}
+
+impl SessionWorker {
+    pub fn spawn(server: &McpServer) -> SessionWorker {
+        let (req_tx, req_rx) = mpsc::channel::<SessionRequest>();
+        let (ev_tx, events) = mpsc::channel();
+        let transport = server.transport.clone();
+        let env = server.env.clone();
+
+        std::thread::spawn(move || {
+            let Transport::Stdio { command, args } = transport else {
+                let _ = ev_tx.send(SessionEvent::Failed("only stdio servers are supported".to_string()));
+                return;
+            };
+            let mut session = match Session::spawn(&command, &args, &env) {
+                Ok(s) => s,
+                Err(e) => {
+                    let _ = ev_tx.send(SessionEvent::Failed(e.to_string()));
+                    return;
+                }
+            };
+            match session.initialize() {
+                Ok(init) => {
+                    let _ = ev_tx.send(SessionEvent::Ready(init));
+                }
+                Err(e) => {
+                    let _ = ev_tx.send(SessionEvent::Failed(e.to_string()));
+                    session.close();
+                    return;
+                }
+            }
+            // Runs until the handle is dropped
+            for req in req_rx {
+                let (method, result) = match req {
+                    SessionRequest::List { method, key } => {
+                        let r = session.list_all(&method, &key).map(Value::Array);
+                        (method, r)
+                    }
+                    SessionRequest::Call { method, params } => {
+                        let timeout = if method == "tools/call" { CALL_TIMEOUT } else { TIMEOUT };
+                        let r = session.request(&method, params, timeout);
+                        (method, r)
+                    }
+                };
+                let event = SessionEvent::Response {
+                    method,
+                    result: result.map_err(|e| e.to_string()),
+                };
+                if ev_tx.send(event).is_err() {
+                    break;
+                }
+            }
+            session.close();
+        });
+
+        SessionWorker { requests: req_tx, events }
+    }
+
+    pub fn send(&self, req: SessionRequest) {
+        let _ = self.requests.send(req);
+    }
+
+    pub fn try_recv(&self) -> Option<SessionEvent> {
+        self.events.try_recv().ok()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Inspector — browse tools, resources and prompts; call, read or render one
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InspectTab {
+    Tools,
+    Resources,
+    Prompts,
+}
+
+impl InspectTab {
+    pub const ALL: [InspectTab; 3] = [InspectTab::Tools, InspectTab::Resources, InspectTab::Prompts];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            InspectTab::Tools => "Tools",
+            InspectTab::Resources => "Resources",
+            InspectTab::Prompts => "Prompts",
+        }
+    }
+
+    /// Capability the server must advertise for this tab
+    fn capability(&self) -> &'static str {
+        match self {
+            InspectTab::Tools => "tools",
+            InspectTab::Resources => "resources",
+            InspectTab::Prompts => "prompts",
+        }
+    }
+
+    fn next(&self) -> InspectTab {
+        match self {
+            InspectTab::Tools => InspectTab::Resources,
+            InspectTab::Resources => InspectTab::Prompts,
+            InspectTab::Prompts => InspectTab::Tools,
+        }
+    }
+}
+
+/// Items of one tab as they arrive from the server
+#[derive(Default)]
+pub struct TabList {
+    /// Tools, prompts, or resources followed by resource templates
+    pub items: Vec<Value>,
+    /// Still waiting on the list request(s)
+    pub loading: bool,
+    /// Why the list is empty, when it isn't just empty
+    pub note: Option<String>,
+}
+
+#[derive(PartialEq)]
+pub enum InspectStep {
+    Connecting,
+    List,
+    Form,
+    Calling,
+    Result,
+    Failed,
+}
+
+pub struct Inspector {
+    pub server_name: String,
+    worker: SessionWorker,
+    pub step: InspectStep,
+    /// "name vX" from the initialize result
+    pub server_info: String,
+    pub tab: InspectTab,
+    pub tools: TabList,
+    pub resources: TabList,
+    pub prompts: TabList,
+    pub cursor: usize,
+    pub form: ArgForm,
+    pub result_lines: Vec<ResultLine>,
+    pub scroll: usize,
+    pub error: Option<String>,
+    /// Calls abandoned with Esc whose replies are still to come
+    abandoned_calls: usize,
+}
+
+impl Inspector {
+    pub fn new(server: &McpServer) -> Self {
+        Inspector {
+            server_name: server.name.clone(),
+            worker: SessionWorker::spawn(server),
+            step: InspectStep::Connecting,
+            server_info: String::new(),
+            tab: InspectTab::Tools,
+            tools: TabList::default(),
+            resources: TabList::default(),
+            prompts: TabList::default(),
+            cursor: 0,
+            form: ArgForm::default(),
+            result_lines: Vec::new(),
+            scroll: 0,
+            error: None,
+            abandoned_calls: 0,
+        }
+    }
+
+    /// Drain worker events and advance the state machine
+    pub fn poll(&mut self) {
+        while let Some(event) = self.worker.try_recv() {
+            match event {
+                SessionEvent::Ready(init) => self.on_ready(&init),
+                SessionEvent::Failed(e) => {
+                    self.error = Some(e);
+                    self.step = InspectStep::Failed;
+                }
+                SessionEvent::Response { method, result } => self.on_response(&method, result),
+            }
+        }
+    }
+
+    /// Request every list the server advertises a capability for
+    fn on_ready(&mut self, init: &Value) {
+        let info = &init["serverInfo"];
+        self.server_info = format!(
+            "{} v{}",
+            info["name"].as_str().unwrap_or("unknown"),
+            info["version"].as_str().unwrap_or("unknown")
+        );
+        let list = |method: &str, key: &str| SessionRequest::List {
+            method: method.to_string(),
+            key: key.to_string(),
+        };
+        for tab in InspectTab::ALL {
+            let supported = init["capabilities"].get(tab.capability()).is_some();
+            let target = self.list_mut(tab);
+            if !supported {
+                target.note = Some(format!("server does not advertise {}", tab.capability()));
+                continue;
+            }
+            target.loading = true;
+            match tab {
+                InspectTab::Tools => self.worker.send(list("tools/list", "tools")),
+                InspectTab::Resources => {
+                    self.worker.send(list("resources/list", "resources"));
+                    self.worker.send(list("resources/templates/list", "resourceTemplates"));
+                }
+                InspectTab::Prompts => self.worker.send(list("prompts/list", "prompts")),
+            }
+        }
+        self.step = InspectStep::List;
+    }
+
+    fn on_response(&mut self, method: &str, result: Result<Value, String>) {
+        let items = |r: Result<Value, String>| match r {
+            Ok(Value::Array(items)) => Ok(items),
+            Ok(_) => Ok(Vec::new()),
+            Err(e) => Err(e),
+        };
+        match method {
+            "tools/list" | "resources/list" | "prompts/list" => {
+                let tab = match method {
+                    "tools/list" => InspectTab::Tools,
+                    "resources/list" => InspectTab::Resources,
+                    _ => InspectTab::Prompts,
+                };
+                let target = self.list_mut(tab);
+                // Resources stay loading until the templates arrive
+                target.loading = tab == InspectTab::Resources;
+                match items(result) {
+                    Ok(items) => target.items = items,
+                    Err(e) => target.note = Some(e),
+                }
+            }
+            "resources/templates/list" => {
+                self.resources.loading = false;
+                // Templates are optional; plenty of servers reject the method
+                if let Ok(templates) = items(result) {
+                    self.resources.items.extend(templates);
+                }
+            }
+            // Replies arrive in order, so abandoned calls are answered first
+            _ if self.abandoned_calls > 0 => self.abandoned_calls -= 1,
+            "tools/call" | "resources/read" | "prompts/get" => {
+                self.result_lines = match result {
+                    Ok(r) if method == "tools/call" => render_call_result(&r),
+                    Ok(r) if method == "resources/read" => render_read_result(&r),
+                    Ok(r) => render_prompt_result(&r),
+                    Err(e) => vec![ResultLine::Error(e)],
+                };
+                self.scroll = 0;
+                self.step = InspectStep::Result;
+            }
+            _ => {}
+        }
+    }
+
+    pub fn list(&self, tab: InspectTab) -> &TabList {
+        match tab {
+            InspectTab::Tools => &self.tools,
+            InspectTab::Resources => &self.resources,
+            InspectTab::Prompts => &self.prompts,
+        }
+    }
+
+    fn list_mut(&mut self, tab: InspectTab) -> &mut TabList {
+        match tab {
+            InspectTab::Tools => &mut self.tools,
+            InspectTab::Resources => &mut self.resources,
+            InspectTab::Prompts => &mut self.prompts,
+        }
+    }
+
+    pub fn selected_item(&self) -> Option<&Value> {
+        self.list(self.tab).items.get(self.cursor)
+    }
+
+    pub fn next_tab(&mut self) {
+        self.tab = self.tab.next();
+        self.cursor = 0;
+    }
+
+    pub fn cursor_up(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn cursor_down(&mut self) {
+        if self.cursor + 1 < self.list(self.tab).items.len() {
+            self.cursor += 1;
+        }
+    }
+
+    /// Open the argument form for the selected item. Resources and prompts
+    /// that take no arguments are fetched straight away.
+    pub fn open_form(&mut self) {
+        let Some(item) = self.selected_item() else {
+            return;
+        };
+        self.form = match self.tab {
+            InspectTab::Tools => {
+                ArgForm::from_schema(item_name(item).to_string(), &item["inputSchema"])
+            }
+            InspectTab::Resources => match item["uriTemplate"].as_str() {
+                Some(template) => ArgForm::from_names(
+                    template.to_string(),
+                    template_variables(template).into_iter().map(|v| (v, true, String::new())),
+                ),
+                None => ArgForm::from_names(item["uri"].as_str().unwrap_or("").to_string(), []),
+            },
+            InspectTab::Prompts => ArgForm::from_names(
+                item_name(item).to_string(),
+                item["arguments"].as_array().into_iter().flatten().map(|a| {
+                    (
+                        a["name"].as_str().unwrap_or("").to_string(),
+                        a["required"].as_bool().unwrap_or(false),
+                        a["description"].as_str().unwrap_or("").to_string(),
+                    )
+                }),
+            ),
+        };
+        if self.form.fields.is_empty() && self.tab != InspectTab::Tools {
+            self.call();
+        } else {
+            self.step = InspectStep::Form;
+        }
+    }
+
+    /// Validate the form and send `tools/call`, `resources/read` or
+    /// `prompts/get` depending on the tab
+    pub fn call(&mut self) {
+        let arguments = match self.form.to_arguments() {
+            Ok(a) => a,
+            Err(e) => {
+                self.form.error = Some(e);
+                return;
+            }
+        };
+        self.form.error = None;
+        let target = Value::String(self.form.target.clone());
+        let (method, params) = match self.tab {
+            InspectTab::Tools => ("tools/call", json!({ "name": target, "arguments": arguments })),
+            InspectTab::Resources => {
+                let uri = expand_template(&self.form.target, &arguments);
+                ("resources/read", json!({ "uri": uri }))
+            }
+            InspectTab::Prompts => ("prompts/get", json!({ "name": target, "arguments": arguments })),
+        };
+        self.worker.send(SessionRequest::Call {
+            method: method.to_string(),
+            params,
+        });
+        self.step = InspectStep::Calling;
+    }
+
+    /// Stop waiting for the current call; its reply will be discarded
+    pub fn abandon_call(&mut self) {
+        if self.step == InspectStep::Calling {
+            self.abandoned_calls += 1;
+            self.back();
+        }
+    }
+
+    /// Step back from a result or pending call: to the form if there is one
+    /// to edit, otherwise to the list
+    pub fn back(&mut self) {
+        self.step = if self.form.fields.is_empty() && self.tab != InspectTab::Tools {
+            InspectStep::List
+        } else {
+            InspectStep::Form
+        };
+    }
+
+    pub fn scroll_up(&mut self) {
+        self.scroll = self.scroll.saturating_sub(1);
+    }
+
+    pub fn scroll_down(&mut self) {
+        if self.scroll + 1 < self.result_lines.len() {
+            self.scroll += 1;
+        }
+    }
+}
+
+/// Display name of a tool, prompt, resource or resource template
+pub fn item_name(item: &Value) -> &str {
+    item["name"]
+        .as_str()
+        .or_else(|| item["uri"].as_str())
+        .or_else(|| item["uriTemplate"].as_str())
+        .unwrap_or("?")
+}
+
+/// Variable names in an RFC 6570 URI template, e.g. `{date}` or `{+path}`
+pub fn template_variables(template: &str) -> Vec<String> {
+    let mut vars = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        let expr = rest[start + 1..start + len].trim_start_matches(['+', '#', '.', '/', ';', '?', '&']);
+        for name in expr.split(',') {
+            let name = name.trim_end_matches('*');
+            if !name.is_empty() && !vars.iter().any(|v| v == name) {
+                vars.push(name.to_string());
+            }
+        }
+        rest = &rest[start + len + 1..];
+    }
+    vars
+}
+
+/// Substitute template variables with the given values. Covers the simple
+/// and reserved forms servers use in practice, not all of RFC 6570.
+pub fn expand_template(template: &str, values: &Value) -> String {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        let expr = rest[start + 1..start + len].trim_start_matches(['+', '#', '.', '/', ';', '?', '&']);
+        let parts: Vec<&str> = expr
+            .split(',')
+            .filter_map(|name| values[name.trim_end_matches('*')].as_str())
+            .collect();
+        out.push_str(&parts.join(","));
+        rest = &rest[start + len + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+// ---------------------------------------------------------------------------
+// Argument form generated from a JSON Schema
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FieldKind {
+    String,
+    Number,
+    Integer,
+    Boolean,
+    /// Objects, arrays and anything else — entered as raw JSON
+    Json,
+}
+
+impl FieldKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            FieldKind::String => "string",
+            FieldKind::Number => "number",
+            FieldKind::Integer => "integer",
+            FieldKind::Boolean => "boolean",
+            FieldKind::Json => "json",
+        }
+    }
+}
+
+pub struct FormField {
+    pub name: String,
+    pub kind: FieldKind,
+    pub required: bool,
+    pub description: String,
+    /// Allowed values from the schema's `enum`, if any
+    pub choices: Vec<String>,
+    pub input: String,
+}
+
+#[derive(Default)]
+pub struct ArgForm {
+    /// Tool or prompt name, or the resource URI (template)
+    pub target: String,
+    pub fields: Vec<FormField>,
+    pub cursor: usize,
+    pub error: Option<String>,
+}
+
+impl ArgForm {
+    /// One field per top-level property, required ones first. Defaults from
+    /// the schema are pre-filled.
+    pub fn from_schema(target: String, schema: &Value) -> Self {
+        let required: Vec<&str> = schema["required"]
+            .as_array()
+            .map(|r| r.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+
+        let mut fields: Vec<FormField> = schema["properties"]
+            .as_object()
+            .map(|props| {
+                props
+                    .iter()
+                    .map(|(name, prop)| {
+                        let kind = match prop["type"].as_str() {
+                            Some("string") => FieldKind::String,
+                            Some("number") => FieldKind::Number,
+                            Some("integer") => FieldKind::Integer,
+                            Some("boolean") => FieldKind::Boolean,
+                            _ => FieldKind::Json,
+                        };
+                        let input = match prop.get("default") {
+                            Some(Value::String(s)) => s.clone(),
+                            Some(Value::Null) | None => String::new(),
+                            Some(other) => other.to_string(),
+                        };
+                        let choices = prop["enum"]
+                            .as_array()
+                            .map(|e| {
+                                e.iter()
+                                    .map(|v| v.as_str().map(str::to_string).unwrap_or_else(|| v.to_string()))
+                                    .collect()
+                            })
+                            .unwrap_or_default();
+                        FormField {
+                            name: name.clone(),
+                            kind,
+                            required: required.contains(&name.as_str()),
+                            description: prop["description"].as_str().unwrap_or("").to_string(),
+                            choices,
+                            input,
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        fields.sort_by_key(|f| !f.required);
+
+        ArgForm {
+            target,
+            fields,
+            cursor: 0,
+            error: None,
+        }
+    }
+
+    /// Plain string fields from (name, required, description) — prompt
+    /// arguments and URI template variables have no schema
+    pub fn from_names(
+        target: String,
+        names: impl IntoIterator<Item = (String, bool, String)>,
+    ) -> Self {
+        let fields = names
+            .into_iter()
+            .map(|(name, required, description)| FormField {
+                name,
+                kind: FieldKind::String,
+                required,
+                description,
+                choices: Vec::new(),
+                input: String::new(),
+            })
+            .collect();
+        ArgForm {
+            target,
+            fields,
+            cursor: 0,
+            error: None,
+        }
+    }
+
+    pub fn push_char(&mut self, c: char) {
+        self.error = None;
+        if let Some(f) = self.fields.get_mut(self.cursor) {
+            f.input.push(c);
+        }
+    }
+
+    pub fn pop_char(&mut self) {
+        if let Some(f) = self.fields.get_mut(self.cursor) {
+            f.input.pop();
+        }
+    }
+
+    pub fn next_field(&mut self) {
+        if self.cursor + 1 < self.fields.len() {
+            self.cursor += 1;
+        }
+    }
+
+    pub fn prev_field(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    /// Build the `arguments` object. Empty optional fields are left out.
+    pub fn to_arguments(&self) -> Result<Value, String> {
+        let mut args = Map::new();
+        for f in &self.fields {
+            let raw = f.input.trim();
+            if raw.is_empty() {
+                if f.required {
+                    return Err(format!("{} is required", f.name));
+                }
+                continue;
+            }
+            if !f.choices.is_empty() && !f.choices.iter().any(|c| c == raw) {
+                return Err(format!("{} must be one of: {}", f.name, f.choices.join(", ")));
+            }
+            let value = match f.kind {
+                FieldKind::String => Value::String(f.input.clone()),
+                FieldKind::Number => raw
+                    .parse::<f64>()
+                    .ok()
+                    .and_then(serde_json::Number::from_f64)
+                    .map(Value::Number)
+                    .ok_or_else(|| format!("{} must be a number", f.name))?,
+                FieldKind::Integer => raw
+                    .parse::<i64>()
+                    .map(Value::from)
+                    .map_err(|_| format!("{} must be an integer", f.name))?,
+                FieldKind::Boolean => match raw {
+                    "true" => Value::Bool(true),
+                    "false" => Value::Bool(false),
+                    _ => return Err(format!("{} must be true or false", f.name)),
+                },
+                FieldKind::Json => serde_json::from_str(raw)
+                    .map_err(|e| format!("{} is not valid JSON: {}", f.name, e))?,
+            };
+            args.insert(f.name.clone(), value);
+        }
+        Ok(Value::Object(args))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Result rendering
+// ---------------------------------------------------------------------------
+
+pub enum ResultLine {
+    Heading(String),
+    Text(String),
+    /// Metadata about binary or linked content
+    Meta(String),
+    Error(String),
+}
+
+/// Flatten a `tools/call` result into display lines: text content as-is,
+/// images/audio as metadata, embedded resources, then structured output.
+pub fn render_call_result(result: &Value) -> Vec<ResultLine> {
+    let mut lines = Vec::new();
+    if result["isError"].as_bool() == Some(true) {
+        lines.push(ResultLine::Error("tool reported an error".to_string()));
+    }
+
+    for item in result["content"].as_array().into_iter().flatten() {
+        push_content(&mut lines, item);
+    }
+
+    if let Some(structured) = result.get("structuredContent") {
+        lines.push(ResultLine::Heading("Structured output".to_string()));
+        let pretty = serde_json::to_string_pretty(structured).unwrap_or_default();
+        lines.extend(pretty.lines().map(|l| ResultLine::Text(l.to_string())));
+    }
+
+    if lines.is_empty() {
+        lines.push(ResultLine::Meta("(empty result)".to_string()));
+    }
+    lines
+}
+
+/// `resources/read` result: each content's URI and MIME type, then its text
+/// or the size of its blob
+pub fn render_read_result(result: &Value) -> Vec<ResultLine> {
+    let mut lines = Vec::new();
+    for contents in result["contents"].as_array().into_iter().flatten() {
+        push_resource_contents(&mut lines, contents);
+    }
+    if lines.is_empty() {
+        lines.push(ResultLine::Meta("(no contents)".to_string()));
+    }
+    lines
+}
+
+/// `prompts/get` result: the description, then each message under its role
+pub fn render_prompt_result(result: &Value) -> Vec<ResultLine> {
+    let mut lines = Vec::new();
+    if let Some(desc) = result["description"].as_str() {
+        lines.push(ResultLine::Meta(desc.to_string()));
+    }
+    for message in result["messages"].as_array().into_iter().flatten() {
+        lines.push(ResultLine::Heading(message["role"].as_str().unwrap_or("?").to_string()));
+        // Spec says a single content block; tolerate an array too
+        match &message["content"] {
+            Value::Array(items) => items.iter().for_each(|item| push_content(&mut lines, item)),
+            item => push_content(&mut lines, item),
+        }
+    }
+    if lines.is_empty() {
+        lines.push(ResultLine::Meta("(no messages)".to_string()));
+    }
+    lines
+}
+
+/// One content block as used in tool results and prompt messages
+fn push_content(lines: &mut Vec<ResultLine>, item: &Value) {
+    match item["type"].as_str().unwrap_or("") {
+        "text" => {
+            let text = item["text"].as_str().unwrap_or("");
+            lines.extend(text.lines().map(|l| ResultLine::Text(l.to_string())));
+        }
+        kind @ ("image" | "audio") => {
+            let data_len = item["data"].as_str().map(str::len).unwrap_or(0);
+            lines.push(ResultLine::Meta(format!(
+                "[{}: {}, ~{} bytes]",
+                kind,
+                item["mimeType"].as_str().unwrap_or("unknown type"),
+                data_len * 3 / 4
+            )));
+        }
+        "resource_link" => lines.push(ResultLine::Meta(format!(
+            "[resource link: {}{}]",
+            item["uri"].as_str().unwrap_or(""),
+            item["name"]
+                .as_str()
+                .map(|n| format!(" ({})", n))
+                .unwrap_or_default()
+        ))),
+        "resource" => push_resource_contents(lines, &item["resource"]),
+        other => lines.push(ResultLine::Meta(format!("[unknown content type: {}]", other))),
+    }
+}
+
+fn push_resource_contents(lines: &mut Vec<ResultLine>, res: &Value) {
+    lines.push(ResultLine::Meta(format!(
+        "[resource: {} {}]",
+        res["uri"].as_str().unwrap_or(""),
+        res["mimeType"].as_str().unwrap_or("")
+    )));
+    if let Some(text) = res["text"].as_str() {
+        lines.extend(text.lines().map(|l| ResultLine::Text(l.to_string())));
+    } else if let Some(blob) = res["blob"].as_str() {
+        lines.push(ResultLine::Meta(format!("[blob, ~{} bytes]", blob.len() * 3 / 4)));
+    }
+}
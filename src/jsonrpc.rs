@@ -0,0 +1,105 @@
+use serde_json::{Value, json};
+
+/// Build a JSON-RPC request
+pub fn request(id: u64, method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+}
+
+/// Build a JSON-RPC notification (no id, no response expected)
+pub fn notification(method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+/// Build a successful JSON-RPC response
+pub fn response(id: Value, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+/// Build a JSON-RPC error response
+pub fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+/// Serialize a message as one newline-delimited frame (the MCP stdio framing)
+pub fn encode(msg: &Value) -> String {
+    format!("{}\n", msg)
+}
+
+/// Incremental decoder for an MCP stdio stream.
+///
+/// Accepts newline-delimited JSON as the spec prescribes, and Content-Length
+/// framed messages from older servers. Lines that aren't JSON (log output
+/// printed to stdout by mistake) are skipped, with the first few hundred bytes
+/// kept so callers can show what the server printed instead of a response.
+#[derive(Default)]
+pub struct FrameDecoder {
+    buf: Vec<u8>,
+    noise: Vec<u8>,
+}
+
+const NOISE_PREVIEW: usize = 200;
+
+impl FrameDecoder {
+    pub fn push(&mut self, data: &[u8]) {
+        self.buf.extend_from_slice(data);
+    }
+
+    /// Pop the next complete message, or None if more data is needed
+    pub fn next_message(&mut self) -> Option<Value> {
+        loop {
+            let start = self.buf.iter().position(|b| !b.is_ascii_whitespace())?;
+            self.buf.drain(..start);
+
+            if self.buf.len() >= 15 && self.buf[..15].eq_ignore_ascii_case(b"content-length:") {
+                let header_end = find(&self.buf, b"\r\n\r\n")?;
+                let header = String::from_utf8_lossy(&self.buf[15..header_end]);
+                let len: usize = header
+                    .lines()
+                    .next()
+                    .and_then(|l| l.trim().parse().ok())
+                    .unwrap_or(0);
+                let body_start = header_end + 4;
+                if self.buf.len() < body_start + len {
+                    return None;
+                }
+                let parsed = serde_json::from_slice(&self.buf[body_start..body_start + len]);
+                self.buf.drain(..body_start + len);
+                match parsed {
+                    Ok(val) => return Some(val),
+                    Err(_) => continue,
+                }
+            }
+
+            if self.buf[0] == b'{' || self.buf[0] == b'[' {
+                let mut stream = serde_json::Deserializer::from_slice(&self.buf).into_iter::<Value>();
+                match stream.next() {
+                    Some(Ok(val)) => {
+                        let consumed = stream.byte_offset();
+                        self.buf.drain(..consumed);
+                        return Some(val);
+                    }
+                    Some(Err(e)) if e.is_eof() => return None,
+                    _ => {}
+                }
+            }
+
+            // Not a message — drop the line once it's complete
+            let end = self.buf.iter().position(|&b| b == b'\n')?;
+            let room = NOISE_PREVIEW.saturating_sub(self.noise.len());
+            self.noise.extend_from_slice(&self.buf[..end.min(room)]);
+            self.buf.drain(..=end);
+        }
+    }
+
+    /// Whatever non-JSON output was skipped, plus any unterminated tail
+    pub fn noise_preview(&self) -> String {
+        let mut out = self.noise.clone();
+        let room = NOISE_PREVIEW.saturating_sub(out.len());
+        out.extend_from_slice(&self.buf[..self.buf.len().min(room)]);
+        String::from_utf8_lossy(&out).trim().to_string()
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
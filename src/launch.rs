@@ -0,0 +1,330 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use crate::health;
+use crate::sandbox::Sandbox;
+use crate::settings::Settings;
+use crate::types::{HealthStatus, McpServer};
+
+/// Variables every launcher passes on, kept when checking in a clean
+/// environment. Without them most runtimes can't even start.
+pub const CLEAN_ENV_ALLOWLIST: &[&str] = &[
+    "PATH", "HOME", "USER", "LOGNAME", "SHELL", "TMPDIR", "LANG", "TERM",
+    // Windows equivalents
+    "SYSTEMROOT", "SYSTEMDRIVE", "WINDIR", "USERPROFILE", "APPDATA", "LOCALAPPDATA", "TEMP", "TMP",
+    "PATHEXT", "COMSPEC",
+];
+
+/// Which of mcpm's own environment variables a server inherits
+#[derive(Debug, Clone, Default)]
+pub enum Inherit {
+    /// Everything, like launching from this shell
+    #[default]
+    All,
+    /// Only these names
+    Only(Vec<String>),
+}
+
+/// How a server process is launched, beyond its command line and the
+/// config's `env`, which is always applied last
+#[derive(Debug, Clone, Default)]
+pub struct LaunchOptions {
+    pub inherit: Inherit,
+    /// Set after inheritance and before the config's env
+    pub env: HashMap<String, String>,
+    /// Working directory; None keeps mcpm's
+    pub cwd: Option<PathBuf>,
+    /// Run the server under these restrictions
+    pub sandbox: Option<Sandbox>,
+}
+
+impl LaunchOptions {
+    /// Minimal allowlist plus the config's env, nothing from this shell
+    pub fn clean() -> Self {
+        LaunchOptions {
+            inherit: Inherit::Only(CLEAN_ENV_ALLOWLIST.iter().map(|s| s.to_string()).collect()),
+            ..LaunchOptions::default()
+        }
+    }
+
+    /// What health checks launch with: sandboxed if the settings say so
+    pub fn for_checks(settings: &Settings) -> Self {
+        LaunchOptions {
+            sandbox: settings.sandbox.enabled.then(|| Sandbox::from_settings(&settings.sandbox)),
+            ..LaunchOptions::default()
+        }
+    }
+
+    pub fn apply(&self, cmd: &mut Command) {
+        if let Inherit::Only(names) = &self.inherit {
+            cmd.env_clear();
+            for name in names {
+                if let Ok(value) = std::env::var(name) {
+                    cmd.env(name, value);
+                }
+            }
+        }
+        cmd.envs(&self.env);
+        if let Some(dir) = &self.cwd {
+            cmd.current_dir(dir);
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Client launch profiles
+// ---------------------------------------------------------------------------
+
+/// Where a client's servers get their PATH from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathSource {
+    /// Same as mcpm — the client was started from a terminal
+    Inherit,
+    /// The user's login shell, which GUI editors run at startup to pick up
+    /// the shell profile (and with it nvm, pyenv, Homebrew…)
+    LoginShell,
+    /// The desktop session: launchd on macOS, the systemd user manager on
+    /// Linux. No shell profile is involved.
+    Session,
+}
+
+/// Working directory a client starts servers in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WorkDir {
+    Project,
+    Home,
+    Root,
+}
+
+/// How a client launches its servers
+#[derive(Debug, Clone, Copy)]
+pub struct LaunchProfile {
+    pub path: PathSource,
+    pub cwd: WorkDir,
+    /// Whether the server sees the client's whole environment or only the
+    /// basics (see CLEAN_ENV_ALLOWLIST)
+    pub full_env: bool,
+}
+
+/// Longest we wait for a login shell to print its PATH
+const SHELL_TIMEOUT: Duration = Duration::from_secs(5);
+
+impl LaunchProfile {
+    pub fn describe(&self) -> String {
+        let path = match self.path {
+            PathSource::Inherit => "PATH from this shell",
+            PathSource::LoginShell => "PATH from login shell",
+            PathSource::Session if cfg!(target_os = "macos") => "PATH from launchd",
+            PathSource::Session => "PATH from systemd user session",
+        };
+        let cwd = match self.cwd {
+            WorkDir::Project => "cwd project",
+            WorkDir::Home => "cwd home",
+            WorkDir::Root => "cwd /",
+        };
+        let env = if self.full_env { "full env" } else { "minimal env" };
+        format!("{}, {}, {}", path, cwd, env)
+    }
+
+    /// Turn the profile into launch options for this machine
+    pub fn resolve(&self, project: &Path) -> Result<LaunchOptions, String> {
+        let mut launch = if self.full_env {
+            LaunchOptions::default()
+        } else {
+            LaunchOptions::clean()
+        };
+        match self.path {
+            PathSource::Inherit => {}
+            PathSource::LoginShell => {
+                launch.env.insert("PATH".to_string(), login_shell_path()?);
+            }
+            PathSource::Session => {
+                launch.env.insert("PATH".to_string(), session_path());
+            }
+        }
+        launch.cwd = Some(match self.cwd {
+            WorkDir::Project => project.to_path_buf(),
+            WorkDir::Home => dirs::home_dir().ok_or("could not determine home directory")?,
+            WorkDir::Root => PathBuf::from("/"),
+        });
+        Ok(launch)
+    }
+}
+
+/// PATH as the user's login shell sets it up, asked once per run
+fn login_shell_path() -> Result<String, String> {
+    static CACHE: OnceLock<Result<String, String>> = OnceLock::new();
+    CACHE.get_or_init(query_login_shell_path).clone()
+}
+
+#[cfg(unix)]
+fn query_login_shell_path() -> Result<String, String> {
+    const MARKER: &str = "__MCPM_PATH__";
+    let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string());
+    // Interactive too, like the editors do — plenty of setups only touch
+    // PATH in .bashrc/.zshrc. The marker skips whatever the profile prints.
+    let mut child = Command::new(&shell)
+        .args(["-i", "-l", "-c", &format!("printf '{}%s' \"$PATH\"", MARKER)])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("failed to run {}: {}", shell, e))?;
+
+    let deadline = Instant::now() + SHELL_TIMEOUT;
+    while child.try_wait().map_err(|e| e.to_string())?.is_none() {
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(format!("{} -il did not finish within {}s", shell, SHELL_TIMEOUT.as_secs()));
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout
+        .rsplit_once(MARKER)
+        .map(|(_, path)| path.trim().to_string())
+        .filter(|path| !path.is_empty())
+        .ok_or_else(|| format!("{} did not report a PATH", shell))
+}
+
+#[cfg(not(unix))]
+fn query_login_shell_path() -> Result<String, String> {
+    // GUI apps on Windows get the same registry PATH as a terminal
+    Ok(std::env::var("PATH").unwrap_or_default())
+}
+
+/// PATH of the desktop session, falling back to the system default when
+/// the session manager can't be asked
+fn session_path() -> String {
+    if cfg!(target_os = "macos") {
+        let launchd = Command::new("launchctl")
+            .args(["getenv", "PATH"])
+            .output()
+            .ok()
+            .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+            .filter(|p| !p.is_empty());
+        launchd.unwrap_or_else(|| "/usr/bin:/bin:/usr/sbin:/sbin".to_string())
+    } else if cfg!(windows) {
+        std::env::var("PATH").unwrap_or_default()
+    } else {
+        let systemd = Command::new("systemctl")
+            .args(["--user", "show-environment"])
+            .output()
+            .ok()
+            .filter(|o| o.status.success())
+            .and_then(|o| {
+                String::from_utf8_lossy(&o.stdout)
+                    .lines()
+                    .find_map(|l| l.strip_prefix("PATH=").map(str::to_string))
+            });
+        systemd.unwrap_or_else(|| "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin".to_string())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Clean-environment diagnosis
+// ---------------------------------------------------------------------------
+
+/// Upper bound on extra checks spent narrowing down missing variables
+const MAX_PROBES: usize = 64;
+
+/// A normal health check compared with one in a clean environment
+pub struct CleanEnvReport {
+    pub normal: HealthStatus,
+    pub clean: HealthStatus,
+    /// Variables from this shell the server needs but its config doesn't
+    /// declare. None when it isn't the environment that makes the difference.
+    pub missing: Option<Vec<String>>,
+    /// MAX_PROBES ran out before `missing` was narrowed down, so it may
+    /// list variables the server doesn't need
+    pub probe_limit_hit: bool,
+}
+
+impl CleanEnvReport {
+    /// Healthy when launched from here, broken with only its declared env
+    pub fn depends_on_shell(&self) -> bool {
+        matches!(self.normal, HealthStatus::Healthy { .. })
+            && !matches!(self.clean, HealthStatus::Healthy { .. })
+    }
+}
+
+/// Check a server normally and in a clean environment. If only the normal
+/// check passes, add this shell's variables back in chunks until it works
+/// again, to find which ones it silently depends on. `on_probe` is called
+/// before every check, for progress output.
+pub fn diagnose_clean_env(server: &McpServer, mut on_probe: impl FnMut()) -> CleanEnvReport {
+    let mut check = |launch: &LaunchOptions| {
+        on_probe();
+        health::check_server_with(0, server, launch).status
+    };
+    let normal = check(&LaunchOptions::default());
+    let clean = check(&LaunchOptions::clean());
+    let mut report = CleanEnvReport {
+        normal,
+        clean,
+        missing: None,
+        probe_limit_hit: false,
+    };
+    if !report.depends_on_shell() {
+        return report;
+    }
+
+    let declared = server.env.as_ref();
+    let mut candidates: Vec<(String, String)> = std::env::vars()
+        .filter(|(k, _)| !CLEAN_ENV_ALLOWLIST.contains(&k.as_str()))
+        .filter(|(k, _)| !declared.is_some_and(|d| d.contains_key(k)))
+        .collect();
+    candidates.sort();
+
+    let mut probes = 0;
+    let mut limit_hit = false;
+    // Out of probes, every remaining chunk counts as needed — a superset
+    let mut works = |extra: &[(String, String)]| {
+        if probes >= MAX_PROBES {
+            limit_hit = true;
+            return false;
+        }
+        probes += 1;
+        let mut launch = LaunchOptions::clean();
+        launch.env = extra.iter().cloned().collect();
+        matches!(check(&launch), HealthStatus::Healthy { .. })
+    };
+    // Adding everything back must fix it, or something else differs
+    if !works(&candidates) {
+        return report;
+    }
+    report.missing = Some(minimize(candidates, works));
+    report.probe_limit_hit = limit_hit;
+    report
+}
+
+/// Shrink a set that passes `works` by dropping ever smaller chunks that
+/// aren't needed (a simplified delta debugging)
+fn minimize(
+    mut needed: Vec<(String, String)>,
+    mut works: impl FnMut(&[(String, String)]) -> bool,
+) -> Vec<String> {
+    let mut chunk = needed.len().div_ceil(2).max(1);
+    loop {
+        let mut i = 0;
+        while i < needed.len() {
+            let end = (i + chunk).min(needed.len());
+            let trial: Vec<_> = needed[..i].iter().chain(&needed[end..]).cloned().collect();
+            if works(&trial) {
+                needed = trial;
+            } else {
+                i = end;
+            }
+        }
+        if chunk == 1 {
+            break;
+        }
+        chunk = chunk.div_ceil(2);
+    }
+    needed.into_iter().map(|(k, _)| k).collect()
+}
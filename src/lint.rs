@@ -0,0 +1,409 @@
+use std::path::{Path, PathBuf};
+
+use serde_json::{Map, Value};
+
+use crate::discovery::{ConfigSource, pointer_token};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl Severity {
+    /// Also the SARIF `level`
+    pub fn label(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        }
+    }
+}
+
+pub struct Rule {
+    /// Stable identifier; never reused once published
+    pub id: &'static str,
+    pub name: &'static str,
+    pub severity: Severity,
+    pub description: &'static str,
+}
+
+pub const RULES: &[Rule] = &[
+    Rule {
+        id: "MCP001",
+        name: "invalid-json",
+        severity: Severity::Error,
+        description: "The config file is not valid JSON, so no server in it is loaded.",
+    },
+    Rule {
+        id: "MCP002",
+        name: "server-not-object",
+        severity: Severity::Warning,
+        description: "A server entry is not a JSON object and is skipped.",
+    },
+    Rule {
+        id: "MCP003",
+        name: "unknown-type",
+        severity: Severity::Error,
+        description: "The `type` field is not one of stdio, http or sse.",
+    },
+    Rule {
+        id: "MCP004",
+        name: "missing-command",
+        severity: Severity::Error,
+        description: "A stdio server has no `command`, or the entry has neither `command` nor `url`.",
+    },
+    Rule {
+        id: "MCP005",
+        name: "missing-url",
+        severity: Severity::Error,
+        description: "An http or sse server has no `url`.",
+    },
+    Rule {
+        id: "MCP006",
+        name: "command-and-url",
+        severity: Severity::Warning,
+        description: "Both `command` and `url` are set; only one of them is used.",
+    },
+    Rule {
+        id: "MCP007",
+        name: "args-not-string-array",
+        severity: Severity::Error,
+        description: "`args` is not an array of strings; non-string arguments are dropped.",
+    },
+    Rule {
+        id: "MCP008",
+        name: "env-not-string-map",
+        severity: Severity::Error,
+        description: "`env` is not an object of string values; other values are dropped.",
+    },
+    Rule {
+        id: "MCP009",
+        name: "headers-not-string-map",
+        severity: Severity::Error,
+        description: "`headers` is not an object of string values; other values are dropped.",
+    },
+    Rule {
+        id: "MCP010",
+        name: "invalid-url",
+        severity: Severity::Error,
+        description: "`url` is not an http:// or https:// URL with a host.",
+    },
+    Rule {
+        id: "MCP011",
+        name: "unknown-field",
+        severity: Severity::Note,
+        description: "A field no supported client reads; often a typo.",
+    },
+    Rule {
+        id: "MCP012",
+        name: "field-ignored",
+        severity: Severity::Warning,
+        description: "A field that has no effect for the server's transport.",
+    },
+    Rule {
+        id: "MCP013",
+        name: "command-with-spaces",
+        severity: Severity::Warning,
+        description: "`command` looks like a whole command line; clients run it as one executable name.",
+    },
+];
+
+/// Look a rule up by ID or name
+pub fn rule(key: &str) -> Option<&'static Rule> {
+    RULES
+        .iter()
+        .find(|r| r.id.eq_ignore_ascii_case(key) || r.name == key)
+}
+
+/// Server entry fields read by at least one supported client
+const KNOWN_FIELDS: &[&str] = &[
+    "type",
+    "command",
+    "args",
+    "env",
+    "envFile",
+    "cwd",
+    "url",
+    "headers",
+    "disabled",
+    "autoApprove",
+    "alwaysAllow",
+    "timeout",
+    "description",
+];
+
+pub struct Finding {
+    pub rule: &'static Rule,
+    pub message: String,
+    pub path: PathBuf,
+    /// JSON pointer to the offending value; empty for the whole file
+    pub pointer: String,
+    /// 1-based line in the file
+    pub line: usize,
+}
+
+/// Lint every source, skipping rules in `disabled` (IDs or names)
+pub fn lint(sources: &[ConfigSource], disabled: &[String]) -> Vec<Finding> {
+    let disabled: Vec<&str> = disabled.iter().filter_map(|d| rule(d)).map(|r| r.id).collect();
+    let mut findings = Vec::new();
+
+    for source in sources {
+        let mut found = Vec::new();
+        match &source.root {
+            Err(e) => found.push(Finding {
+                rule: &RULES[0],
+                message: format!("invalid JSON: {}", e),
+                path: source.path.clone(),
+                pointer: String::new(),
+                line: e.line().max(1),
+            }),
+            Ok(root) => {
+                for map_pointer in &source.maps {
+                    let Some(map) = root.pointer(map_pointer).and_then(Value::as_object) else {
+                        continue;
+                    };
+                    // A flat file has other top-level keys (e.g. "$schema") that
+                    // were never meant as servers
+                    let flat = map_pointer.is_empty();
+                    for (name, entry) in map {
+                        let pointer = format!("{}/{}", map_pointer, pointer_token(name));
+                        lint_entry(name, entry, flat, &mut |id, field: &str, message| {
+                            let pointer = if field.is_empty() {
+                                pointer.clone()
+                            } else {
+                                format!("{}/{}", pointer, field)
+                            };
+                            found.push(Finding {
+                                rule: rule(id).expect("rule is defined"),
+                                message,
+                                path: source.path.clone(),
+                                line: line_of(&source.text, &pointer),
+                                pointer,
+                            });
+                        });
+                    }
+                }
+            }
+        }
+        found.retain(|f| !disabled.contains(&f.rule.id));
+        found.sort_by(|a, b| a.line.cmp(&b.line).then(a.rule.id.cmp(b.rule.id)));
+        findings.extend(found);
+    }
+    findings
+}
+
+/// Check one server entry. `report(rule, field, message)` takes the field's
+/// pointer relative to the entry ("" for the entry itself).
+fn lint_entry(name: &str, entry: &Value, flat: bool, report: &mut dyn FnMut(&str, &str, String)) {
+    let Some(obj) = entry.as_object() else {
+        if !flat {
+            report("MCP002", "", format!("server \"{}\" is not an object and is skipped", name));
+        }
+        return;
+    };
+
+    let ttype = match obj.get("type") {
+        None => None,
+        Some(Value::String(t)) if matches!(t.as_str(), "stdio" | "http" | "sse") => Some(t.as_str()),
+        Some(Value::String(t)) => {
+            report(
+                "MCP003",
+                "type",
+                format!("unknown type \"{}\" (expected stdio, http or sse)", t),
+            );
+            return;
+        }
+        Some(other) => {
+            report("MCP003", "type", format!("type must be a string, not {}", kind(other)));
+            return;
+        }
+    };
+
+    // Mirror the order discovery decides the transport in
+    let has_command = obj.contains_key("command");
+    let has_url = obj.contains_key("url");
+    let remote = match ttype {
+        Some("http" | "sse") => true,
+        Some(_) => false,
+        None if has_command => false,
+        None if has_url => true,
+        None => {
+            report(
+                "MCP004",
+                "",
+                format!("server \"{}\" has neither command nor url, so it can't be started", name),
+            );
+            return;
+        }
+    };
+
+    if has_command && has_url {
+        let used = if remote { "url" } else { "command" };
+        report("MCP006", "", format!("both command and url are set; {} is used", used));
+    }
+
+    if remote {
+        lint_url(obj, ttype.unwrap_or("http"), report);
+        lint_string_map(obj, "headers", "MCP009", report);
+        for field in ["args", "cwd"] {
+            if obj.contains_key(field) {
+                report("MCP012", field, format!("{} has no effect on a remote server", field));
+            }
+        }
+    } else {
+        lint_command(obj, report);
+        lint_args(obj, report);
+        if obj.contains_key("headers") {
+            report("MCP012", "headers", "headers has no effect on a stdio server".to_string());
+        }
+    }
+    lint_string_map(obj, "env", "MCP008", report);
+
+    for key in obj.keys() {
+        if !KNOWN_FIELDS.contains(&key.as_str()) {
+            report("MCP011", &pointer_token(key), format!("unknown field \"{}\"", key));
+        }
+    }
+}
+
+fn lint_command(obj: &Map<String, Value>, report: &mut dyn FnMut(&str, &str, String)) {
+    let command = match obj.get("command") {
+        None => {
+            report("MCP004", "", "stdio server has no command".to_string());
+            return;
+        }
+        Some(Value::String(c)) if !c.trim().is_empty() => c,
+        Some(Value::String(_)) => {
+            report("MCP004", "command", "command is empty".to_string());
+            return;
+        }
+        Some(other) => {
+            report("MCP004", "command", format!("command must be a string, not {}", kind(other)));
+            return;
+        }
+    };
+
+    let no_args = obj.get("args").and_then(Value::as_array).is_none_or(Vec::is_empty);
+    // A path with spaces in it is fine as long as it exists
+    if no_args && command.trim().contains(char::is_whitespace) && !Path::new(command).exists() {
+        report(
+            "MCP013",
+            "command",
+            format!("command \"{}\" contains spaces; put the arguments in args", command),
+        );
+    }
+}
+
+fn lint_args(obj: &Map<String, Value>, report: &mut dyn FnMut(&str, &str, String)) {
+    match obj.get("args") {
+        None => {}
+        Some(Value::Array(args)) => {
+            for (i, arg) in args.iter().enumerate() {
+                if !arg.is_string() {
+                    report(
+                        "MCP007",
+                        &format!("args/{}", i),
+                        format!("args[{}] is {}, not a string; it is dropped", i, kind(arg)),
+                    );
+                }
+            }
+        }
+        Some(other) => {
+            report("MCP007", "args", format!("args must be an array of strings, not {}", kind(other)));
+        }
+    }
+}
+
+fn lint_url(obj: &Map<String, Value>, ttype: &str, report: &mut dyn FnMut(&str, &str, String)) {
+    let url = match obj.get("url") {
+        None => {
+            report("MCP005", "", format!("{} server has no url", ttype));
+            return;
+        }
+        Some(Value::String(u)) if !u.trim().is_empty() => u,
+        Some(Value::String(_)) => {
+            report("MCP005", "url", "url is empty".to_string());
+            return;
+        }
+        Some(other) => {
+            report("MCP005", "url", format!("url must be a string, not {}", kind(other)));
+            return;
+        }
+    };
+
+    let host = url
+        .strip_prefix("http://")
+        .or_else(|| url.strip_prefix("https://"))
+        .map(|rest| rest.split(['/', '?', '#']).next().unwrap_or(""));
+    match host {
+        None => report("MCP010", "url", format!("url \"{}\" is not http:// or https://", url)),
+        Some("") => report("MCP010", "url", format!("url \"{}\" has no host", url)),
+        Some(h) if h.contains(char::is_whitespace) => {
+            report("MCP010", "url", format!("url \"{}\" contains spaces", url))
+        }
+        Some(_) => {}
+    }
+}
+
+fn lint_string_map(
+    obj: &Map<String, Value>,
+    field: &str,
+    id: &str,
+    report: &mut dyn FnMut(&str, &str, String),
+) {
+    match obj.get(field) {
+        None => {}
+        Some(Value::Object(map)) => {
+            for (key, value) in map {
+                if !value.is_string() {
+                    report(
+                        id,
+                        &format!("{}/{}", field, pointer_token(key)),
+                        format!("{}.{} is {}, not a string; it is dropped", field, key, kind(value)),
+                    );
+                }
+            }
+        }
+        Some(other) => {
+            report(id, field, format!("{} must be an object of strings, not {}", field, kind(other)));
+        }
+    }
+}
+
+fn kind(v: &Value) -> &'static str {
+    match v {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+/// Approximate line of the value at `pointer`, found by searching for each
+/// key in turn. Array indices stay on the array's line.
+fn line_of(text: &str, pointer: &str) -> usize {
+    let mut pos = 0;
+    for token in pointer.split('/').skip(1) {
+        let key = token.replace("~1", "/").replace("~0", "~");
+        if key.parse::<usize>().is_ok() {
+            continue;
+        }
+        let quoted = serde_json::to_string(&key).unwrap_or_default();
+        let mut from = pos;
+        while let Some(i) = text[from..].find(&quoted) {
+            let at = from + i;
+            let after = text[at + quoted.len()..].trim_start();
+            if after.starts_with(':') {
+                pos = at;
+                break;
+            }
+            from = at + quoted.len();
+        }
+    }
+    text[..pos].matches('\n').count() + 1
+}
@@ -1,20 +1,43 @@
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
 
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use crossterm::{
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
 
+mod analysis;
 mod app;
+mod audit;
+mod bench;
+mod bridge;
 mod config_writer;
 mod discovery;
+mod endpoint;
+mod gateway;
 mod health;
+mod inspector;
+mod jsonrpc;
+mod launch;
+mod lint;
+mod mcp;
+mod mock;
+mod oauth;
+mod pins;
+#[cfg(target_os = "linux")]
+mod procfs;
+mod proxy;
+mod remote;
+mod sandbox;
+mod secrets;
+mod settings;
+mod state;
 mod types;
 mod ui;
+mod vault;
 mod wizard;
 
 #[derive(Parser)]
@@ -22,14 +45,365 @@ mod wizard;
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
+    /// Show tokens, passwords and other secret values instead of masking them
+    #[arg(long, global = true)]
+    show_secrets: bool,
 }
 
 #[derive(Subcommand)]
 enum Commands {
-    /// List all discovered MCP servers as plain text (no TUI)
+    /// List all discovered MCP servers (no TUI)
+    List {
+        /// Output format; json includes estimated context cost per server and client
+        #[arg(long, value_enum, default_value_t = ListFormat::Text)]
+        format: ListFormat,
+    },
+    /// Run health checks on all stdio and HTTP servers and print results
+    Check {
+        /// Print flapping or recently regressed servers from recorded history
+        /// instead of running checks
+        #[arg(long, conflicts_with = "clean_env")]
+        history: bool,
+        /// Also check each server with only a minimal environment plus its
+        /// configured `env`, and name the undeclared variables it relies on
+        #[arg(long)]
+        clean_env: bool,
+        /// Launch servers the way a client would: its PATH source, working
+        /// directory and environment. `own` uses each server's own client.
+        /// Results are not recorded in history.
+        #[arg(long = "as", value_name = "CLIENT", conflicts_with_all = ["history", "clean_env"])]
+        launch_as: Option<LaunchAs>,
+        /// Check stdio servers with no network beyond `sandbox.allow_hosts`,
+        /// a read-only home and memory and CPU limits (Linux; bubblewrap
+        /// when installed, namespaces otherwise). Results are not recorded
+        /// in history.
+        #[arg(long, conflicts_with_all = ["history", "clean_env", "launch_as"])]
+        sandbox: bool,
+    },
+    /// Report problems across clients: colliding tool names, names over
+    /// client limits and clients loading too many tools, using tool lists
+    /// recorded by `mcpm check`
+    Doctor,
+    /// Review configured servers for risky patterns: plaintext secrets,
+    /// unencrypted remote URLs, shell wrappers, unpinned packages and
+    /// filesystem servers with broad access
+    Audit {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = ListFormat::Text)]
+        format: ListFormat,
+        /// Exit 1 when a finding is at least this severe
+        #[arg(long, value_enum, default_value_t = AuditLevel::Medium)]
+        fail_on: AuditLevel,
+    },
+    /// Tool definitions and launch configs pinned at the first successful
+    /// check; review, accept or reject what changed since
+    Pins {
+        #[command(subcommand)]
+        action: Option<PinsAction>,
+    },
+    /// Check config files for entries clients would reject or misread.
+    /// Rules can be disabled per project in .mcpm.json.
+    Lint {
+        /// Output format; sarif is for code scanning tools
+        #[arg(long, value_enum, default_value_t = LintFormat::Text)]
+        format: LintFormat,
+        /// Only lint configs inside the current directory
+        #[arg(long)]
+        project: bool,
+        /// Skip a rule by ID or name, repeatable
+        #[arg(long, value_name = "RULE")]
+        disable: Vec<String>,
+    },
+    /// Start a stdio server repeatedly and report startup latency percentiles
+    Bench {
+        /// Server name, as shown by `mcpm list`
+        server: String,
+        /// Number of start → handshake → shutdown cycles
+        #[arg(long, default_value_t = 10, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+        runs: usize,
+        /// Another configured server, or a quoted command line, to benchmark
+        /// against (e.g. a local install of the same package)
+        #[arg(long)]
+        compare: Option<String>,
+    },
+    /// List a stdio server's resources and resource templates, or read one
+    Resources {
+        /// Server name, as shown by `mcpm list`
+        server: String,
+        /// Read the resource with this URI instead of listing
+        #[arg(long, value_name = "URI")]
+        read: Option<String>,
+    },
+    /// List a stdio server's prompts, or render one
+    Prompts {
+        /// Server name, as shown by `mcpm list`
+        server: String,
+        /// Render the prompt with this name instead of listing
+        #[arg(long, value_name = "NAME")]
+        get: Option<String>,
+        /// Prompt argument, repeatable
+        #[arg(long = "arg", value_name = "KEY=VALUE", requires = "get")]
+        args: Vec<String>,
+    },
+    /// Run a scripted MCP server for testing clients and mcpm itself
+    MockServer {
+        /// JSON script with tools, resources and prompts (or just an array
+        /// of tools). Without one, a single `echo` tool is served.
+        #[arg(long, value_name = "FILE")]
+        tools: Option<PathBuf>,
+        /// Wait this long before every response
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        delay_ms: u64,
+        /// Answer `initialize` with an error
+        #[arg(long)]
+        fail_initialize: bool,
+        /// Never answer `initialize`
+        #[arg(long)]
+        hang: bool,
+        /// Answer `initialize` with a broken frame, then exit
+        #[arg(long)]
+        malformed: bool,
+        /// Exit without answering `initialize`
+        #[arg(long)]
+        crash: bool,
+        /// Write to stderr continuously
+        #[arg(long)]
+        stderr_spam: bool,
+        /// Ignore stdin closing and SIGTERM, so only SIGKILL stops it
+        #[arg(long)]
+        ignore_shutdown: bool,
+        /// Serve Streamable HTTP on this port (0 picks one) instead of stdio
+        #[arg(long, value_name = "PORT")]
+        http: Option<u16>,
+        /// Require an OAuth bearer token, and act as the authorization server
+        /// that issues it (needs --http)
+        #[arg(long, requires = "http")]
+        oauth: bool,
+    },
+    /// Route a stdio server through mcpm, logging every JSON-RPC message.
+    /// Rewrites the server's config entry until `--remove`.
+    Proxy {
+        /// Server name, as shown by `mcpm list`
+        server: String,
+        /// Which client's entry to wrap when several have this server, by
+        /// its label in `mcpm list` (e.g. Desktop, Cursor, CC-Project)
+        #[arg(long)]
+        client: Option<String>,
+        /// Restore the original command
+        #[arg(long)]
+        remove: bool,
+    },
+    /// The pass-through a proxied config entry runs
+    #[command(name = "proxy-run", hide = true)]
+    ProxyRun {
+        #[arg(long)]
+        log_dir: PathBuf,
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true, required = true)]
+        command: Vec<String>,
+    },
+    /// Sets up the sandbox a sandboxed check runs the server in
+    #[command(name = "sandbox-exec", hide = true)]
+    SandboxExec {
+        /// Create the namespaces here rather than relying on bubblewrap
+        #[arg(long)]
+        unshare: bool,
+        #[arg(long)]
+        writable: Vec<PathBuf>,
+        #[arg(long)]
+        proxy_socket: Option<PathBuf>,
+        #[arg(long)]
+        memory_mb: Option<u64>,
+        #[arg(long)]
+        cpu_secs: Option<u64>,
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true, required = true)]
+        command: Vec<String>,
+    },
+    /// Run one MCP server that fronts several configured stdio servers, with
+    /// their tools and prompts renamed `<server>__<name>`
+    Serve {
+        /// Aggregate every stdio server of this client, by its label in
+        /// `mcpm list` (e.g. Desktop, Cursor, CC-Project)
+        #[arg(long, required_unless_present = "servers", conflicts_with = "servers")]
+        client: Option<String>,
+        /// Aggregate these servers, comma-separated
+        #[arg(long, value_delimiter = ',', value_name = "NAMES")]
+        servers: Vec<String>,
+        /// Serve Streamable HTTP on this port (0 picks one) instead of stdio
+        #[arg(long, value_name = "PORT")]
+        http: Option<u16>,
+    },
+    /// Cross transports: expose an HTTP/SSE server on stdio, or serve a
+    /// stdio server as Streamable HTTP on localhost
+    Bridge {
+        /// Server name, as shown by `mcpm list`
+        #[arg(required_unless_present = "url", conflicts_with = "url")]
+        server: Option<String>,
+        /// Bridge this remote URL to stdio instead of a configured server
+        #[arg(long)]
+        url: Option<String>,
+        /// The URL speaks the older HTTP+SSE transport
+        #[arg(long, requires = "url")]
+        sse: bool,
+        /// Request header for the remote server, as "Name: value" (repeatable)
+        #[arg(long = "header", value_name = "HEADER", requires = "url")]
+        headers: Vec<String>,
+        /// Port to serve a stdio server on (0 picks one)
+        #[arg(long, default_value_t = 0)]
+        port: u16,
+    },
+    /// Log in to an HTTP server that requires OAuth: discover its
+    /// authorization server, run the browser flow on a loopback redirect and
+    /// put the token into the entry's `headers`
+    Login {
+        /// Server name, as shown by `mcpm list`
+        server: String,
+        /// Which client's entry to update when several have this server, by
+        /// its label in `mcpm list` (e.g. Desktop, Cursor, CC-Project)
+        #[arg(long)]
+        client: Option<String>,
+        /// OAuth client id, for authorization servers without dynamic
+        /// client registration
+        #[arg(long)]
+        client_id: Option<String>,
+        /// Print the token instead of writing it to the config
+        #[arg(long)]
+        print: bool,
+        /// Don't open a browser; just print the authorization URL
+        #[arg(long)]
+        no_browser: bool,
+    },
+    /// Manage the local secret vault that `${secret:name}` placeholders in
+    /// configs refer to
+    Secret {
+        #[command(subcommand)]
+        action: SecretAction,
+    },
+    /// Resolve `${secret:name}` placeholders in client configs: an env var
+    /// reference for clients that expand them, the secret itself for
+    /// clients that don't
+    Materialize {
+        /// Only this client, by its label in `mcpm list`
+        #[arg(long)]
+        client: Option<String>,
+        /// Show which entries would change without writing
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Run mcpm itself as an MCP server on stdio, with tools to list, check,
+    /// add, remove and sync servers and to show config drift
+    Mcp {
+        /// Only offer the tools that don't change any config
+        #[arg(long)]
+        read_only: bool,
+    },
+    /// Play back the server side of a proxy log on stdio
+    Replay {
+        /// Log file, or a proxied server's name for its newest log
+        log: String,
+        /// Keep the recorded delay before each response
+        #[arg(long)]
+        realtime: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum SecretAction {
+    /// Store a secret, read from stdin or a hidden prompt
+    Set {
+        /// Name used in `${secret:name}`
+        name: String,
+    },
+    /// Print a secret's value
+    Get { name: String },
+    /// List secret names
     List,
-    /// Run health checks on all stdio servers and print results
-    Check,
+    /// Delete a secret
+    Rm { name: String },
+    /// Encrypt the vault with a passphrase
+    Encrypt,
+    /// Store the vault unencrypted again
+    Decrypt,
+    /// Print `export NAME=value` lines for clients that read secrets from
+    /// the environment
+    Env,
+}
+
+#[derive(Subcommand)]
+enum PinsAction {
+    /// Each server's pin status, with a diff of anything that changed
+    /// (the default)
+    Show {
+        /// Only this server
+        server: Option<String>,
+    },
+    /// Trust a server's current tool definitions and config
+    Accept {
+        server: String,
+        /// Only its entry in this client, by its label in `mcpm list`
+        #[arg(long)]
+        client: Option<String>,
+    },
+    /// Keep the pin and mark the current definitions as rejected
+    Reject {
+        server: String,
+        /// Only its entry in this client, by its label in `mcpm list`
+        #[arg(long)]
+        client: Option<String>,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ListFormat {
+    Text,
+    Json,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum AuditLevel {
+    Low,
+    Medium,
+    High,
+}
+
+impl AuditLevel {
+    fn severity(self) -> audit::Severity {
+        match self {
+            AuditLevel::Low => audit::Severity::Low,
+            AuditLevel::Medium => audit::Severity::Medium,
+            AuditLevel::High => audit::Severity::High,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum LintFormat {
+    Text,
+    Json,
+    Sarif,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum LaunchAs {
+    Own,
+    ClaudeCode,
+    Desktop,
+    Cursor,
+    Vscode,
+    Windsurf,
+}
+
+impl LaunchAs {
+    /// Client whose profile to use; None means each server's own
+    fn client(self) -> Option<types::ClientKind> {
+        match self {
+            LaunchAs::Own => None,
+            LaunchAs::ClaudeCode => Some(types::ClientKind::ClaudeCodeProject),
+            LaunchAs::Desktop => Some(types::ClientKind::ClaudeDesktop),
+            LaunchAs::Cursor => Some(types::ClientKind::CursorProject),
+            LaunchAs::Vscode => Some(types::ClientKind::VsCodeProject),
+            LaunchAs::Windsurf => Some(types::ClientKind::Windsurf),
+        }
+    }
 }
 
 fn main() -> ExitCode {
@@ -37,12 +411,127 @@ fn main() -> ExitCode {
     let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
 
     match cli.command {
-        Some(Commands::List) => {
+        Some(Commands::List { format: ListFormat::Text }) => {
             cmd_list(&cwd);
             ExitCode::SUCCESS
         }
-        Some(Commands::Check) => cmd_check(&cwd),
-        None => match run_tui(cwd) {
+        Some(Commands::List { format: ListFormat::Json }) => {
+            cmd_list_json(&cwd, cli.show_secrets);
+            ExitCode::SUCCESS
+        }
+        Some(Commands::Check { history: true, .. }) => cmd_check_history(&cwd),
+        Some(Commands::Check { clean_env: true, .. }) => cmd_check_clean_env(&cwd),
+        Some(Commands::Check { launch_as, sandbox, .. }) => cmd_check(&cwd, launch_as, sandbox),
+        Some(Commands::Doctor) => cmd_doctor(&cwd),
+        Some(Commands::Audit { format, fail_on }) => cmd_audit(&cwd, format, fail_on),
+        Some(Commands::Pins { action }) => cmd_pins(&cwd, action.unwrap_or(PinsAction::Show { server: None })),
+        Some(Commands::Lint {
+            format,
+            project,
+            disable,
+        }) => cmd_lint(&cwd, format, project, disable),
+        Some(Commands::Bench {
+            server,
+            runs,
+            compare,
+        }) => cmd_bench(&cwd, &server, runs, compare.as_deref(), cli.show_secrets),
+        Some(Commands::Resources { server, read }) => cmd_resources(&cwd, &server, read.as_deref()),
+        Some(Commands::Prompts { server, get, args }) => {
+            cmd_prompts(&cwd, &server, get.as_deref(), &args)
+        }
+        Some(Commands::MockServer {
+            tools,
+            delay_ms,
+            fail_initialize,
+            hang,
+            malformed,
+            crash,
+            stderr_spam,
+            ignore_shutdown,
+            http,
+            oauth,
+        }) => {
+            let faults = mock::Faults {
+                delay: std::time::Duration::from_millis(delay_ms),
+                fail_initialize,
+                hang,
+                malformed,
+                crash,
+                stderr_spam,
+                ignore_shutdown,
+            };
+            cmd_mock_server(tools.as_deref(), faults, http, oauth)
+        }
+        Some(Commands::Proxy {
+            server,
+            client,
+            remove,
+        }) => cmd_proxy(&cwd, &server, client.as_deref(), remove),
+        Some(Commands::ProxyRun { log_dir, command }) => {
+            match proxy::run(&log_dir, &command[0], &command[1..]) {
+                Ok(code) => ExitCode::from(code.clamp(0, 255) as u8),
+                Err(e) => {
+                    eprintln!("mcpm proxy: {}", e);
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        Some(Commands::SandboxExec {
+            unshare,
+            writable,
+            proxy_socket,
+            memory_mb,
+            cpu_secs,
+            command,
+        }) => {
+            let inner = sandbox::Inner {
+                unshare,
+                writable,
+                proxy_socket,
+                memory_mb,
+                cpu_secs,
+            };
+            match sandbox::exec(&inner, &command[0], &command[1..]) {
+                Ok(code) => ExitCode::from(code.clamp(0, 255) as u8),
+                Err(e) => {
+                    eprintln!("mcpm sandbox: {}", e);
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        Some(Commands::Serve {
+            client,
+            servers,
+            http,
+        }) => cmd_serve(&cwd, client.as_deref(), &servers, http),
+        Some(Commands::Bridge {
+            server,
+            url,
+            sse,
+            headers,
+            port,
+        }) => cmd_bridge(&cwd, server.as_deref(), url.as_deref(), sse, &headers, port),
+        Some(Commands::Login {
+            server,
+            client,
+            client_id,
+            print,
+            no_browser,
+        }) => cmd_login(&cwd, &server, client.as_deref(), client_id.as_deref(), print, no_browser),
+        Some(Commands::Secret { action }) => cmd_secret(action),
+        Some(Commands::Materialize { client, dry_run }) => cmd_materialize(&cwd, client.as_deref(), dry_run),
+        Some(Commands::Mcp { read_only }) => {
+            let server = std::sync::Arc::new(mcp::ManageServer::new(cwd, read_only));
+            match endpoint::serve_stdio(server) {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(e) => {
+                    eprintln!("mcpm mcp: {}", e);
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        Some(Commands::Replay { log, realtime }) => cmd_replay(&log, realtime),
+        None => match run_tui(cwd, cli.show_secrets) {
             Ok(()) => ExitCode::SUCCESS,
             Err(e) => {
                 eprintln!("Error: {}", e);
@@ -52,15 +541,15 @@ fn main() -> ExitCode {
     }
 }
 
-fn cmd_list(cwd: &PathBuf) {
+fn cmd_list(cwd: &Path) {
     let result = discovery::discover(cwd);
 
     if result.servers.is_empty() {
         println!("No MCP servers found.");
     } else {
         println!(
-            "{:<25} {:>12}  {:<8}  {}",
-            "SERVER", "CLIENT", "TYPE", "SOURCE"
+            "{:<25} {:>12}  {:<8}  SOURCE",
+            "SERVER", "CLIENT", "TYPE"
         );
         println!("{}", "-".repeat(80));
         for s in &result.servers {
@@ -82,31 +571,165 @@ fn cmd_list(cwd: &PathBuf) {
     }
 }
 
-fn cmd_check(cwd: &PathBuf) -> ExitCode {
+/// Settings for CLI commands; an invalid config file is reported and ignored
+fn load_settings() -> settings::Settings {
+    settings::Settings::load().unwrap_or_else(|e| {
+        eprintln!("warning: ignoring invalid config: {}", e);
+        settings::Settings::default()
+    })
+}
+
+/// command/args or url for list output, masked unless asked not to
+fn launch_fields(transport: &types::Transport, show_secrets: bool) -> serde_json::Value {
+    match transport {
+        types::Transport::Stdio { command, args } => serde_json::json!({
+            "command": command,
+            "args": if show_secrets { args.clone() } else { secrets::mask_args(args) },
+        }),
+        types::Transport::Http { url, .. } | types::Transport::Sse { url } => serde_json::json!({
+            "url": if show_secrets { url.clone() } else { secrets::mask_url(url) },
+        }),
+        types::Transport::Unknown => serde_json::json!({}),
+    }
+}
+
+fn cmd_list_json(cwd: &Path, show_secrets: bool) {
+    let result = discovery::discover(cwd);
+    let state = state::State::load();
+    let settings = load_settings();
+    let context = analysis::ContextReport::build(&result.servers, &state, settings.tool_token_budget());
+
+    let servers: Vec<serde_json::Value> = context
+        .servers
+        .iter()
+        .map(|c| {
+            let launch = launch_fields(&c.server.transport, show_secrets);
+            serde_json::json!({
+                "name": c.server.name,
+                "client": c.server.client.label(),
+                "transport": c.server.transport.kind_label(),
+                "source": c.server.source_path,
+                "command": launch["command"],
+                "args": launch["args"],
+                "url": launch["url"],
+                "tool_count": c.tool_count,
+                "estimated_tokens": c.tokens,
+                "over_budget": c.over_budget,
+            })
+        })
+        .collect();
+    let clients: Vec<serde_json::Value> = context
+        .clients
+        .iter()
+        .map(|c| {
+            serde_json::json!({
+                "client": c.client.label(),
+                "estimated_tokens": c.tokens,
+                "servers_without_tool_data": c.unknown,
+            })
+        })
+        .collect();
+
+    let out = serde_json::json!({
+        "servers": servers,
+        "clients": clients,
+        "tool_token_budget": context.budget,
+        "errors": result.errors,
+    });
+    println!("{}", serde_json::to_string_pretty(&out).unwrap_or_default());
+}
+
+fn cmd_check(cwd: &Path, launch_as: Option<LaunchAs>, sandbox_only: bool) -> ExitCode {
     let result = discovery::discover(cwd);
+    let settings = load_settings();
+    let sandbox = (sandbox_only || settings.sandbox.enabled).then(|| sandbox::Sandbox::from_settings(&settings.sandbox));
+    // Fail closed rather than quietly checking without restrictions
+    let backend = match sandbox.as_ref().map(|_| sandbox::backend_name()).transpose() {
+        Ok(backend) => backend,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let stdio_only = launch_as.is_some() || sandbox_only;
+    // A simulated launch says nothing about how the server normally runs
+    let record = launch_as.is_none() && !sandbox_only;
 
-    let stdio_servers: Vec<(usize, &types::McpServer)> = result
+    // Launch profiles and sandboxes only mean something for servers mcpm spawns
+    let targets: Vec<(usize, &types::McpServer)> = result
         .servers
         .iter()
         .enumerate()
-        .filter(|(_, s)| s.transport.is_stdio())
+        .filter(|(_, s)| if stdio_only { s.transport.is_stdio() } else { s.transport.is_checkable() })
         .collect();
 
-    if stdio_servers.is_empty() {
-        println!("No stdio servers found to health check.");
+    if targets.is_empty() {
+        let kind = if stdio_only { "stdio servers" } else { "servers" };
+        println!("No {} found to health check.", kind);
         return ExitCode::SUCCESS;
     }
 
-    println!(
-        "Checking {} stdio server{}...\n",
-        stdio_servers.len(),
-        if stdio_servers.len() == 1 { "" } else { "s" }
-    );
+    let fixed_client = launch_as.and_then(LaunchAs::client);
+    let plural = if targets.len() == 1 { "" } else { "s" };
+    match (launch_as, &fixed_client) {
+        (None, _) if sandbox_only => println!("Checking {} stdio server{} in a sandbox...", targets.len(), plural),
+        (None, _) => println!("Checking {} server{}...", targets.len(), plural),
+        (Some(_), Some(client)) => println!(
+            "Checking {} stdio server{} as {} would launch them ({})...",
+            targets.len(),
+            plural,
+            client.app_name(),
+            client.launch_profile().describe()
+        ),
+        (Some(_), None) => println!(
+            "Checking {} stdio server{} as their own clients would launch them...",
+            targets.len(),
+            plural
+        ),
+    }
+    if let (Some(sandbox), Some(backend)) = (&sandbox, backend) {
+        println!("\x1b[90mSandboxed with {}: {}\x1b[0m", backend, sandbox.describe());
+    }
+    println!();
 
     let mut any_failed = false;
+    let mut state = state::State::load();
 
-    for (i, server) in &stdio_servers {
-        let hr = health::check_server(*i, server);
+    for (i, server) in &targets {
+        let hr = match launch_as {
+            None => health::check_server_with(
+                *i,
+                server,
+                &launch::LaunchOptions {
+                    sandbox: sandbox.clone(),
+                    ..launch::LaunchOptions::default()
+                },
+            ),
+            Some(_) => {
+                let client = fixed_client.as_ref().unwrap_or(&server.client);
+                match client.launch_profile().resolve(cwd) {
+                    Ok(launch) => health::check_server_with(
+                        *i,
+                        server,
+                        &launch::LaunchOptions {
+                            sandbox: sandbox.clone(),
+                            ..launch
+                        },
+                    ),
+                    Err(e) => {
+                        println!("  \x1b[31m✗\x1b[0m {:<25} launch profile: {}", server.name, e);
+                        any_failed = true;
+                        continue;
+                    }
+                }
+            }
+        };
+        if record {
+            state.record(server, &hr.status, hr.checked_at);
+            if let Some(tools) = hr.tools {
+                state.record_tools(server, tools, hr.checked_at);
+            }
+        }
         match &hr.status {
             types::HealthStatus::Healthy {
                 server_name,
@@ -125,8 +748,69 @@ fn cmd_check(cwd: &PathBuf) -> ExitCode {
                 println!("  \x1b[31m✗\x1b[0m {:<25} {}", server.name, e);
                 any_failed = true;
             }
+            // Reachable and telling us how to log in; not a failure
+            status @ types::HealthStatus::AuthRequired { .. } => {
+                println!("  \x1b[36m◆\x1b[0m {:<25} {}", server.name, status.label());
+                println!("    \x1b[90mlog in with: mcpm login {}\x1b[0m", server.name);
+            }
             _ => {}
         }
+        if record {
+            match pins::verify(&state, server) {
+                pins::Verdict::Changed(changes) => {
+                    let summary: Vec<String> = changes.iter().map(pins::Change::summary).collect();
+                    println!("    \x1b[33m⚠\x1b[0m changed since pinned: {}", summary.join(", "));
+                    println!("    \x1b[90mreview with: mcpm pins show {}\x1b[0m", server.name);
+                    any_failed = true;
+                }
+                pins::Verdict::Rejected(_) => {
+                    println!("    \x1b[31m✗\x1b[0m runs definitions you rejected");
+                    println!("    \x1b[90mreview with: mcpm pins show {}\x1b[0m", server.name);
+                    any_failed = true;
+                }
+                pins::Verdict::Unpinned | pins::Verdict::Trusted => {}
+            }
+        }
+        if launch_as.is_some() && fixed_client.is_none() {
+            println!(
+                "    \x1b[90mas {}: {}\x1b[0m",
+                server.client.app_name(),
+                server.client.launch_profile().describe()
+            );
+        }
+        let metrics = hr.metrics.parts();
+        if !metrics.is_empty() {
+            let summary: Vec<String> = metrics
+                .iter()
+                .map(|(label, value)| format!("{} {}", label, value))
+                .collect();
+            println!("    \x1b[90m{}\x1b[0m", summary.join(" · "));
+        }
+        if hr.cleanup == Some(types::Cleanup::Leaked) {
+            println!(
+                "    \x1b[33m⚠\x1b[0m processes still running after SIGKILL — check for orphans"
+            );
+        }
+    }
+
+    if let Err(e) = state.save() {
+        eprintln!("warning: failed to save health history: {}", e);
+    }
+
+    let excess = analysis::tool_limit_excess(&result.servers, &state);
+    if !excess.is_empty() {
+        println!();
+        for e in &excess {
+            println!("  \x1b[33m⚠\x1b[0m {}", e.summary());
+        }
+    }
+
+    if any_failed && sandbox.is_some() {
+        println!();
+        println!(
+            "  \x1b[90mA server that needs the network or writes files can be given\n  sandbox.allow_hosts and sandbox.writable in {}\x1b[0m",
+            settings::Settings::path().map(|p| p.display().to_string()).unwrap_or_else(|| "the config".to_string())
+        );
     }
 
     println!();
@@ -137,7 +821,1375 @@ fn cmd_check(cwd: &PathBuf) -> ExitCode {
     }
 }
 
-fn run_tui(cwd: PathBuf) -> io::Result<()> {
+fn cmd_check_clean_env(cwd: &Path) -> ExitCode {
+    let result = discovery::discover(cwd);
+    let stdio_servers: Vec<&types::McpServer> =
+        result.servers.iter().filter(|s| s.transport.is_stdio()).collect();
+
+    if stdio_servers.is_empty() {
+        println!("No stdio servers found to health check.");
+        return ExitCode::SUCCESS;
+    }
+
+    println!(
+        "Checking {} stdio server{} with and without this shell's environment...\n",
+        stdio_servers.len(),
+        if stdio_servers.len() == 1 { "" } else { "s" }
+    );
+
+    let mut any_dependent = false;
+    for server in stdio_servers {
+        print!("  {:<25} ", server.name);
+        let _ = io::Write::flush(&mut io::stdout());
+        let report = launch::diagnose_clean_env(server, || {
+            print!(".");
+            let _ = io::Write::flush(&mut io::stdout());
+        });
+        println!();
+
+        let healthy = |s: &types::HealthStatus| matches!(s, types::HealthStatus::Healthy { .. });
+        if report.depends_on_shell() {
+            any_dependent = true;
+            match &report.missing {
+                Some(vars) if report.probe_limit_hit => println!(
+                    "    \x1b[31m✗\x1b[0m needs undeclared env, at most these (probe limit reached): {}",
+                    vars.join(", ")
+                ),
+                Some(vars) => println!(
+                    "    \x1b[31m✗\x1b[0m needs undeclared env: {}",
+                    vars.join(", ")
+                ),
+                None => println!(
+                    "    \x1b[31m✗\x1b[0m fails in a clean environment, but not because of a missing variable"
+                ),
+            }
+            println!("    \x1b[90mclean env: {}\x1b[0m", report.clean.label());
+        } else if healthy(&report.normal) {
+            println!("    \x1b[32m✓\x1b[0m works with only its declared env");
+        } else {
+            println!(
+                "    \x1b[33m⚠\x1b[0m unhealthy either way: {}",
+                report.normal.label()
+            );
+        }
+    }
+
+    println!();
+    if any_dependent {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn cmd_check_history(cwd: &Path) -> ExitCode {
+    let result = discovery::discover(cwd);
+    let state = state::State::load();
+
+    let mut flapping = Vec::new();
+    let mut regressed = Vec::new();
+    for server in &result.servers {
+        let Some(h) = state.get(server) else {
+            continue;
+        };
+        if h.is_flapping() {
+            flapping.push((server, h));
+        } else if h.regressed() {
+            regressed.push((server, h));
+        }
+    }
+
+    if flapping.is_empty() && regressed.is_empty() {
+        println!("No flapping or regressed servers in recorded history.");
+        return ExitCode::SUCCESS;
+    }
+
+    let spark = |h: &state::ServerState| -> String {
+        h.sparkline(20)
+            .into_iter()
+            .map(|o| match o {
+                state::Outcome::Healthy => "\x1b[32m█\x1b[0m",
+                state::Outcome::Timeout => "\x1b[33m▄\x1b[0m",
+                state::Outcome::Error => "\x1b[31m▁\x1b[0m",
+                state::Outcome::AuthRequired => "\x1b[36m▂\x1b[0m",
+            })
+            .collect()
+    };
+
+    if !flapping.is_empty() {
+        println!("Flapping:");
+        for (server, h) in &flapping {
+            println!(
+                "  {:<25} {:>12}  {}  {} changes in last checks",
+                server.name,
+                server.client.label(),
+                spark(h),
+                h.recent_changes()
+            );
+        }
+        println!();
+    }
+
+    if !regressed.is_empty() {
+        println!("Recently regressed:");
+        for (server, h) in &regressed {
+            let last_ok = h
+                .last_healthy()
+                .map(types::format_ago_unix)
+                .unwrap_or_else(|| "never".to_string());
+            let now = h.last().map(|e| e.detail.as_str()).unwrap_or("");
+            println!(
+                "  {:<25} {:>12}  {}  last healthy {}, now {}",
+                server.name,
+                server.client.label(),
+                spark(h),
+                last_ok,
+                now
+            );
+        }
+        println!();
+    }
+
+    ExitCode::FAILURE
+}
+
+fn cmd_doctor(cwd: &Path) -> ExitCode {
+    let result = discovery::discover(cwd);
+    let state = state::State::load();
+    let names = analysis::ToolNameReport::build(&result.servers, &state);
+    let excess = analysis::tool_limit_excess(&result.servers, &state);
+
+    if !names.collisions.is_empty() {
+        println!("Tool name collisions:");
+        for c in &names.collisions {
+            println!("  \x1b[33m⚠\x1b[0m {:<15} {}", c.app, c.name);
+            for (server, tool) in &c.tools {
+                println!("      {} → {}", server, tool);
+            }
+        }
+        println!();
+    }
+
+    if !names.too_long.is_empty() {
+        println!("Tool names over client limits:");
+        for l in &names.too_long {
+            println!(
+                "  \x1b[33m⚠\x1b[0m {:<15} {} ({} > {} chars)",
+                l.client.label(),
+                l.qualified,
+                l.qualified.len(),
+                l.limit,
+            );
+        }
+        println!();
+    }
+
+    if !excess.is_empty() {
+        println!("Clients over their tool limit:");
+        for e in &excess {
+            println!(
+                "  \x1b[33m⚠\x1b[0m {:<15} {} tools, limit {}",
+                e.app, e.total, e.limit
+            );
+            for (i, (server, count)) in e.servers.iter().enumerate() {
+                let marker = if i >= e.over_from { "  \x1b[33m← over\x1b[0m" } else { "" };
+                println!("      {:<25} {:>4}{}", server, count, marker);
+            }
+        }
+        println!();
+    }
+
+    let unchecked: Vec<&str> = result
+        .servers
+        .iter()
+        .filter(|s| s.transport.is_stdio() && state.tools(s).is_none())
+        .map(|s| s.name.as_str())
+        .collect::<std::collections::BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    if !unchecked.is_empty() {
+        println!(
+            "\x1b[90mNo tool list yet for {} — run `mcpm check` to include them.\x1b[0m\n",
+            unchecked.join(", ")
+        );
+    }
+
+    if names.is_empty() && excess.is_empty() {
+        println!("No problems found.");
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn cmd_audit(cwd: &Path, format: ListFormat, fail_on: AuditLevel) -> ExitCode {
+    let result = discovery::discover(cwd);
+    let findings = audit::audit(&result.servers);
+
+    match format {
+        ListFormat::Json => {
+            let items: Vec<serde_json::Value> = findings
+                .iter()
+                .map(|f| {
+                    serde_json::json!({
+                        "server": f.server.name,
+                        "client": f.server.client.label(),
+                        "source": f.server.source_path,
+                        "check": f.check,
+                        "severity": f.severity.label(),
+                        "field": f.field,
+                        "message": f.message,
+                        "remediation": f.remediation,
+                    })
+                })
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&items).unwrap_or_default());
+        }
+        ListFormat::Text => {
+            for f in &findings {
+                let symbol = match f.severity {
+                    audit::Severity::High => "\x1b[31m✗\x1b[0m",
+                    audit::Severity::Medium => "\x1b[33m⚠\x1b[0m",
+                    audit::Severity::Low => "\x1b[36m·\x1b[0m",
+                };
+                println!(
+                    "{} {:<6} {} ({})  {}",
+                    symbol,
+                    f.severity.label(),
+                    f.server.name,
+                    f.server.client.label(),
+                    f.message
+                );
+                println!("         \x1b[90m{}: {}\x1b[0m", f.check, f.remediation);
+            }
+            let count = |sev| findings.iter().filter(|f| f.severity == sev).count();
+            if findings.is_empty() {
+                println!("No risky patterns found in {} server(s).", result.servers.len());
+            } else {
+                println!(
+                    "\n{} high, {} medium, {} low across {} server(s)",
+                    count(audit::Severity::High),
+                    count(audit::Severity::Medium),
+                    count(audit::Severity::Low),
+                    result.servers.len(),
+                );
+            }
+        }
+    }
+
+    if findings.iter().any(|f| f.severity >= fail_on.severity()) {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn cmd_pins(cwd: &Path, action: PinsAction) -> ExitCode {
+    let result = discovery::discover(cwd);
+    let mut state = state::State::load();
+
+    let (name, client, accept) = match action {
+        PinsAction::Show { server } => return show_pins(&result.servers, &state, server.as_deref()),
+        PinsAction::Accept { server, client } => (server, client, true),
+        PinsAction::Reject { server, client } => (server, client, false),
+    };
+    let targets: Vec<&types::McpServer> = result
+        .servers
+        .iter()
+        .filter(|s| s.name == name)
+        .filter(|s| client.as_deref().is_none_or(|c| s.client.label().eq_ignore_ascii_case(c)))
+        .collect();
+    if targets.is_empty() {
+        eprintln!("No server named \"{}\" found.", name);
+        return ExitCode::FAILURE;
+    }
+
+    let mut failed = false;
+    for server in targets {
+        let outcome = if accept {
+            pins::accept(&mut state, server)
+        } else {
+            pins::reject(&mut state, server)
+        };
+        match outcome {
+            Ok(()) if accept => {
+                println!("\x1b[32m✓\x1b[0m {} ({}): current definitions pinned", server.name, server.client.label())
+            }
+            Ok(()) => println!(
+                "\x1b[32m✓\x1b[0m {} ({}): changes rejected; `mcpm serve` leaves it out until it matches its pin",
+                server.name,
+                server.client.label()
+            ),
+            Err(e) => {
+                println!("\x1b[31m✗\x1b[0m {} ({}): {}", server.name, server.client.label(), e);
+                failed = true;
+            }
+        }
+    }
+    if let Err(e) = state.save() {
+        eprintln!("Error: failed to save pins: {}", e);
+        return ExitCode::FAILURE;
+    }
+    if failed { ExitCode::FAILURE } else { ExitCode::SUCCESS }
+}
+
+/// Exits 1 while any change is waiting for review
+fn show_pins(servers: &[types::McpServer], state: &state::State, only: Option<&str>) -> ExitCode {
+    let servers: Vec<&types::McpServer> = servers.iter().filter(|s| only.is_none_or(|n| s.name == n)).collect();
+    if servers.is_empty() {
+        match only {
+            Some(name) => eprintln!("No server named \"{}\" found.", name),
+            None => println!("No MCP servers found."),
+        }
+        return ExitCode::FAILURE;
+    }
+
+    let mut unreviewed = false;
+    for server in servers {
+        let label = format!("{} ({})", server.name, server.client.label());
+        match pins::verify(state, server) {
+            pins::Verdict::Unpinned => {
+                println!("\x1b[36m·\x1b[0m {:<35} not pinned yet; the next successful check pins it", label)
+            }
+            pins::Verdict::Trusted => {
+                let at = state.get(server).and_then(|s| s.pin.as_ref()).map_or(0, |p| p.at);
+                println!("\x1b[32m✓\x1b[0m {:<35} matches its pin from {}", label, types::format_ago_unix(at));
+            }
+            pins::Verdict::Changed(changes) => {
+                println!("\x1b[33m⚠\x1b[0m {:<35} changed since pinned", label);
+                print_pin_changes(&changes);
+                unreviewed = true;
+            }
+            pins::Verdict::Rejected(changes) => {
+                println!("\x1b[31m✗\x1b[0m {:<35} differs from its pin; changes rejected", label);
+                print_pin_changes(&changes);
+            }
+        }
+    }
+    if unreviewed {
+        println!("\nTrust the changes with `mcpm pins accept NAME`, or keep the pin with `mcpm pins reject NAME`.");
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn print_pin_changes(changes: &[pins::Change]) {
+    for change in changes {
+        println!("    {}", change.summary());
+        for line in &change.diff {
+            match line {
+                pins::DiffLine::Same(l) => println!("      \x1b[90m  {}\x1b[0m", l),
+                pins::DiffLine::Removed(l) => println!("      \x1b[31m- {}\x1b[0m", l),
+                pins::DiffLine::Added(l) => println!("      \x1b[32m+ {}\x1b[0m", l),
+                pins::DiffLine::Skipped(n) => println!(
+                    "      \x1b[90m  … {} unchanged line{}\x1b[0m",
+                    n,
+                    if *n == 1 { "" } else { "s" }
+                ),
+            }
+        }
+    }
+}
+
+/// Keep pins in step after mcpm itself rewrote config entries
+fn follow_pins(cwd: &Path, before: &[types::McpServer]) {
+    let mut state = state::State::load();
+    pins::follow_edits(&mut state, before, cwd);
+    if let Err(e) = state.save() {
+        eprintln!("warning: failed to save pins: {}", e);
+    }
+}
+
+fn cmd_lint(cwd: &Path, format: LintFormat, project_only: bool, mut disable: Vec<String>) -> ExitCode {
+    match settings::ProjectSettings::load(cwd) {
+        Ok(project) => disable.extend(project.lint.disable),
+        Err(e) => eprintln!("warning: ignoring invalid project config: {}", e),
+    }
+    for d in &disable {
+        if lint::rule(d).is_none() {
+            eprintln!("warning: unknown lint rule: {}", d);
+        }
+    }
+
+    let mut sources = discovery::config_sources(cwd);
+    if project_only {
+        sources.retain(|s| s.path.starts_with(cwd));
+    }
+    let findings = lint::lint(&sources, &disable);
+
+    match format {
+        LintFormat::Text => print_lint_text(cwd, &sources, &findings),
+        LintFormat::Json => {
+            let items: Vec<serde_json::Value> = findings
+                .iter()
+                .map(|f| {
+                    serde_json::json!({
+                        "rule": f.rule.id,
+                        "name": f.rule.name,
+                        "severity": f.rule.severity.label(),
+                        "message": f.message,
+                        "file": relative_path(cwd, &f.path),
+                        "pointer": f.pointer,
+                        "line": f.line,
+                    })
+                })
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&items).unwrap_or_default());
+        }
+        LintFormat::Sarif => {
+            let sarif = lint_sarif(cwd, &findings);
+            println!("{}", serde_json::to_string_pretty(&sarif).unwrap_or_default());
+        }
+    }
+
+    if findings.iter().any(|f| f.rule.severity == lint::Severity::Error) {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Path relative to the working directory when inside it
+fn relative_path(cwd: &Path, path: &Path) -> String {
+    path.strip_prefix(cwd)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+fn print_lint_text(cwd: &Path, sources: &[discovery::ConfigSource], findings: &[lint::Finding]) {
+    if sources.is_empty() {
+        println!("No config files found.");
+        return;
+    }
+    for source in sources {
+        let found: Vec<&lint::Finding> = findings.iter().filter(|f| f.path == source.path).collect();
+        if found.is_empty() {
+            continue;
+        }
+        println!("{}", relative_path(cwd, &source.path));
+        for f in found {
+            let symbol = match f.rule.severity {
+                lint::Severity::Error => "\x1b[31m✗\x1b[0m",
+                lint::Severity::Warning => "\x1b[33m⚠\x1b[0m",
+                lint::Severity::Note => "\x1b[36m·\x1b[0m",
+            };
+            let pointer = if f.pointer.is_empty() {
+                String::new()
+            } else {
+                format!("  \x1b[90m{}\x1b[0m", f.pointer)
+            };
+            println!("  {} {:>4}  {} {}{}", symbol, f.line, f.rule.id, f.message, pointer);
+        }
+        println!();
+    }
+
+    let count = |sev| findings.iter().filter(|f| f.rule.severity == sev).count();
+    if findings.is_empty() {
+        println!("No problems found in {} config file(s).", sources.len());
+    } else {
+        println!(
+            "{} error(s), {} warning(s), {} note(s)",
+            count(lint::Severity::Error),
+            count(lint::Severity::Warning),
+            count(lint::Severity::Note),
+        );
+    }
+}
+
+/// SARIF 2.1.0 log. Files inside the working directory are given relative
+/// to %SRCROOT% so code scanning can match them to the repository.
+fn lint_sarif(cwd: &Path, findings: &[lint::Finding]) -> serde_json::Value {
+    let rules: Vec<serde_json::Value> = lint::RULES
+        .iter()
+        .map(|r| {
+            serde_json::json!({
+                "id": r.id,
+                "name": r.name,
+                "shortDescription": { "text": r.description },
+                "defaultConfiguration": { "level": r.severity.label() },
+            })
+        })
+        .collect();
+    let results: Vec<serde_json::Value> = findings
+        .iter()
+        .map(|f| {
+            let location = if f.path.starts_with(cwd) {
+                serde_json::json!({ "uri": relative_path(cwd, &f.path), "uriBaseId": "%SRCROOT%" })
+            } else {
+                serde_json::json!({ "uri": format!("file://{}", f.path.to_string_lossy().replace('\\', "/")) })
+            };
+            serde_json::json!({
+                "ruleId": f.rule.id,
+                "ruleIndex": lint::RULES.iter().position(|r| r.id == f.rule.id),
+                "level": f.rule.severity.label(),
+                "message": { "text": f.message },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": location,
+                        "region": { "startLine": f.line },
+                    },
+                    "logicalLocations": [{ "fullyQualifiedName": f.pointer, "kind": "member" }],
+                }],
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "mcpm",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/Joncik91/mcpm",
+                    "rules": rules,
+                }
+            },
+            "results": results,
+        }],
+    })
+}
+
+fn cmd_bench(cwd: &Path, name: &str, runs: usize, compare: Option<&str>, show_secrets: bool) -> ExitCode {
+    let result = discovery::discover(cwd);
+
+    let Some(server) = result
+        .servers
+        .iter()
+        .find(|s| s.name == name && s.transport.is_stdio())
+    else {
+        eprintln!("No stdio server named \"{}\" found.", name);
+        return ExitCode::FAILURE;
+    };
+    let types::Transport::Stdio { command, args } = &server.transport else {
+        unreachable!("filtered to stdio above");
+    };
+
+    let mut variants = vec![(server.name.clone(), command.clone(), args.clone(), server.env.clone())];
+    if let Some(other) = compare {
+        let configured = result
+            .servers
+            .iter()
+            .find(|s| s.name == other && s.transport.is_stdio());
+        match configured.map(|s| (&s.transport, &s.env)) {
+            Some((types::Transport::Stdio { command, args }, env)) => {
+                variants.push((other.to_string(), command.clone(), args.clone(), env.clone()));
+            }
+            _ => {
+                // Not a configured server — treat it as a command line that
+                // shares the first server's env
+                let mut words = match shell_words::split(other) {
+                    Ok(words) => words.into_iter(),
+                    Err(e) => {
+                        eprintln!("--compare: {}", e);
+                        return ExitCode::FAILURE;
+                    }
+                };
+                let Some(command) = words.next() else {
+                    eprintln!("--compare needs a server name or a command line");
+                    return ExitCode::FAILURE;
+                };
+                variants.push((other.to_string(), command, words.collect(), server.env.clone()));
+            }
+        }
+    }
+
+    let mut reports = Vec::new();
+    for (label, command, args, env) in &variants {
+        let shown = if show_secrets { args.clone() } else { secrets::mask_args(args) };
+        let cmdline = std::iter::once(command).chain(&shown).cloned().collect::<Vec<_>>();
+        println!("Benchmarking {} ({}), {} runs", label, cmdline.join(" "), runs);
+        print!("  ");
+        let report = bench::run(command, args, env, runs, |ok| {
+            print!("{}", if ok { "." } else { "\x1b[31mx\x1b[0m" });
+            let _ = io::Write::flush(&mut io::stdout());
+        });
+        println!("\n");
+        print_bench_report(&report);
+        println!();
+        reports.push((label, report));
+    }
+
+    if let [(a_label, a), (b_label, b)] = reports.as_slice()
+        && let (Some(a_cold), Some(b_cold)) = (a.cold_start_summary(), b.cold_start_summary())
+    {
+        let (fast, slow, fast_p50, slow_p50) = if a_cold.p50 <= b_cold.p50 {
+            (a_label, b_label, a_cold.p50, b_cold.p50)
+        } else {
+            (b_label, a_label, b_cold.p50, a_cold.p50)
+        };
+        println!(
+            "{} starts {} faster than {} at p50 ({:.1}×)",
+            fast,
+            types::format_duration(slow_p50 - fast_p50),
+            slow,
+            slow_p50.as_secs_f64() / fast_p50.as_secs_f64().max(0.001),
+        );
+    }
+
+    if reports.iter().any(|(_, r)| r.failures == r.runs) {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn print_bench_report(report: &bench::BenchReport) {
+    println!("  {:<12} {:>9} {:>9} {:>9}", "", "p50", "p95", "max");
+    for (label, summary) in [
+        ("cold start", report.cold_start_summary()),
+        ("handshake", report.handshake_summary()),
+    ] {
+        match summary {
+            Some(s) => println!(
+                "  {:<12} {:>9} {:>9} {:>9}",
+                label,
+                types::format_duration(s.p50),
+                types::format_duration(s.p95),
+                types::format_duration(s.max),
+            ),
+            None => println!("  {:<12} {:>9} {:>9} {:>9}", label, "-", "-", "-"),
+        }
+    }
+    println!(
+        "  {:<12} {}/{} ({:.0}%)",
+        "failures",
+        report.failures,
+        report.runs,
+        report.failure_rate() * 100.0
+    );
+    if let Some(e) = &report.last_error {
+        println!("  {:<12} {}", "last error", e);
+    }
+}
+
+/// Start a configured stdio server and complete the handshake. Returns the
+/// session and its initialize result, or prints why it couldn't.
+fn open_session(cwd: &Path, name: &str) -> Option<(health::Session, serde_json::Value)> {
+    let result = discovery::discover(cwd);
+    let Some(server) = result
+        .servers
+        .iter()
+        .find(|s| s.name == name && s.transport.is_stdio())
+    else {
+        eprintln!("No stdio server named \"{}\" found.", name);
+        return None;
+    };
+    let types::Transport::Stdio { command, args } = &server.transport else {
+        unreachable!("filtered to stdio above");
+    };
+    let mut session = match health::Session::spawn(command, args, &server.env) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("{}: {}", name, e);
+            return None;
+        }
+    };
+    match session.initialize() {
+        Ok(init) => Some((session, init)),
+        Err(e) => {
+            eprintln!("{}: {}", name, e);
+            session.close();
+            None
+        }
+    }
+}
+
+fn cmd_resources(cwd: &Path, name: &str, read: Option<&str>) -> ExitCode {
+    let Some((mut session, init)) = open_session(cwd, name) else {
+        return ExitCode::FAILURE;
+    };
+
+    let ok = if let Some(uri) = read {
+        let params = serde_json::json!({ "uri": uri });
+        match session.request("resources/read", params, health::TIMEOUT) {
+            Ok(r) => {
+                print_result_lines(&inspector::render_read_result(&r));
+                true
+            }
+            Err(e) => {
+                eprintln!("{}: {}", name, e);
+                false
+            }
+        }
+    } else if init["capabilities"].get("resources").is_none() {
+        println!("{} does not advertise resources.", name);
+        true
+    } else {
+        match session.list_all("resources/list", "resources") {
+            Ok(resources) => {
+                // Templates are optional; a server rejecting the method has none
+                let templates = session
+                    .list_all("resources/templates/list", "resourceTemplates")
+                    .unwrap_or_default();
+                if resources.is_empty() && templates.is_empty() {
+                    println!("{} has no resources.", name);
+                }
+                for r in &resources {
+                    println!(
+                        "  {:<25} {}  \x1b[90m{}\x1b[0m",
+                        inspector::item_name(r),
+                        r["uri"].as_str().unwrap_or(""),
+                        r["mimeType"].as_str().unwrap_or("")
+                    );
+                }
+                if !templates.is_empty() {
+                    println!("\nTemplates:");
+                    for t in &templates {
+                        println!(
+                            "  {:<25} {}",
+                            inspector::item_name(t),
+                            t["uriTemplate"].as_str().unwrap_or("")
+                        );
+                    }
+                }
+                true
+            }
+            Err(e) => {
+                eprintln!("{}: {}", name, e);
+                false
+            }
+        }
+    };
+
+    session.close();
+    if ok { ExitCode::SUCCESS } else { ExitCode::FAILURE }
+}
+
+fn cmd_prompts(cwd: &Path, name: &str, get: Option<&str>, args: &[String]) -> ExitCode {
+    let mut arguments = serde_json::Map::new();
+    for arg in args {
+        let Some((k, v)) = arg.split_once('=') else {
+            eprintln!("--arg expects KEY=VALUE, got \"{}\"", arg);
+            return ExitCode::FAILURE;
+        };
+        arguments.insert(k.to_string(), serde_json::Value::String(v.to_string()));
+    }
+
+    let Some((mut session, init)) = open_session(cwd, name) else {
+        return ExitCode::FAILURE;
+    };
+
+    let ok = if let Some(prompt) = get {
+        let params = serde_json::json!({ "name": prompt, "arguments": arguments });
+        match session.request("prompts/get", params, health::TIMEOUT) {
+            Ok(r) => {
+                print_result_lines(&inspector::render_prompt_result(&r));
+                true
+            }
+            Err(e) => {
+                eprintln!("{}: {}", name, e);
+                false
+            }
+        }
+    } else if init["capabilities"].get("prompts").is_none() {
+        println!("{} does not advertise prompts.", name);
+        true
+    } else {
+        match session.list_all("prompts/list", "prompts") {
+            Ok(prompts) => {
+                if prompts.is_empty() {
+                    println!("{} has no prompts.", name);
+                }
+                for p in &prompts {
+                    println!(
+                        "  {:<25} \x1b[90m{}\x1b[0m",
+                        inspector::item_name(p),
+                        p["description"].as_str().unwrap_or("")
+                    );
+                    for a in p["arguments"].as_array().into_iter().flatten() {
+                        let required = a["required"].as_bool().unwrap_or(false);
+                        println!(
+                            "      {}{}  \x1b[90m{}\x1b[0m",
+                            a["name"].as_str().unwrap_or("?"),
+                            if required { "*" } else { "" },
+                            a["description"].as_str().unwrap_or("")
+                        );
+                    }
+                }
+                true
+            }
+            Err(e) => {
+                eprintln!("{}: {}", name, e);
+                false
+            }
+        }
+    };
+
+    session.close();
+    if ok { ExitCode::SUCCESS } else { ExitCode::FAILURE }
+}
+
+fn print_result_lines(lines: &[inspector::ResultLine]) {
+    for line in lines {
+        match line {
+            inspector::ResultLine::Heading(t) => println!("\x1b[35m{}:\x1b[0m", t),
+            inspector::ResultLine::Text(t) => println!("{}", t),
+            inspector::ResultLine::Meta(t) => println!("\x1b[90m{}\x1b[0m", t),
+            inspector::ResultLine::Error(t) => println!("\x1b[31m✗ {}\x1b[0m", t),
+        }
+    }
+}
+
+fn cmd_mock_server(script: Option<&Path>, faults: mock::Faults, http: Option<u16>, oauth: bool) -> ExitCode {
+    let script = match script {
+        Some(path) => match mock::Script::load(path) {
+            Ok(script) => script,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                return ExitCode::FAILURE;
+            }
+        },
+        None => mock::Script::default(),
+    };
+    let result = match http {
+        Some(port) => mock::run_http(script, faults, port, oauth),
+        None => mock::run_stdio(script, faults),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn cmd_proxy(cwd: &Path, name: &str, client: Option<&str>, remove: bool) -> ExitCode {
+    let result = discovery::discover(cwd);
+    let candidates: Vec<&types::McpServer> = result
+        .servers
+        .iter()
+        .filter(|s| s.name == name && s.transport.is_stdio())
+        .filter(|s| types::ClientKind::writable().contains(&s.client))
+        .filter(|s| client.is_none_or(|c| s.client.label().eq_ignore_ascii_case(c)))
+        .collect();
+
+    let server = match candidates.as_slice() {
+        [] => {
+            eprintln!("No stdio server named \"{}\" found in a writable config.", name);
+            return ExitCode::FAILURE;
+        }
+        [server] => *server,
+        several => {
+            let labels: Vec<&str> = several.iter().map(|s| s.client.label()).collect();
+            eprintln!(
+                "\"{}\" is configured for several clients ({}); pick one with --client.",
+                name,
+                labels.join(", ")
+            );
+            return ExitCode::FAILURE;
+        }
+    };
+    let types::Transport::Stdio { command, args } = &server.transport else {
+        unreachable!("filtered to stdio above");
+    };
+    let Some(log_dir) = proxy::log_dir(&server.name) else {
+        eprintln!("Error: could not determine data directory");
+        return ExitCode::FAILURE;
+    };
+    let app = server.client.app_name();
+
+    let (new_command, new_args) = match (proxy::unwrap(args), remove) {
+        (Some(original), true) => original,
+        (None, true) => {
+            eprintln!("{} ({}) is not proxied.", name, server.client.label());
+            return ExitCode::FAILURE;
+        }
+        (Some(_), false) => {
+            println!("{} ({}) is already proxied.", name, server.client.label());
+            println!("  Logs: {}", log_dir.display());
+            return ExitCode::SUCCESS;
+        }
+        (None, false) => match proxy::wrap(&log_dir, command, args) {
+            Ok(wrapped) => wrapped,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                return ExitCode::FAILURE;
+            }
+        },
+    };
+
+    if let Err(e) = config_writer::set_command(&server.client, cwd, name, &new_command, &new_args) {
+        eprintln!("Error: {}", e);
+        return ExitCode::FAILURE;
+    }
+    follow_pins(cwd, &result.servers);
+    if remove {
+        println!("\x1b[32m✓\x1b[0m {} ({}) runs its own command again. Restart {} to pick it up.", name, server.client.label(), app);
+        let logs = proxy::list_logs(&log_dir);
+        if !logs.is_empty() {
+            println!("  {} log{} kept in {}", logs.len(), if logs.len() == 1 { "" } else { "s" }, log_dir.display());
+        }
+    } else {
+        println!("\x1b[32m✓\x1b[0m {} ({}) now runs through mcpm proxy. Restart {} to pick it up.", name, server.client.label(), app);
+        println!("  Logs: {}", log_dir.display());
+        println!("  Replay the newest with: mcpm replay {}", name);
+        println!("  Undo with: mcpm proxy {} --client {} --remove", name, server.client.label());
+    }
+    ExitCode::SUCCESS
+}
+
+/// A config entry that runs `mcpm serve` itself — aggregating it would have
+/// the gateway start itself over and over
+fn is_gateway_entry(server: &types::McpServer) -> bool {
+    let types::Transport::Stdio { command, args } = &server.transport else {
+        return false;
+    };
+    let is_mcpm = Path::new(command).file_stem().is_some_and(|s| s == "mcpm")
+        || std::env::current_exe().is_ok_and(|exe| exe == Path::new(command));
+    is_mcpm && args.first().is_some_and(|a| a == "serve")
+}
+
+fn cmd_serve(cwd: &Path, client: Option<&str>, names: &[String], http: Option<u16>) -> ExitCode {
+    let result = discovery::discover(cwd);
+    let mut selected: Vec<&types::McpServer> = Vec::new();
+    match client {
+        Some(label) => {
+            for server in result.servers.iter().filter(|s| s.client.label().eq_ignore_ascii_case(label)) {
+                if is_gateway_entry(server) {
+                    continue;
+                }
+                if !server.transport.is_stdio() {
+                    eprintln!("mcpm serve: skipping {} (only stdio servers can be aggregated)", server.name);
+                    continue;
+                }
+                if !selected.iter().any(|s| s.name == server.name) {
+                    selected.push(server);
+                }
+            }
+        }
+        None => {
+            for name in names {
+                let Some(server) = result
+                    .servers
+                    .iter()
+                    .find(|s| &s.name == name && s.transport.is_stdio() && !is_gateway_entry(s))
+                else {
+                    eprintln!("No stdio server named \"{}\" found.", name);
+                    return ExitCode::FAILURE;
+                };
+                if !selected.iter().any(|s| s.name == server.name) {
+                    selected.push(server);
+                }
+            }
+        }
+    }
+    // Definitions the user rejected don't reach the model
+    let state = state::State::load();
+    selected.retain(|server| match pins::verify(&state, server) {
+        pins::Verdict::Rejected(_) => {
+            eprintln!("mcpm serve: skipping {} (its changed definitions were rejected)", server.name);
+            false
+        }
+        pins::Verdict::Changed(_) => {
+            eprintln!("mcpm serve: {} changed since pinned; review with mcpm pins show {}", server.name, server.name);
+            true
+        }
+        pins::Verdict::Unpinned | pins::Verdict::Trusted => true,
+    });
+    if selected.is_empty() {
+        eprintln!("No stdio servers to serve.");
+        return ExitCode::FAILURE;
+    }
+
+    // stdout belongs to the protocol; progress goes to stderr
+    let gateway = gateway::Gateway::start(&selected, |name, outcome| match outcome {
+        Ok(()) => eprintln!("mcpm serve: {} ready", name),
+        Err(e) => eprintln!("mcpm serve: {} failed to start: {}", name, e),
+    });
+    if gateway.is_empty() {
+        eprintln!("mcpm serve: no server could be started");
+        return ExitCode::FAILURE;
+    }
+
+    let gateway = std::sync::Arc::new(gateway);
+    let served = match http {
+        Some(port) => endpoint::serve_http(gateway.clone(), port, |url| {
+            eprintln!("mcpm serve: listening on {}", url);
+        }),
+        None => endpoint::serve_stdio(gateway.clone()),
+    };
+    gateway.close();
+    match served {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("mcpm serve: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn cmd_bridge(
+    cwd: &Path,
+    name: Option<&str>,
+    url: Option<&str>,
+    sse: bool,
+    headers: &[String],
+    port: u16,
+) -> ExitCode {
+    let result = match (name, url) {
+        (_, Some(url)) => match bridge::parse_headers(&[bridge::env_headers(), headers.to_vec()].concat()) {
+            Ok(headers) => bridge::http_to_stdio(url, &headers, sse),
+            Err(e) => Err(e),
+        },
+        (Some(name), None) => {
+            let discovered = discovery::discover(cwd);
+            let Some(server) = discovered.servers.iter().find(|s| s.name == name) else {
+                eprintln!("No server named \"{}\" found.", name);
+                return ExitCode::FAILURE;
+            };
+            match &server.transport {
+                types::Transport::Http { url, headers } => {
+                    bridge::http_to_stdio(url, &headers.clone().unwrap_or_default(), false)
+                }
+                types::Transport::Sse { url } => bridge::http_to_stdio(url, &Default::default(), true),
+                types::Transport::Stdio { command, args } => {
+                    bridge::stdio_to_http(command, args, &server.env, port)
+                }
+                types::Transport::Unknown => Err(format!("{} has no usable transport", name)),
+            }
+        }
+        (None, None) => unreachable!("clap requires a server or --url"),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("mcpm bridge: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn cmd_login(
+    cwd: &Path,
+    name: &str,
+    client: Option<&str>,
+    client_id: Option<&str>,
+    print: bool,
+    no_browser: bool,
+) -> ExitCode {
+    let result = discovery::discover(cwd);
+    let candidates: Vec<&types::McpServer> = result
+        .servers
+        .iter()
+        .filter(|s| s.name == name && matches!(s.transport, types::Transport::Http { .. }))
+        .filter(|s| print || types::ClientKind::writable().contains(&s.client))
+        .filter(|s| client.is_none_or(|c| s.client.label().eq_ignore_ascii_case(c)))
+        .collect();
+    let server = match candidates.as_slice() {
+        [] => {
+            eprintln!("No HTTP server named \"{}\" found{}.", name, if print { "" } else { " in a writable config" });
+            return ExitCode::FAILURE;
+        }
+        [server] => *server,
+        several => {
+            let labels: Vec<&str> = several.iter().map(|s| s.client.label()).collect();
+            eprintln!(
+                "\"{}\" is configured for several clients ({}); pick one with --client.",
+                name,
+                labels.join(", ")
+            );
+            return ExitCode::FAILURE;
+        }
+    };
+    let types::Transport::Http { url, headers } = &server.transport else {
+        unreachable!("filtered to http above");
+    };
+
+    // Ask the server first: its 401 challenge says where the metadata is
+    let probe = remote::RemoteClient::connect(url, &headers.clone().unwrap_or_default(), false, health::TIMEOUT)
+        .and_then(|mut c| {
+            let init = c.request("initialize", health::initialize_params(), health::TIMEOUT);
+            c.close();
+            init
+        });
+    let challenge = match probe {
+        Ok(_) => {
+            println!("{} doesn't require authorization with its current headers.", name);
+            return ExitCode::SUCCESS;
+        }
+        Err(remote::RemoteError::Unauthorized(challenge)) => challenge,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let info = match oauth::discover(url, challenge.as_deref()) {
+        Ok(info) => info,
+        Err(e) => {
+            eprintln!("Error: OAuth discovery failed: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    println!("Authorization server: {}", info.issuer);
+    if !info.scopes.is_empty() {
+        println!("Scopes: {}", info.scopes.join(" "));
+    }
+
+    let token = oauth::login(&info, client_id, |auth_url| {
+        println!("\nOpen this URL to authorize mcpm:\n  {}\n", auth_url);
+        if !no_browser {
+            open_browser(auth_url);
+        }
+        println!("Waiting for the redirect...");
+    });
+    let token = match token {
+        Ok(token) => token,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let expiry = token
+        .expires_in
+        .map(|s| format!(" It expires in {} min.", s / 60))
+        .unwrap_or_default();
+    if print {
+        println!("{}", token.access_token);
+        return ExitCode::SUCCESS;
+    }
+    let value = format!("{} {}", if token.token_type.eq_ignore_ascii_case("bearer") { "Bearer" } else { &token.token_type }, token.access_token);
+    if let Err(e) = config_writer::set_header(&server.client, cwd, name, "Authorization", &value) {
+        eprintln!("Error: {}", e);
+        return ExitCode::FAILURE;
+    }
+    println!(
+        "\x1b[32m✓\x1b[0m Token written to {}'s headers ({}).{} Restart {} to pick it up.",
+        name,
+        server.client.label(),
+        expiry,
+        server.client.app_name()
+    );
+    if token.refresh_token.is_some() {
+        println!("  \x1b[90mThe refresh token was not saved; run mcpm login again when it expires.\x1b[0m");
+    }
+    ExitCode::SUCCESS
+}
+
+/// Best effort; the URL is printed either way
+fn open_browser(url: &str) {
+    let (program, args): (&str, Vec<&str>) = if cfg!(target_os = "macos") {
+        ("open", vec![url])
+    } else if cfg!(windows) {
+        // Not `cmd /C start`: cmd splits the URL at every `&`
+        ("rundll32", vec!["url.dll,FileProtocolHandler", url])
+    } else {
+        ("xdg-open", vec![url])
+    };
+    let _ = std::process::Command::new(program)
+        .args(args)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn();
+}
+
+fn cmd_secret(action: SecretAction) -> ExitCode {
+    let mut vault = match vault::Vault::open_interactive() {
+        Ok(vault) => vault,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let result = match action {
+        SecretAction::Set { name } => {
+            if !vault::is_valid_name(&name) {
+                eprintln!("Secret names may only use letters, digits, '_', '-' and '.'.");
+                return ExitCode::FAILURE;
+            }
+            read_secret(&format!("Value for {}: ", name)).and_then(|value| {
+                if value.is_empty() {
+                    return Err("empty value; nothing stored".to_string());
+                }
+                vault.set(&name, &value);
+                vault.save()?;
+                println!(
+                    "\x1b[32m✓\x1b[0m Stored {}. Use it in a config as {}",
+                    name,
+                    vault::placeholder(&name)
+                );
+                Ok(())
+            })
+        }
+        SecretAction::Get { name } => match vault.get(&name) {
+            Some(value) => {
+                println!("{}", value);
+                Ok(())
+            }
+            None => Err(format!("no secret named \"{}\"", name)),
+        },
+        SecretAction::List => {
+            let names: Vec<&String> = vault.names().collect();
+            if names.is_empty() {
+                println!("The vault is empty. Add a secret with `mcpm secret set NAME`.");
+            }
+            for name in names {
+                println!("{:<30} {}", name, vault::placeholder(name));
+            }
+            Ok(())
+        }
+        SecretAction::Rm { name } => {
+            if vault.remove(&name) {
+                vault.save().map(|()| println!("\x1b[32m✓\x1b[0m Removed {}.", name))
+            } else {
+                Err(format!("no secret named \"{}\"", name))
+            }
+        }
+        SecretAction::Encrypt => new_passphrase().and_then(|passphrase| {
+            vault.set_passphrase(Some(passphrase));
+            vault.save()?;
+            println!(
+                "\x1b[32m✓\x1b[0m Vault encrypted. The TUI and `mcpm mcp` read the passphrase from {}.",
+                vault::PASSPHRASE_VAR
+            );
+            Ok(())
+        }),
+        SecretAction::Decrypt => {
+            vault.set_passphrase(None);
+            vault.save().map(|()| println!("\x1b[32m✓\x1b[0m Vault stored unencrypted."))
+        }
+        SecretAction::Env => {
+            for name in vault.names() {
+                let value = vault.get(name).unwrap_or_default();
+                println!("export {}='{}'", vault::env_var(name), value.replace('\'', r"'\''"));
+            }
+            Ok(())
+        }
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// A secret value from a hidden prompt, or from stdin when it's piped
+fn read_secret(prompt: &str) -> Result<String, String> {
+    use std::io::IsTerminal;
+    if io::stdin().is_terminal() {
+        rpassword::prompt_password(prompt).map_err(|e| e.to_string())
+    } else {
+        let mut value = String::new();
+        io::Read::read_to_string(&mut io::stdin(), &mut value).map_err(|e| e.to_string())?;
+        Ok(value.trim_end_matches(['\n', '\r']).to_string())
+    }
+}
+
+/// A new vault passphrase: from the environment, or asked twice
+fn new_passphrase() -> Result<String, String> {
+    if let Ok(passphrase) = std::env::var(vault::PASSPHRASE_VAR) {
+        return Ok(passphrase);
+    }
+    let first = rpassword::prompt_password("New passphrase: ").map_err(|e| e.to_string())?;
+    if first.is_empty() {
+        return Err("empty passphrase; vault left as it was".to_string());
+    }
+    let second = rpassword::prompt_password("Repeat passphrase: ").map_err(|e| e.to_string())?;
+    if first != second {
+        return Err("passphrases don't match; vault left as it was".to_string());
+    }
+    Ok(first)
+}
+
+fn cmd_materialize(cwd: &Path, client: Option<&str>, dry_run: bool) -> ExitCode {
+    let only = match client.map(types::ClientKind::from_label) {
+        Some(None) => {
+            eprintln!("Unknown client \"{}\".", client.unwrap_or_default());
+            return ExitCode::FAILURE;
+        }
+        Some(c) => c,
+        None => None,
+    };
+    let result = discovery::discover(cwd);
+    // Opened once, so an encrypted vault asks for its passphrase only once
+    let vault = vault::Vault::open_interactive();
+
+    let mut changed = 0;
+    let mut failed = 0;
+    let mut references = false;
+    for server in &result.servers {
+        if !types::ClientKind::writable().contains(&server.client)
+            || only.as_ref().is_some_and(|c| *c != server.client)
+        {
+            continue;
+        }
+        let current = config_writer::server_value(server);
+        match vault::materialize(&server.client, &current, &vault) {
+            Ok(value) if value == current => continue,
+            Ok(_) => {}
+            Err(e) => {
+                println!("\x1b[31m✗\x1b[0m {} ({}): {}", server.name, server.client.label(), e);
+                failed += 1;
+                continue;
+            }
+        }
+        let how = if server.client.expands_env() {
+            references = true;
+            "env var references"
+        } else {
+            "secret values"
+        };
+        if dry_run {
+            println!("  {} ({}) would get {}", server.name, server.client.label(), how);
+            changed += 1;
+            continue;
+        }
+        match config_writer::materialize_entry(&server.client, cwd, &server.name, &vault) {
+            Ok(()) => {
+                println!("\x1b[32m✓\x1b[0m {} ({}): {}", server.name, server.client.label(), how);
+                changed += 1;
+            }
+            Err(e) => {
+                println!("\x1b[31m✗\x1b[0m {} ({}): {}", server.name, server.client.label(), e);
+                failed += 1;
+            }
+        }
+    }
+
+    if changed == 0 && failed == 0 {
+        println!("No placeholders to resolve.");
+    } else if !dry_run {
+        follow_pins(cwd, &result.servers);
+    }
+    if references {
+        println!(
+            "\x1b[90mClients given env var references need the secrets in their environment; `mcpm secret env` prints them.\x1b[0m"
+        );
+    }
+    if failed > 0 { ExitCode::FAILURE } else { ExitCode::SUCCESS }
+}
+
+fn cmd_replay(log: &str, realtime: bool) -> ExitCode {
+    let path = PathBuf::from(log);
+    let path = if path.is_file() {
+        path
+    } else {
+        match proxy::log_dir(log).and_then(|dir| proxy::list_logs(&dir).pop()) {
+            Some(newest) => newest,
+            None => {
+                eprintln!("No log file or proxied server named \"{}\".", log);
+                return ExitCode::FAILURE;
+            }
+        }
+    };
+    match proxy::replay(&path, realtime) {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("mcpm replay: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run_tui(cwd: PathBuf, show_secrets: bool) -> io::Result<()> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen)?;
@@ -145,6 +2197,7 @@ fn run_tui(cwd: PathBuf) -> io::Result<()> {
     let mut terminal = Terminal::new(backend)?;
 
     let mut app_state = app::App::new(cwd);
+    app_state.show_secrets = show_secrets;
 
     let result = loop {
         terminal.draw(|f| ui::render(f, &mut app_state))?;
@@ -0,0 +1,510 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde_json::{Value, json};
+
+use crate::analysis;
+use crate::bridge;
+use crate::config_writer;
+use crate::discovery;
+use crate::endpoint::{Handler, Reply};
+use crate::health::{self, PROTOCOL_VERSION};
+use crate::jsonrpc;
+use crate::launch::LaunchOptions;
+use crate::pins;
+use crate::secrets;
+use crate::settings::Settings;
+use crate::state::State;
+use crate::types::{ClientKind, HealthResult, HealthStatus, McpServer, Transport};
+
+/// mcpm's own operations as MCP tools, so an agent can manage configs
+/// through the same backup-and-validate path as the TUI
+pub struct ManageServer {
+    cwd: PathBuf,
+    read_only: bool,
+    /// Config edits and state saves happen one at a time
+    writes: Mutex<()>,
+}
+
+type ToolResult = Result<Value, String>;
+
+impl ManageServer {
+    pub fn new(cwd: PathBuf, read_only: bool) -> ManageServer {
+        ManageServer {
+            cwd,
+            read_only,
+            writes: Mutex::new(()),
+        }
+    }
+
+    fn tools(&self) -> Vec<Value> {
+        let labels: Vec<&str> = ClientKind::writable().iter().map(ClientKind::label).collect();
+        let client_list = json!({
+            "type": "array",
+            "items": { "type": "string", "enum": labels },
+        });
+        let mut tools = vec![
+            json!({
+                "name": "list_servers",
+                "description": "List every MCP server mcpm finds across clients, with its transport and last known health",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "client": { "type": "string", "description": "Only this client's servers, by label (e.g. Desktop, Cursor)" },
+                    },
+                },
+            }),
+            json!({
+                "name": "check_health",
+                "description": "Run the MCP handshake with servers (stdio, HTTP or SSE) and report whether they are healthy and whether their tool definitions still match the pinned ones",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "name": { "type": "string", "description": "Only check servers with this name" },
+                    },
+                },
+            }),
+            json!({
+                "name": "show_drift",
+                "description": "Servers whose definitions differ between clients, and which fields differ",
+                "inputSchema": { "type": "object", "properties": {} },
+            }),
+        ];
+        if self.read_only {
+            return tools;
+        }
+        tools.extend([
+            json!({
+                "name": "add_server",
+                "description": "Add a server to one or more clients' configs. The config files are backed up first.",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "name": { "type": "string" },
+                        "clients": client_list,
+                        "transport": { "type": "string", "enum": ["stdio", "http", "sse"], "default": "stdio" },
+                        "command": { "type": "string", "description": "stdio: executable to run" },
+                        "args": { "type": "array", "items": { "type": "string" } },
+                        "env": { "type": "object", "additionalProperties": { "type": "string" } },
+                        "url": { "type": "string", "description": "http/sse: server URL" },
+                        "headers": { "type": "object", "additionalProperties": { "type": "string" } },
+                        "replace": { "type": "boolean", "description": "Overwrite an existing server with this name", "default": false },
+                    },
+                    "required": ["name", "clients"],
+                },
+            }),
+            json!({
+                "name": "remove_server",
+                "description": "Remove a server from clients' configs. The config files are backed up first.",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "name": { "type": "string" },
+                        "clients": { "description": "Defaults to every writable client that has it", "type": "array", "items": { "type": "string", "enum": labels } },
+                    },
+                    "required": ["name"],
+                },
+            }),
+            json!({
+                "name": "sync_server",
+                "description": "Copy a server's definition from one client to others, replacing theirs. Clients that can't connect to HTTP servers get it through mcpm bridge",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "name": { "type": "string" },
+                        "clients": client_list,
+                        "from": { "type": "string", "description": "Client to copy from; required when the definitions differ" },
+                    },
+                    "required": ["name", "clients"],
+                },
+            }),
+        ]);
+        tools
+    }
+
+    fn call(&self, name: &str, args: &Value) -> ToolResult {
+        match name {
+            "list_servers" => self.list_servers(args),
+            "check_health" => self.check_health(args),
+            "show_drift" => self.show_drift(),
+            "add_server" | "remove_server" | "sync_server" if self.read_only => {
+                Err("mcpm is running read-only".to_string())
+            }
+            "add_server" => self.add_server(args),
+            "remove_server" => self.remove_server(args),
+            "sync_server" => self.sync_server(args),
+            _ => Err(format!("unknown tool: {}", name)),
+        }
+    }
+
+    fn list_servers(&self, args: &Value) -> ToolResult {
+        let client = optional_client(args, "client")?;
+        let result = discovery::discover(&self.cwd);
+        let state = State::load();
+        let servers: Vec<Value> = result
+            .servers
+            .iter()
+            .filter(|s| client.as_ref().is_none_or(|c| s.client == *c))
+            .map(|s| {
+                let mut entry = describe(s);
+                if let Some(last) = state.get(s).and_then(|st| st.last()) {
+                    entry["last_check"] = json!({ "at": last.at, "outcome": last.outcome, "detail": last.detail });
+                }
+                entry
+            })
+            .collect();
+        Ok(json!({ "servers": servers, "errors": result.errors }))
+    }
+
+    fn check_health(&self, args: &Value) -> ToolResult {
+        let name = args["name"].as_str();
+        let result = discovery::discover(&self.cwd);
+        let targets: Vec<(usize, &McpServer)> = result
+            .servers
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| name.is_none_or(|n| s.name == n))
+            .collect();
+        if targets.is_empty() {
+            return Err(match name {
+                Some(n) => format!("no server named \"{}\"", n),
+                None => "no servers configured".to_string(),
+            });
+        }
+
+        // Same concurrency limit as the TUI's check queue
+        let settings = Settings::load().unwrap_or_default();
+        let limit = settings.check_limit();
+        let launch = LaunchOptions::for_checks(&settings);
+        let launch = &launch;
+        let mut checks: Vec<Option<HealthResult>> = Vec::new();
+        for batch in targets.chunks(limit) {
+            std::thread::scope(|scope| {
+                let handles: Vec<_> = batch
+                    .iter()
+                    .map(|(i, s)| scope.spawn(move || s.transport.is_checkable().then(|| health::check_server_with(*i, s, launch))))
+                    .collect();
+                checks.extend(handles.into_iter().map(|h| h.join().ok().flatten()));
+            });
+        }
+
+        let _guard = self.writes.lock();
+        let mut state = State::load();
+        let mut report = Vec::new();
+        for ((_, server), check) in targets.iter().zip(checks) {
+            let mut entry = json!({ "name": server.name, "client": server.client.label() });
+            match check {
+                Some(hr) => {
+                    state.record(server, &hr.status, hr.checked_at);
+                    entry["healthy"] = json!(matches!(hr.status, HealthStatus::Healthy { .. }));
+                    entry["status"] = json!(hr.status.label());
+                    if let Some(tools) = hr.tools {
+                        entry["tool_count"] = json!(tools.len());
+                        state.record_tools(server, tools, hr.checked_at);
+                    }
+                    entry["pin"] = json!(pins::verify(&state, server).label());
+                }
+                None => {
+                    entry["status"] = json!("skipped: unknown transport");
+                }
+            }
+            report.push(entry);
+        }
+        if let Err(e) = state.save() {
+            eprintln!("mcpm mcp: failed to save health history: {}", e);
+        }
+        Ok(json!({ "results": report }))
+    }
+
+    fn show_drift(&self) -> ToolResult {
+        let result = discovery::discover(&self.cwd);
+        let drift: Vec<Value> = analysis::config_drift(&result.servers)
+            .iter()
+            .map(|d| {
+                let variants: Vec<Value> = d
+                    .variants
+                    .iter()
+                    .map(|users| {
+                        let clients: Vec<&str> = users.iter().map(|s| s.client.label()).collect();
+                        let mut v = describe(users[0]);
+                        if let Some(obj) = v.as_object_mut() {
+                            obj.remove("client");
+                            obj.remove("source");
+                        }
+                        v["clients"] = json!(clients);
+                        v
+                    })
+                    .collect();
+                json!({ "name": d.name, "differs": d.fields, "variants": variants })
+            })
+            .collect();
+        Ok(json!({ "drift": drift }))
+    }
+
+    fn add_server(&self, args: &Value) -> ToolResult {
+        let name = required_str(args, "name")?;
+        let clients = client_list(args, "clients")?;
+        let env = string_map(&args["env"], "env")?;
+        let value = match args["transport"].as_str().unwrap_or("stdio") {
+            "stdio" => {
+                let command = required_str(args, "command").map_err(|_| "Command cannot be empty".to_string())?;
+                let server_args: Vec<String> = match &args["args"] {
+                    Value::Null => Vec::new(),
+                    Value::Array(items) => items
+                        .iter()
+                        .map(|a| a.as_str().map(str::to_string).ok_or("args must be strings"))
+                        .collect::<Result<_, _>>()?,
+                    _ => return Err("args must be an array of strings".to_string()),
+                };
+                config_writer::build_server_value(command, &server_args, &env)
+            }
+            "http" => {
+                let url = required_str(args, "url").map_err(|_| "URL cannot be empty".to_string())?;
+                let headers = string_map(&args["headers"], "headers")?;
+                config_writer::build_http_server_value(url, Some(&headers), &env)
+            }
+            "sse" => {
+                let url = required_str(args, "url").map_err(|_| "URL cannot be empty".to_string())?;
+                config_writer::build_sse_server_value(url, &env)
+            }
+            other => return Err(format!("unknown transport: {}", other)),
+        };
+
+        let replace = args["replace"].as_bool().unwrap_or(false);
+        let existing = discovery::discover(&self.cwd);
+        if !replace {
+            let taken: Vec<&str> = clients
+                .iter()
+                .filter(|c| existing.servers.iter().any(|s| s.name == name && s.client == **c))
+                .map(ClientKind::label)
+                .collect();
+            if !taken.is_empty() {
+                return Err(format!(
+                    "\"{}\" already exists in {}; pass replace: true to overwrite",
+                    name,
+                    taken.join(", ")
+                ));
+            }
+        }
+        let report = self.write_each(&clients, |client| {
+            config_writer::add_server(client, &self.cwd, name, &value)
+        });
+        // A replaced entry is mcpm's own edit, not a change to review
+        self.follow_pins(&existing.servers);
+        Ok(report)
+    }
+
+    fn remove_server(&self, args: &Value) -> ToolResult {
+        let name = required_str(args, "name")?;
+        let result = discovery::discover(&self.cwd);
+        let present: Vec<ClientKind> = result
+            .servers
+            .iter()
+            .filter(|s| s.name == name && ClientKind::writable().contains(&s.client))
+            .map(|s| s.client.clone())
+            .collect();
+        let clients = match &args["clients"] {
+            Value::Null => present,
+            _ => {
+                let wanted = client_list(args, "clients")?;
+                if let Some(missing) = wanted.iter().find(|c| !present.contains(c)) {
+                    return Err(format!("\"{}\" is not configured in {}", name, missing.label()));
+                }
+                wanted
+            }
+        };
+        if clients.is_empty() {
+            return Err(format!("no writable config has a server named \"{}\"", name));
+        }
+        Ok(self.write_each(&clients, |client| config_writer::remove_server(client, &self.cwd, name)))
+    }
+
+    fn sync_server(&self, args: &Value) -> ToolResult {
+        let name = required_str(args, "name")?;
+        let clients = client_list(args, "clients")?;
+        let from = optional_client(args, "from")?;
+        let result = discovery::discover(&self.cwd);
+        let candidates: Vec<&McpServer> = result
+            .servers
+            .iter()
+            .filter(|s| s.name == name && from.as_ref().is_none_or(|c| s.client == *c))
+            .collect();
+        let Some(source) = candidates.first() else {
+            return Err(format!("no server named \"{}\"{}", name, match &from {
+                Some(c) => format!(" in {}", c.label()),
+                None => String::new(),
+            }));
+        };
+        if matches!(source.transport, Transport::Unknown) {
+            return Err("Cannot sync server with unknown transport".to_string());
+        }
+        let value = config_writer::server_value(source);
+        if candidates.iter().any(|s| config_writer::server_value(s) != value) {
+            let labels: Vec<&str> = candidates.iter().map(|s| s.client.label()).collect();
+            return Err(format!(
+                "\"{}\" is defined differently in {}; say which to copy with from",
+                name,
+                labels.join(", ")
+            ));
+        }
+        // Clients that only launch local processes get the server through
+        // mcpm bridge
+        let bridged = if source.transport.is_stdio() {
+            None
+        } else {
+            Some(bridge::bridged_value(source)?)
+        };
+        let report = self.write_each(&clients, |client| match &bridged {
+            Some(bridged) if !client.supports_remote() => config_writer::add_server(client, &self.cwd, name, bridged),
+            _ => config_writer::add_server(client, &self.cwd, name, &value),
+        });
+        self.follow_pins(&result.servers);
+        Ok(report)
+    }
+
+    /// Move pins along with the config edits just made; `before` is the
+    /// discovery from before them
+    fn follow_pins(&self, before: &[McpServer]) {
+        let _guard = self.writes.lock();
+        let mut state = State::load();
+        pins::follow_edits(&mut state, before, &self.cwd);
+        if let Err(e) = state.save() {
+            eprintln!("mcpm mcp: failed to save pins: {}", e);
+        }
+    }
+
+    /// Apply one config edit per client, reporting each outcome
+    fn write_each(
+        &self,
+        clients: &[ClientKind],
+        edit: impl Fn(&ClientKind) -> Result<(), String>,
+    ) -> Value {
+        let _guard = self.writes.lock();
+        let results: Vec<Value> = clients
+            .iter()
+            .map(|client| match edit(client) {
+                Ok(()) => json!({ "client": client.label(), "ok": true }),
+                Err(e) => json!({ "client": client.label(), "ok": false, "error": e }),
+            })
+            .collect();
+        json!({ "results": results })
+    }
+}
+
+impl Handler for ManageServer {
+    fn handle(&self, msg: &Value) -> Reply {
+        let Some(method) = msg["method"].as_str() else {
+            return Reply::None;
+        };
+        let Some(id) = msg.get("id").cloned() else {
+            return Reply::None;
+        };
+        let params = &msg["params"];
+        let result = match method {
+            "initialize" => json!({
+                "protocolVersion": params["protocolVersion"].as_str().unwrap_or(PROTOCOL_VERSION),
+                "capabilities": { "tools": {} },
+                "serverInfo": { "name": "mcpm", "version": env!("CARGO_PKG_VERSION") },
+            }),
+            "ping" => json!({}),
+            "tools/list" => json!({ "tools": self.tools() }),
+            "tools/call" => {
+                let name = params["name"].as_str().unwrap_or("");
+                let args = params.get("arguments").cloned().unwrap_or(json!({}));
+                match self.call(name, &args) {
+                    Ok(value) => json!({
+                        "content": [{ "type": "text", "text": serde_json::to_string_pretty(&value).unwrap_or_default() }],
+                        "structuredContent": value,
+                    }),
+                    Err(e) => json!({ "content": [{ "type": "text", "text": e }], "isError": true }),
+                }
+            }
+            _ => {
+                return Reply::Send(jsonrpc::error_response(
+                    id,
+                    -32601,
+                    &format!("method not found: {}", method),
+                ));
+            }
+        };
+        Reply::Send(jsonrpc::response(id, result))
+    }
+}
+
+/// What a client is told about one server
+fn describe(server: &McpServer) -> Value {
+    let mut entry = json!({
+        "name": server.name,
+        "client": server.client.label(),
+        "transport": server.transport.kind_label(),
+        "source": server.source_path,
+    });
+    match &server.transport {
+        Transport::Stdio { command, args } => {
+            entry["command"] = json!(command);
+            entry["args"] = json!(secrets::mask_args(args));
+        }
+        Transport::Http { url, .. } | Transport::Sse { url } => entry["url"] = json!(secrets::mask_url(url)),
+        Transport::Unknown => {}
+    }
+    // Only the names — values are often credentials
+    if let Some(env) = &server.env {
+        let mut keys: Vec<&String> = env.keys().collect();
+        keys.sort();
+        entry["env"] = json!(keys);
+    }
+    entry
+}
+
+fn required_str<'a>(args: &'a Value, key: &str) -> Result<&'a str, String> {
+    args[key]
+        .as_str()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| format!("{} is required", key))
+}
+
+fn parse_client(label: &str) -> Result<ClientKind, String> {
+    ClientKind::from_label(label).ok_or_else(|| {
+        let labels: Vec<&str> = ClientKind::all().iter().map(ClientKind::label).collect();
+        format!("unknown client \"{}\" (expected one of {})", label, labels.join(", "))
+    })
+}
+
+fn optional_client(args: &Value, key: &str) -> Result<Option<ClientKind>, String> {
+    args[key].as_str().map(parse_client).transpose()
+}
+
+/// A non-empty list of writable clients
+fn client_list(args: &Value, key: &str) -> Result<Vec<ClientKind>, String> {
+    let labels = args[key].as_array().ok_or_else(|| format!("{} must be an array of client labels", key))?;
+    let mut clients = Vec::new();
+    for label in labels {
+        let client = parse_client(label.as_str().unwrap_or(""))?;
+        if !ClientKind::writable().contains(&client) {
+            return Err(format!("{} configs can't be edited", client.label()));
+        }
+        if !clients.contains(&client) {
+            clients.push(client);
+        }
+    }
+    if clients.is_empty() {
+        return Err("Select at least one client".to_string());
+    }
+    Ok(clients)
+}
+
+fn string_map(value: &Value, key: &str) -> Result<HashMap<String, String>, String> {
+    match value {
+        Value::Null => Ok(HashMap::new()),
+        Value::Object(map) => map
+            .iter()
+            .map(|(k, v)| match v.as_str() {
+                Some(s) => Ok((k.clone(), s.to_string())),
+                None => Err(format!("{}.{} must be a string", key, k)),
+            })
+            .collect(),
+        _ => Err(format!("{} must be an object of strings", key)),
+    }
+}
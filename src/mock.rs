@@ -0,0 +1,526 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde_json::{Value, json};
+
+use crate::endpoint::{self, Handler, HttpRequest, HttpResponse, Reply};
+use crate::health::PROTOCOL_VERSION;
+use crate::jsonrpc;
+use crate::oauth;
+
+/// What the mock server serves, loaded from a JSON script:
+///
+/// ```json
+/// {
+///   "serverInfo": { "name": "mock", "version": "1.0.0" },
+///   "tools": [{ "name": "echo", "inputSchema": {…}, "result": { "content": […] } }],
+///   "resources": [{ "uri": "mem://readme", "name": "readme", "text": "…" }],
+///   "resourceTemplates": [{ "uriTemplate": "mem://{id}", "name": "item" }],
+///   "prompts": [{ "name": "greet", "arguments": […], "messages": […] }]
+/// }
+/// ```
+///
+/// A bare array is taken as the tool list. Tools without a scripted `result`
+/// echo their arguments back; `"error": "…"` makes a call fail with isError.
+#[derive(Debug, Clone)]
+pub struct Script {
+    pub server_info: Value,
+    pub tools: Vec<Value>,
+    pub resources: Vec<Value>,
+    pub resource_templates: Vec<Value>,
+    pub prompts: Vec<Value>,
+}
+
+impl Default for Script {
+    fn default() -> Self {
+        Script {
+            server_info: default_server_info(),
+            tools: vec![json!({
+                "name": "echo",
+                "description": "Echo the given text back",
+                "inputSchema": {
+                    "type": "object",
+                    "properties": { "text": { "type": "string" } },
+                    "required": ["text"],
+                },
+            })],
+            resources: Vec::new(),
+            resource_templates: Vec::new(),
+            prompts: Vec::new(),
+        }
+    }
+}
+
+fn default_server_info() -> Value {
+    json!({ "name": "mcpm-mock", "version": env!("CARGO_PKG_VERSION") })
+}
+
+impl Script {
+    pub fn load(path: &Path) -> Result<Script, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        let value: Value = serde_json::from_str(&text)
+            .map_err(|e| format!("failed to parse {}: {}", path.display(), e))?;
+        Self::from_value(value).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    fn from_value(value: Value) -> Result<Script, String> {
+        if let Value::Array(tools) = value {
+            return Ok(Script {
+                tools,
+                ..Script::empty()
+            });
+        }
+        if !value.is_object() {
+            return Err("expected an object or an array of tools".to_string());
+        }
+        let list = |key: &str| -> Result<Vec<Value>, String> {
+            match &value[key] {
+                Value::Null => Ok(Vec::new()),
+                Value::Array(items) => Ok(items.clone()),
+                _ => Err(format!("\"{}\" must be an array", key)),
+            }
+        };
+        Ok(Script {
+            server_info: value
+                .get("serverInfo")
+                .cloned()
+                .unwrap_or_else(default_server_info),
+            tools: list("tools")?,
+            resources: list("resources")?,
+            resource_templates: list("resourceTemplates")?,
+            prompts: list("prompts")?,
+        })
+    }
+
+    fn empty() -> Script {
+        Script {
+            server_info: default_server_info(),
+            tools: Vec::new(),
+            resources: Vec::new(),
+            resource_templates: Vec::new(),
+            prompts: Vec::new(),
+        }
+    }
+
+    fn capabilities(&self) -> Value {
+        let mut caps = json!({});
+        if !self.tools.is_empty() {
+            caps["tools"] = json!({});
+        }
+        if !self.resources.is_empty() || !self.resource_templates.is_empty() {
+            caps["resources"] = json!({});
+        }
+        if !self.prompts.is_empty() {
+            caps["prompts"] = json!({});
+        }
+        caps
+    }
+}
+
+/// Ways to misbehave, so every health-check outcome can be reproduced
+#[derive(Debug, Clone, Default)]
+pub struct Faults {
+    /// Wait this long before every response
+    pub delay: Duration,
+    /// Answer `initialize` with a JSON-RPC error
+    pub fail_initialize: bool,
+    /// Never answer `initialize`
+    pub hang: bool,
+    /// Answer `initialize` with a broken frame, then exit
+    pub malformed: bool,
+    /// Exit without answering `initialize`
+    pub crash: bool,
+    /// Write to stderr continuously
+    pub stderr_spam: bool,
+    /// Ignore stdin closing and SIGTERM, so only SIGKILL stops the server
+    pub ignore_shutdown: bool,
+}
+
+struct MockServer {
+    script: Script,
+    faults: Faults,
+    auth: Option<MockAuth>,
+}
+
+impl Handler for MockServer {
+    fn handle(&self, msg: &Value) -> Reply {
+        let Some(method) = msg["method"].as_str() else {
+            return Reply::None;
+        };
+        let Some(id) = msg.get("id").cloned() else {
+            return Reply::None;
+        };
+
+        if method == "initialize" {
+            if self.faults.crash {
+                eprintln!("mcpm mock-server: crashing on initialize as requested");
+                return Reply::Exit;
+            }
+            if self.faults.hang {
+                return Reply::Hang;
+            }
+        }
+        if !self.faults.delay.is_zero() {
+            std::thread::sleep(self.faults.delay);
+        }
+        if method == "initialize" {
+            if self.faults.malformed {
+                return Reply::Malformed(MALFORMED_FRAME);
+            }
+            if self.faults.fail_initialize {
+                return Reply::Send(jsonrpc::error_response(id, -32603, "initialize failed (mock)"));
+            }
+        }
+
+        let params = &msg["params"];
+        let result = match method {
+            "initialize" => Ok(json!({
+                "protocolVersion": params["protocolVersion"].as_str().unwrap_or(PROTOCOL_VERSION),
+                "capabilities": self.script.capabilities(),
+                "serverInfo": self.script.server_info,
+            })),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({ "tools": self.script.tools.iter().map(strip_tool).collect::<Vec<_>>() })),
+            "tools/call" => self.call_tool(params),
+            "resources/list" => Ok(json!({
+                "resources": self.script.resources.iter().map(strip_resource).collect::<Vec<_>>(),
+            })),
+            "resources/templates/list" => Ok(json!({ "resourceTemplates": self.script.resource_templates })),
+            "resources/read" => self.read_resource(params),
+            "prompts/list" => Ok(json!({
+                "prompts": self.script.prompts.iter().map(strip_prompt).collect::<Vec<_>>(),
+            })),
+            "prompts/get" => self.get_prompt(params),
+            _ => Err((-32601, format!("method not found: {}", method))),
+        };
+        Reply::Send(match result {
+            Ok(result) => jsonrpc::response(id, result),
+            Err((code, message)) => jsonrpc::error_response(id, code, &message),
+        })
+    }
+
+    fn session_id(&self) -> &str {
+        "mcpm-mock-session"
+    }
+
+    fn intercept(&self, request: &HttpRequest) -> Option<HttpResponse> {
+        self.auth.as_ref()?.intercept(request)
+    }
+}
+
+impl MockServer {
+    fn call_tool(&self, params: &Value) -> Result<Value, (i64, String)> {
+        let name = params["name"].as_str().unwrap_or("");
+        let tool = find_by(&self.script.tools, "name", name)
+            .ok_or_else(|| (-32602, format!("unknown tool: {}", name)))?;
+        if let Some(result) = tool.get("result") {
+            return Ok(result.clone());
+        }
+        if let Some(error) = tool["error"].as_str() {
+            return Ok(json!({ "content": [{ "type": "text", "text": error }], "isError": true }));
+        }
+        let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
+        Ok(json!({
+            "content": [{ "type": "text", "text": format!("{} called with {}", name, arguments) }],
+        }))
+    }
+
+    fn read_resource(&self, params: &Value) -> Result<Value, (i64, String)> {
+        let uri = params["uri"].as_str().unwrap_or("");
+        let resource = find_by(&self.script.resources, "uri", uri)
+            .ok_or_else(|| (-32002, format!("resource not found: {}", uri)))?;
+        let mut contents = json!({ "uri": uri, "text": resource["text"].as_str().unwrap_or("") });
+        if let Some(mime) = resource.get("mimeType") {
+            contents["mimeType"] = mime.clone();
+        }
+        Ok(json!({ "contents": [contents] }))
+    }
+
+    fn get_prompt(&self, params: &Value) -> Result<Value, (i64, String)> {
+        let name = params["name"].as_str().unwrap_or("");
+        let prompt = find_by(&self.script.prompts, "name", name)
+            .ok_or_else(|| (-32602, format!("unknown prompt: {}", name)))?;
+        let arguments = params["arguments"].as_object().cloned().unwrap_or_default();
+        let substitute = |text: &str| {
+            arguments.iter().fold(text.to_string(), |text, (key, value)| {
+                text.replace(&format!("{{{}}}", key), value.as_str().unwrap_or(""))
+            })
+        };
+
+        let messages = match prompt["messages"].as_array() {
+            Some(messages) => messages
+                .iter()
+                .map(|m| {
+                    let mut m = m.clone();
+                    if let Some(text) = m["content"]["text"].as_str() {
+                        m["content"]["text"] = json!(substitute(text));
+                    }
+                    m
+                })
+                .collect(),
+            None => vec![json!({
+                "role": "user",
+                "content": { "type": "text", "text": format!("{} with {}", name, Value::Object(arguments.clone())) },
+            })],
+        };
+        let mut result = json!({ "messages": messages });
+        if let Some(description) = prompt.get("description") {
+            result["description"] = description.clone();
+        }
+        Ok(result)
+    }
+}
+
+fn find_by<'a>(items: &'a [Value], key: &str, value: &str) -> Option<&'a Value> {
+    items.iter().find(|item| item[key].as_str() == Some(value))
+}
+
+/// Script-only fields don't belong in list results
+fn strip_tool(tool: &Value) -> Value {
+    without(tool, &["result", "error"])
+}
+
+fn strip_resource(resource: &Value) -> Value {
+    without(resource, &["text"])
+}
+
+fn strip_prompt(prompt: &Value) -> Value {
+    without(prompt, &["messages"])
+}
+
+fn without(item: &Value, keys: &[&str]) -> Value {
+    let mut item = item.clone();
+    if let Some(obj) = item.as_object_mut() {
+        for key in keys {
+            obj.remove(*key);
+        }
+    }
+    item
+}
+
+// ---------------------------------------------------------------------------
+// OAuth
+// ---------------------------------------------------------------------------
+
+/// Scope the mock's tokens are issued for
+const MOCK_SCOPE: &str = "mcp:tools";
+
+/// A protected resource and its own authorization server in one: MCP
+/// requests need a bearer token, which the mock issues through dynamic
+/// registration and an authorization-code + PKCE flow that approves at once
+#[derive(Default)]
+struct MockAuth {
+    /// client_id → registered redirect URIs
+    clients: Mutex<HashMap<String, Vec<String>>>,
+    /// code → (client_id, redirect_uri, code_challenge)
+    codes: Mutex<HashMap<String, (String, String, String)>>,
+    tokens: Mutex<Vec<String>>,
+}
+
+impl MockAuth {
+    fn intercept(&self, request: &HttpRequest) -> Option<HttpResponse> {
+        let base = format!("http://{}", request.header("Host").unwrap_or("127.0.0.1"));
+        let path = request.path();
+        let response = match (request.method.as_str(), path) {
+            ("GET", "/.well-known/oauth-protected-resource" | "/.well-known/oauth-protected-resource/mcp") => {
+                HttpResponse::json("200 OK", &json!({
+                    "resource": format!("{}/mcp", base),
+                    "authorization_servers": [base],
+                    "scopes_supported": [MOCK_SCOPE],
+                    "bearer_methods_supported": ["header"],
+                }))
+            }
+            ("GET", "/.well-known/oauth-authorization-server") => HttpResponse::json("200 OK", &json!({
+                "issuer": base,
+                "authorization_endpoint": format!("{}/authorize", base),
+                "token_endpoint": format!("{}/token", base),
+                "registration_endpoint": format!("{}/register", base),
+                "response_types_supported": ["code"],
+                "grant_types_supported": ["authorization_code"],
+                "code_challenge_methods_supported": ["S256"],
+                "token_endpoint_auth_methods_supported": ["none"],
+                "scopes_supported": [MOCK_SCOPE],
+            })),
+            ("POST", "/register") => self.register(request),
+            ("GET", "/authorize") => self.authorize(request),
+            ("POST", "/token") => self.token(request),
+            _ if self.authorized(request) => return None,
+            _ => HttpResponse {
+                status: "401 Unauthorized",
+                headers: vec![(
+                    "WWW-Authenticate",
+                    format!(
+                        "Bearer resource_metadata=\"{}/.well-known/oauth-protected-resource/mcp\", scope=\"{}\"",
+                        base, MOCK_SCOPE
+                    ),
+                )],
+                body: String::new(),
+            },
+        };
+        Some(response)
+    }
+
+    fn authorized(&self, request: &HttpRequest) -> bool {
+        let Some(token) = request.header("Authorization").and_then(|h| h.strip_prefix("Bearer ")) else {
+            return false;
+        };
+        self.tokens.lock().is_ok_and(|tokens| tokens.iter().any(|t| t == token.trim()))
+    }
+
+    fn register(&self, request: &HttpRequest) -> HttpResponse {
+        let body: Value = serde_json::from_slice(&request.body).unwrap_or_default();
+        let redirect_uris: Vec<String> = body["redirect_uris"]
+            .as_array()
+            .map(|uris| uris.iter().filter_map(|u| u.as_str().map(str::to_string)).collect())
+            .unwrap_or_default();
+        if redirect_uris.is_empty() {
+            return oauth_error("invalid_redirect_uri", "redirect_uris is required");
+        }
+        let Ok(client_id) = oauth::random_token(12) else {
+            return oauth_error("server_error", "no randomness available");
+        };
+        if let Ok(mut clients) = self.clients.lock() {
+            clients.insert(client_id.clone(), redirect_uris.clone());
+        }
+        eprintln!("mcpm mock-server: registered client {}", client_id);
+        HttpResponse::json("201 Created", &json!({
+            "client_id": client_id,
+            "redirect_uris": redirect_uris,
+            "token_endpoint_auth_method": "none",
+        }))
+    }
+
+    /// Approve straight away and send the browser back with a code
+    fn authorize(&self, request: &HttpRequest) -> HttpResponse {
+        let params = oauth::decode_query(request.query());
+        let param = |key: &str| params.get(key).map(String::as_str).unwrap_or("");
+        let client_id = param("client_id");
+        let redirect_uri = param("redirect_uri");
+        let known = self
+            .clients
+            .lock()
+            .is_ok_and(|clients| clients.get(client_id).is_some_and(|uris| uris.iter().any(|u| u == redirect_uri)));
+        if !known {
+            return plain("400 Bad Request", "unknown client_id or redirect_uri");
+        }
+        if param("response_type") != "code" || param("code_challenge_method") != "S256" || param("code_challenge").is_empty() {
+            return plain("400 Bad Request", "expected response_type=code with an S256 code_challenge");
+        }
+        let Ok(code) = oauth::random_token(16) else {
+            return plain("500 Internal Server Error", "no randomness available");
+        };
+        if let Ok(mut codes) = self.codes.lock() {
+            codes.insert(
+                code.clone(),
+                (client_id.to_string(), redirect_uri.to_string(), param("code_challenge").to_string()),
+            );
+        }
+        let separator = if redirect_uri.contains('?') { '&' } else { '?' };
+        let query = oauth::encode_query(&[("code", code), ("state", param("state").to_string())]);
+        HttpResponse {
+            status: "302 Found",
+            headers: vec![("Location", format!("{}{}{}", redirect_uri, separator, query))],
+            body: String::new(),
+        }
+    }
+
+    fn token(&self, request: &HttpRequest) -> HttpResponse {
+        let params = oauth::decode_query(&String::from_utf8_lossy(&request.body));
+        let param = |key: &str| params.get(key).map(String::as_str).unwrap_or("");
+        if param("grant_type") != "authorization_code" {
+            return oauth_error("unsupported_grant_type", "only authorization_code is supported");
+        }
+        let Some((client_id, redirect_uri, challenge)) =
+            self.codes.lock().ok().and_then(|mut codes| codes.remove(param("code")))
+        else {
+            return oauth_error("invalid_grant", "unknown or used code");
+        };
+        if client_id != param("client_id") || redirect_uri != param("redirect_uri") {
+            return oauth_error("invalid_grant", "client_id or redirect_uri doesn't match the code");
+        }
+        if oauth::pkce_challenge(param("code_verifier")) != challenge {
+            return oauth_error("invalid_grant", "code_verifier doesn't match the code_challenge");
+        }
+        let Ok(token) = oauth::random_token(24) else {
+            return oauth_error("server_error", "no randomness available");
+        };
+        if let Ok(mut tokens) = self.tokens.lock() {
+            tokens.push(token.clone());
+        }
+        eprintln!("mcpm mock-server: issued a token to {}", client_id);
+        HttpResponse::json("200 OK", &json!({
+            "access_token": token,
+            "token_type": "Bearer",
+            "expires_in": 3600,
+            "scope": MOCK_SCOPE,
+        }))
+    }
+}
+
+fn oauth_error(error: &str, description: &str) -> HttpResponse {
+    HttpResponse::json("400 Bad Request", &json!({ "error": error, "error_description": description }))
+}
+
+fn plain(status: &'static str, message: &str) -> HttpResponse {
+    HttpResponse {
+        status,
+        headers: vec![("Content-Type", "text/plain".to_string())],
+        body: message.to_string(),
+    }
+}
+
+const MALFORMED_FRAME: &str = "{\"jsonrpc\":\"2.0\",\"id\":1,\"result\":{\"protocolVersion\":\n";
+
+fn start_faults(faults: &Faults) {
+    if faults.stderr_spam {
+        std::thread::spawn(|| {
+            let mut n: u64 = 0;
+            loop {
+                n += 1;
+                eprintln!("mcpm mock-server: noisy log line {} {}", n, "x".repeat(100));
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        });
+    }
+    #[cfg(unix)]
+    if faults.ignore_shutdown {
+        // SAFETY: installing SIG_IGN for SIGTERM has no memory-safety
+        // preconditions
+        unsafe {
+            libc::signal(libc::SIGTERM, libc::SIG_IGN);
+        }
+    }
+}
+
+/// Serve over stdin/stdout until stdin closes
+pub fn run_stdio(script: Script, faults: Faults) -> Result<(), String> {
+    start_faults(&faults);
+    let ignore_shutdown = faults.ignore_shutdown;
+    endpoint::serve_stdio(Arc::new(MockServer {
+        script,
+        faults,
+        auth: None,
+    }))?;
+    if ignore_shutdown {
+        loop {
+            std::thread::sleep(Duration::from_secs(3600));
+        }
+    }
+    Ok(())
+}
+
+/// Serve Streamable HTTP on 127.0.0.1:port, optionally behind OAuth
+pub fn run_http(script: Script, faults: Faults, port: u16, oauth: bool) -> Result<(), String> {
+    start_faults(&faults);
+    let server = MockServer {
+        script,
+        faults,
+        auth: oauth.then(MockAuth::default),
+    };
+    endpoint::serve_http(Arc::new(server), port, |url| {
+        eprintln!("mcpm mock-server listening on {}", url);
+    })
+}
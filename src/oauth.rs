@@ -0,0 +1,500 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::health::TIMEOUT;
+
+/// How long the browser round trip may take before the login gives up
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// How a protected server wants clients to get a token, assembled from its
+/// protected-resource metadata (RFC 9728) and its authorization server's
+/// metadata (RFC 8414 / OpenID discovery)
+#[derive(Debug, Clone)]
+pub struct AuthInfo {
+    /// The server's canonical URL, sent as the `resource` parameter
+    pub resource: String,
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub registration_endpoint: Option<String>,
+    pub scopes: Vec<String>,
+}
+
+/// Follow the discovery chain from a 401: the `WWW-Authenticate` challenge
+/// (or the well-known location) to the protected-resource metadata, then to
+/// the first authorization server's metadata. Servers that publish no
+/// resource metadata are taken to be their own authorization server, as the
+/// 2025-03-26 spec had it.
+pub fn discover(server_url: &str, challenge: Option<&str>) -> Result<AuthInfo, String> {
+    let params = challenge.map(parse_challenge).unwrap_or_default();
+    let (origin, path) = split_url(server_url);
+
+    let mut candidates: Vec<String> = params.get("resource_metadata").cloned().into_iter().collect();
+    if !path.is_empty() {
+        candidates.push(format!("{}/.well-known/oauth-protected-resource{}", origin, path));
+    }
+    candidates.push(format!("{}/.well-known/oauth-protected-resource", origin));
+    let resource_metadata = candidates.iter().find_map(|url| fetch_json(url).ok());
+
+    let (resource, issuer, resource_scopes) = match &resource_metadata {
+        Some(metadata) => {
+            let issuer = metadata["authorization_servers"][0]
+                .as_str()
+                .ok_or("protected-resource metadata lists no authorization server")?;
+            (
+                metadata["resource"].as_str().unwrap_or(server_url).to_string(),
+                issuer.trim_end_matches('/').to_string(),
+                string_list(&metadata["scopes_supported"]),
+            )
+        }
+        None => (server_url.to_string(), origin.clone(), Vec::new()),
+    };
+
+    let (issuer_origin, issuer_path) = split_url(&issuer);
+    let mut candidates = vec![format!("{}/.well-known/oauth-authorization-server{}", issuer_origin, issuer_path)];
+    candidates.push(format!("{}/.well-known/openid-configuration{}", issuer_origin, issuer_path));
+    if !issuer_path.is_empty() {
+        candidates.push(format!("{}/.well-known/openid-configuration", issuer));
+    }
+    let metadata = candidates
+        .iter()
+        .find_map(|url| fetch_json(url).ok())
+        .ok_or_else(|| match resource_metadata {
+            Some(_) => format!("no authorization server metadata for {}", issuer),
+            None => "no OAuth metadata published".to_string(),
+        })?;
+
+    let endpoint = |key: &str| -> Result<String, String> {
+        metadata[key]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| format!("authorization server metadata has no {}", key))
+    };
+    // The challenge names what this request needs; otherwise offer what the
+    // resource, then the authorization server, supports
+    let scopes = match params.get("scope") {
+        Some(scope) => scope.split_whitespace().map(str::to_string).collect(),
+        None if !resource_scopes.is_empty() => resource_scopes,
+        None => string_list(&metadata["scopes_supported"]),
+    };
+    Ok(AuthInfo {
+        resource,
+        issuer: metadata["issuer"].as_str().unwrap_or(&issuer).to_string(),
+        authorization_endpoint: endpoint("authorization_endpoint")?,
+        token_endpoint: endpoint("token_endpoint")?,
+        registration_endpoint: metadata["registration_endpoint"].as_str().map(str::to_string),
+        scopes,
+    })
+}
+
+/// The auth-params of a `Bearer` challenge, e.g.
+/// `Bearer resource_metadata="https://…", scope="read write"`
+fn parse_challenge(header: &str) -> HashMap<String, String> {
+    let mut params = HashMap::new();
+    let Some(rest) = header.trim().strip_prefix("Bearer").or_else(|| header.trim().strip_prefix("bearer")) else {
+        return params;
+    };
+    let mut rest = rest.trim_start();
+    while let Some(eq) = rest.find('=') {
+        let key = rest[..eq].trim().trim_start_matches(',').trim().to_ascii_lowercase();
+        rest = &rest[eq + 1..];
+        let value = if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted.find('"').unwrap_or(quoted.len());
+            let value = &quoted[..end];
+            rest = quoted.get(end + 1..).unwrap_or("");
+            value
+        } else {
+            let end = rest.find(',').unwrap_or(rest.len());
+            let value = rest[..end].trim();
+            rest = &rest[end..];
+            value
+        };
+        params.insert(key, value.to_string());
+        rest = rest.trim_start().trim_start_matches(',').trim_start();
+    }
+    params
+}
+
+/// Origin (`https://host:port`) and path without query or trailing slash
+fn split_url(url: &str) -> (String, String) {
+    let url = &url[..url.find(['?', '#']).unwrap_or(url.len())];
+    let scheme_end = url.find("://").map(|i| i + 3).unwrap_or(0);
+    let origin_end = url[scheme_end..].find('/').map(|i| scheme_end + i).unwrap_or(url.len());
+    (
+        url[..origin_end].to_string(),
+        url[origin_end..].trim_end_matches('/').to_string(),
+    )
+}
+
+fn fetch_json(url: &str) -> Result<Value, String> {
+    let response = ureq::get(url)
+        .set("Accept", "application/json")
+        .timeout(TIMEOUT)
+        .call()
+        .map_err(|e| e.to_string())?;
+    response.into_json().map_err(|e| e.to_string())
+}
+
+fn string_list(value: &Value) -> Vec<String> {
+    value
+        .as_array()
+        .map(|items| items.iter().filter_map(|s| s.as_str().map(str::to_string)).collect())
+        .unwrap_or_default()
+}
+
+// ---------------------------------------------------------------------------
+// Authorization code + PKCE on a loopback redirect
+// ---------------------------------------------------------------------------
+
+pub struct Token {
+    pub access_token: String,
+    pub token_type: String,
+    pub expires_in: Option<u64>,
+    pub refresh_token: Option<String>,
+}
+
+/// Run the authorization-code flow with PKCE (S256) against a redirect on
+/// 127.0.0.1. Without a `client_id` the client is registered dynamically
+/// (RFC 7591). `on_authorize` gets the URL the user has to open.
+pub fn login(info: &AuthInfo, client_id: Option<&str>, on_authorize: impl FnOnce(&str)) -> Result<Token, String> {
+    let listener = TcpListener::bind(("127.0.0.1", 0)).map_err(|e| format!("failed to listen for the redirect: {}", e))?;
+    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+    let redirect_uri = format!("http://127.0.0.1:{}/callback", port);
+
+    let (client_id, client_secret) = match client_id {
+        Some(id) => (id.to_string(), None),
+        None => register(info, &redirect_uri)?,
+    };
+    let verifier = random_token(32)?;
+    let state = random_token(16)?;
+
+    let mut query = vec![
+        ("response_type", "code".to_string()),
+        ("client_id", client_id.clone()),
+        ("redirect_uri", redirect_uri.clone()),
+        ("code_challenge", pkce_challenge(&verifier)),
+        ("code_challenge_method", "S256".to_string()),
+        ("state", state.clone()),
+        ("resource", info.resource.clone()),
+    ];
+    if !info.scopes.is_empty() {
+        query.push(("scope", info.scopes.join(" ")));
+    }
+    let separator = if info.authorization_endpoint.contains('?') { '&' } else { '?' };
+    on_authorize(&format!("{}{}{}", info.authorization_endpoint, separator, encode_query(&query)));
+
+    let code = wait_for_code(&listener, &state)?;
+
+    let mut form = vec![
+        ("grant_type", "authorization_code"),
+        ("code", code.as_str()),
+        ("redirect_uri", redirect_uri.as_str()),
+        ("client_id", client_id.as_str()),
+        ("code_verifier", verifier.as_str()),
+        ("resource", info.resource.as_str()),
+    ];
+    if let Some(secret) = &client_secret {
+        form.push(("client_secret", secret));
+    }
+    let response = ureq::post(&info.token_endpoint)
+        .set("Accept", "application/json")
+        .timeout(TIMEOUT)
+        .send_form(&form)
+        .map_err(|e| format!("token request failed: {}", describe(e)))?;
+    let body: Value = response.into_json().map_err(|e| format!("invalid token response: {}", e))?;
+    let access_token = body["access_token"]
+        .as_str()
+        .ok_or("token response has no access_token")?;
+    Ok(Token {
+        access_token: access_token.to_string(),
+        token_type: body["token_type"].as_str().unwrap_or("Bearer").to_string(),
+        expires_in: body["expires_in"].as_u64(),
+        refresh_token: body["refresh_token"].as_str().map(str::to_string),
+    })
+}
+
+/// Register mcpm as a public client; returns its id and, if the server
+/// issued one anyway, its secret
+fn register(info: &AuthInfo, redirect_uri: &str) -> Result<(String, Option<String>), String> {
+    let endpoint = info
+        .registration_endpoint
+        .as_deref()
+        .ok_or("the authorization server doesn't support dynamic registration; pass --client-id")?;
+    let response = ureq::post(endpoint)
+        .set("Accept", "application/json")
+        .timeout(TIMEOUT)
+        .send_json(serde_json::json!({
+            "client_name": "mcpm",
+            "redirect_uris": [redirect_uri],
+            "grant_types": ["authorization_code", "refresh_token"],
+            "response_types": ["code"],
+            "token_endpoint_auth_method": "none",
+        }))
+        .map_err(|e| format!("client registration failed: {}", describe(e)))?;
+    let body: Value = response.into_json().map_err(|e| format!("invalid registration response: {}", e))?;
+    let id = body["client_id"].as_str().ok_or("registration response has no client_id")?;
+    Ok((id.to_string(), body["client_secret"].as_str().map(str::to_string)))
+}
+
+/// Wait for the browser to come back to the redirect URI and return the code
+fn wait_for_code(listener: &TcpListener, state: &str) -> Result<String, String> {
+    listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+    let deadline = Instant::now() + LOGIN_TIMEOUT;
+    loop {
+        let stream = match listener.accept() {
+            Ok((stream, _)) => stream,
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                if Instant::now() >= deadline {
+                    return Err(format!("no redirect within {}s", LOGIN_TIMEOUT.as_secs()));
+                }
+                std::thread::sleep(Duration::from_millis(100));
+                continue;
+            }
+            Err(e) => return Err(e.to_string()),
+        };
+        let _ = stream.set_nonblocking(false);
+        let _ = stream.set_read_timeout(Some(TIMEOUT));
+        let Some(params) = read_callback(&stream) else {
+            // Favicon and other stray requests
+            respond(stream, "404 Not Found", "Not found");
+            continue;
+        };
+
+        if let Some(error) = params.get("error") {
+            let detail = params.get("error_description").unwrap_or(error);
+            respond(stream, "400 Bad Request", &format!("Authorization failed: {}", detail));
+            return Err(format!("authorization failed: {}", detail));
+        }
+        if params.get("state").map(String::as_str) != Some(state) {
+            respond(stream, "400 Bad Request", "Authorization failed: state mismatch");
+            return Err("state mismatch in redirect".to_string());
+        }
+        let Some(code) = params.get("code") else {
+            respond(stream, "400 Bad Request", "Authorization failed: no code");
+            return Err("redirect carried no code".to_string());
+        };
+        respond(stream, "200 OK", "mcpm is authorized. You can close this tab.");
+        return Ok(code.clone());
+    }
+}
+
+/// The query of a `GET /callback` request
+fn read_callback(stream: &TcpStream) -> Option<HashMap<String, String>> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line).ok()?;
+    let target = line.split_whitespace().nth(1)?;
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    if path != "/callback" {
+        return None;
+    }
+    Some(decode_query(query))
+}
+
+fn respond(mut stream: TcpStream, status: &str, message: &str) {
+    let body = format!("<!doctype html><title>mcpm</title><p>{}</p>", message);
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+}
+
+fn describe(e: ureq::Error) -> String {
+    match e {
+        ureq::Error::Status(code, response) => {
+            let body = response.into_string().unwrap_or_default();
+            format!("HTTP {} {}", code, body.trim())
+        }
+        ureq::Error::Transport(t) => t.to_string(),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Encoding
+// ---------------------------------------------------------------------------
+
+/// Base64url SHA-256 of the verifier, as PKCE's S256 method defines it
+pub fn pkce_challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+/// `bytes` of randomness, base64url-encoded
+pub fn random_token(bytes: usize) -> Result<String, String> {
+    let mut buf = vec![0u8; bytes];
+    getrandom::fill(&mut buf).map_err(|e| format!("no randomness available: {}", e))?;
+    Ok(URL_SAFE_NO_PAD.encode(buf))
+}
+
+pub fn encode_query(pairs: &[(&str, String)]) -> String {
+    pairs
+        .iter()
+        .map(|(k, v)| format!("{}={}", percent_encode(k), percent_encode(v)))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+pub fn decode_query(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter(|p| !p.is_empty())
+        .map(|pair| {
+            let (k, v) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(k), percent_decode(v))
+        })
+        .collect()
+}
+
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                match s.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                    Some(b) => {
+                        out.push(b);
+                        i += 2;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use super::*;
+    use crate::health;
+    use crate::launch::LaunchOptions;
+    use crate::mock::{self, Faults, Script};
+    use crate::types::{ClientKind, HealthMetrics, HealthStatus, McpServer, Transport};
+
+    /// `mcpm mock-server --http --oauth` on a free port; returns its MCP URL
+    fn start_mock() -> String {
+        let port = TcpListener::bind(("127.0.0.1", 0)).unwrap().local_addr().unwrap().port();
+        std::thread::spawn(move || mock::run_http(Script::default(), Faults::default(), port, true));
+        let deadline = Instant::now() + TIMEOUT;
+        while TcpStream::connect(("127.0.0.1", port)).is_err() {
+            assert!(Instant::now() < deadline, "mock server didn't start");
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        format!("http://127.0.0.1:{}/mcp", port)
+    }
+
+    fn http_server(url: &str, token: Option<&str>) -> McpServer {
+        McpServer {
+            name: "mock".to_string(),
+            client: ClientKind::ClaudeCodeProject,
+            source_path: String::new(),
+            transport: Transport::Http {
+                url: url.to_string(),
+                headers: token.map(|t| HashMap::from([("Authorization".to_string(), format!("Bearer {}", t))])),
+            },
+            env: None,
+            health: HealthStatus::Unchecked,
+            last_checked: None,
+            cleanup: None,
+            metrics: HealthMetrics::default(),
+        }
+    }
+
+    /// Play the browser: open the authorization URL and follow its redirect
+    /// to the loopback listener, passing each URL through `tamper` first
+    fn browse(authorize_url: &str, tamper: fn(&str) -> String) {
+        let agent = ureq::AgentBuilder::new().redirects(0).build();
+        let response = agent.get(&tamper(authorize_url)).call().expect("authorize request");
+        let redirect = response.header("Location").expect("redirect to the callback").to_string();
+        let _ = agent.get(&tamper(&redirect)).call();
+    }
+
+    /// Run `login` against the mock with the browser step tampered with
+    fn login_with(info: &AuthInfo, tamper: fn(&str) -> String) -> Result<Token, String> {
+        login(info, None, |url| {
+            let url = url.to_string();
+            // login only listens for the redirect once this returns
+            std::thread::spawn(move || browse(&url, tamper));
+        })
+    }
+
+    fn replace_param(url: &str, key: &str, value: &str) -> String {
+        let (base, query) = url.split_once('?').unwrap();
+        let params = decode_query(query);
+        let params: Vec<(&str, String)> = params
+            .iter()
+            .map(|(k, v)| (k.as_str(), if k == key { value.to_string() } else { v.clone() }))
+            .collect();
+        format!("{}?{}", base, encode_query(&params))
+    }
+
+    #[test]
+    fn check_reports_auth_and_login_gets_a_working_token() {
+        let url = start_mock();
+        let base = url.trim_end_matches("/mcp").to_string();
+
+        let hr = health::check_server_with(0, &http_server(&url, None), &LaunchOptions::default());
+        match hr.status {
+            HealthStatus::AuthRequired { issuer, scopes } => {
+                assert_eq!(issuer, base);
+                assert_eq!(scopes, vec!["mcp:tools".to_string()]);
+            }
+            other => panic!("expected AuthRequired, got {:?}", other),
+        }
+
+        let info = discover(&url, None).unwrap();
+        let token = login_with(&info, |url| url.to_string()).unwrap();
+        assert_eq!(token.token_type, "Bearer");
+
+        let hr = health::check_server_with(0, &http_server(&url, Some(&token.access_token)), &LaunchOptions::default());
+        assert!(matches!(hr.status, HealthStatus::Healthy { .. }), "{:?}", hr.status);
+    }
+
+    #[test]
+    fn login_rejects_a_redirect_with_the_wrong_state() {
+        let info = discover(&start_mock(), None).unwrap();
+        let tamper = |url: &str| {
+            if url.contains("/callback") { replace_param(url, "state", "forged") } else { url.to_string() }
+        };
+        assert_eq!(login_with(&info, tamper).err().as_deref(), Some("state mismatch in redirect"));
+    }
+
+    #[test]
+    fn token_exchange_fails_when_the_verifier_doesnt_match() {
+        let info = discover(&start_mock(), None).unwrap();
+        let tamper = |url: &str| {
+            if url.contains("/authorize") {
+                replace_param(url, "code_challenge", &pkce_challenge("some other verifier"))
+            } else {
+                url.to_string()
+            }
+        };
+        let err = login_with(&info, tamper).err().expect("login should fail");
+        assert!(err.contains("code_verifier doesn't match"), "{}", err);
+    }
+}
@@ -0,0 +1,419 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
+use crate::config_writer;
+use crate::discovery;
+use crate::secrets;
+use crate::state::{State, server_key, unix_secs};
+use crate::types::McpServer;
+
+/// Tool fields a client passes on to the model
+const TOOL_FIELDS: &[&str] = &["name", "title", "description", "inputSchema", "outputSchema", "annotations"];
+
+/// Unchanged lines shown around each change in a diff
+const CONTEXT: usize = 2;
+
+/// Largest changed block (old lines × new lines) aligned line by line; a
+/// schema rewritten wholesale would otherwise take quadratic time and memory
+const MAX_DIFF_CELLS: usize = 250_000;
+
+/// Tool definitions and launch config the user trusts for one server.
+/// Taken on the first check that lists its tools, then only moved by
+/// `mcpm pins accept`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pin {
+    /// Unix timestamp (seconds)
+    pub at: u64,
+    /// Fingerprint of the launch config, secrets included
+    pub config_hash: String,
+    /// The launch config with secrets masked, to diff against
+    pub config: Value,
+    pub tools_hash: String,
+    /// Definitions as pinned, sorted by name
+    pub tools: Vec<Value>,
+    /// Fingerprints of configs and tool lists the user rejected
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rejected: Vec<String>,
+}
+
+impl Pin {
+    pub fn new(server: &McpServer, tools: &[Value], at: SystemTime) -> Pin {
+        let tools = definitions(tools);
+        Pin {
+            at: unix_secs(at),
+            config_hash: fingerprint(&launch_config(server)),
+            config: masked_config(server),
+            tools_hash: fingerprint(&Value::Array(tools.clone())),
+            tools,
+            rejected: Vec::new(),
+        }
+    }
+}
+
+/// Where a server stands against its pin
+pub enum Verdict {
+    /// No check has listed its tools yet
+    Unpinned,
+    Trusted,
+    /// Differs from the pin and hasn't been reviewed
+    Changed(Vec<Change>),
+    /// Differs in a way the user already rejected
+    Rejected(Vec<Change>),
+}
+
+impl Verdict {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Verdict::Unpinned => "unpinned",
+            Verdict::Trusted => "pinned",
+            Verdict::Changed(_) => "changed",
+            Verdict::Rejected(_) => "rejected",
+        }
+    }
+}
+
+/// One difference from the pin
+pub struct Change {
+    /// "config", or the tool's name
+    pub subject: String,
+    pub kind: ChangeKind,
+    pub diff: Vec<DiffLine>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Config,
+    ToolAdded,
+    ToolRemoved,
+    ToolChanged,
+}
+
+impl Change {
+    pub fn summary(&self) -> String {
+        match self.kind {
+            ChangeKind::Config => "launch config changed".to_string(),
+            ChangeKind::ToolAdded => format!("tool {} added", self.subject),
+            ChangeKind::ToolRemoved => format!("tool {} removed", self.subject),
+            ChangeKind::ToolChanged => format!("tool {} changed", self.subject),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffLine {
+    Same(String),
+    Removed(String),
+    Added(String),
+    /// Unchanged lines left out
+    Skipped(usize),
+}
+
+/// Compare a server's current config and last listed tools with its pin
+pub fn verify(state: &State, server: &McpServer) -> Verdict {
+    let Some(pin) = state.get(server).and_then(|s| s.pin.as_ref()) else {
+        return Verdict::Unpinned;
+    };
+    let mut changes = Vec::new();
+    let mut hashes = Vec::new();
+
+    let config_hash = fingerprint(&launch_config(server));
+    if config_hash != pin.config_hash {
+        let mut diff = diff_lines(&pretty_lines(&pin.config), &pretty_lines(&masked_config(server)));
+        if diff.is_empty() {
+            diff.push(DiffLine::Same("(only masked values differ)".to_string()));
+        }
+        changes.push(Change {
+            subject: "config".to_string(),
+            kind: ChangeKind::Config,
+            diff,
+        });
+        hashes.push(config_hash);
+    }
+
+    if let Some(tools) = state.tools(server) {
+        let tools = definitions(tools);
+        let tools_hash = fingerprint(&Value::Array(tools.clone()));
+        if tools_hash != pin.tools_hash {
+            changes.extend(tool_changes(&pin.tools, &tools));
+            hashes.push(tools_hash);
+        }
+    }
+
+    if changes.is_empty() {
+        Verdict::Trusted
+    } else if hashes.iter().all(|h| pin.rejected.contains(h)) {
+        Verdict::Rejected(changes)
+    } else {
+        Verdict::Changed(changes)
+    }
+}
+
+/// Trust the server as it is now. Keeps the pinned tools if none have been
+/// listed since.
+pub fn accept(state: &mut State, server: &McpServer) -> Result<(), String> {
+    let tools = match state.tools(server) {
+        Some(tools) => tools.to_vec(),
+        None => match state.get(server).and_then(|s| s.pin.as_ref()) {
+            Some(pin) => pin.tools.clone(),
+            None => return Err(format!("{} has no tool list yet; run `mcpm check` first", server.name)),
+        },
+    };
+    let pin = Pin::new(server, &tools, SystemTime::now());
+    state.servers.entry(server_key(server)).or_default().pin = Some(pin);
+    Ok(())
+}
+
+/// Keep the pin, and stop asking about the server as it is now. It stays
+/// flagged until it matches the pin again or is accepted.
+pub fn reject(state: &mut State, server: &McpServer) -> Result<(), String> {
+    let config_hash = fingerprint(&launch_config(server));
+    let tools_hash = state
+        .tools(server)
+        .map(|tools| fingerprint(&Value::Array(definitions(tools))));
+    let Some(pin) = state.servers.get_mut(&server_key(server)).and_then(|s| s.pin.as_mut()) else {
+        return Err(format!("{} isn't pinned yet", server.name));
+    };
+    let changed: Vec<String> = std::iter::once(config_hash)
+        .chain(tools_hash)
+        .filter(|h| *h != pin.config_hash && *h != pin.tools_hash)
+        .collect();
+    if changed.is_empty() {
+        return Err(format!("{} matches its pin; nothing to reject", server.name));
+    }
+    for hash in changed {
+        if !pin.rejected.contains(&hash) {
+            pin.rejected.push(hash);
+        }
+    }
+    Ok(())
+}
+
+/// Carry pins across config edits mcpm made itself, so its own writes
+/// aren't reported as changes. `before` is the discovery from before the
+/// edits; an entry that already differed from its pin is left for review.
+pub fn follow_edits(state: &mut State, before: &[McpServer], cwd: &Path) {
+    let after = discovery::discover(cwd);
+    for old in before {
+        let key = server_key(old);
+        let Some(pin) = state.servers.get_mut(&key).and_then(|s| s.pin.as_mut()) else {
+            continue;
+        };
+        if pin.config_hash != fingerprint(&launch_config(old)) {
+            continue;
+        }
+        if let Some(new) = after.servers.iter().find(|s| server_key(s) == key) {
+            pin.config_hash = fingerprint(&launch_config(new));
+            pin.config = masked_config(new);
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Fingerprints
+// ---------------------------------------------------------------------------
+
+/// What decides what runs: transport, command line, URL and env. Header
+/// values are left out; they are credentials that rotate, like the token
+/// `mcpm login` writes.
+fn launch_config(server: &McpServer) -> Value {
+    let mut value = config_writer::server_value(server);
+    if let Some(headers) = value.get_mut("headers").and_then(Value::as_object_mut) {
+        headers.values_mut().for_each(|v| *v = Value::String(String::new()));
+    }
+    value
+}
+
+fn masked_config(server: &McpServer) -> Value {
+    let mut value = launch_config(server);
+    if let Some(args) = value.get_mut("args") {
+        let plain: Vec<String> = serde_json::from_value(args.clone()).unwrap_or_default();
+        *args = Value::from(secrets::mask_args(&plain));
+    }
+    if let Some(Value::String(url)) = value.get_mut("url") {
+        *url = secrets::mask_url(url);
+    }
+    if let Some(env) = value.get_mut("env").and_then(Value::as_object_mut) {
+        for (key, v) in env.iter_mut() {
+            if let Value::String(s) = v {
+                *s = secrets::display(key, s);
+            }
+        }
+    }
+    value
+}
+
+/// The parts of each tool a model sees, sorted by name
+fn definitions(tools: &[Value]) -> Vec<Value> {
+    let mut defs: Vec<Value> = tools
+        .iter()
+        .map(|tool| {
+            let fields: Map<String, Value> = TOOL_FIELDS
+                .iter()
+                .filter_map(|f| Some((f.to_string(), tool.get(*f)?.clone())))
+                .collect();
+            Value::Object(fields)
+        })
+        .collect();
+    defs.sort_by_key(tool_name);
+    defs
+}
+
+fn tool_name(tool: &Value) -> String {
+    tool["name"].as_str().unwrap_or_default().to_string()
+}
+
+/// sha256 of the compact JSON; object keys are already sorted
+fn fingerprint(value: &Value) -> String {
+    Sha256::digest(value.to_string().as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// Diffs
+// ---------------------------------------------------------------------------
+
+fn tool_changes(pinned: &[Value], current: &[Value]) -> Vec<Change> {
+    let old: BTreeMap<String, &Value> = pinned.iter().map(|t| (tool_name(t), t)).collect();
+    let new: BTreeMap<String, &Value> = current.iter().map(|t| (tool_name(t), t)).collect();
+    let mut names: Vec<&String> = old.keys().chain(new.keys()).collect();
+    names.sort();
+    names.dedup();
+
+    let mut changes = Vec::new();
+    for name in names {
+        let (kind, diff) = match (old.get(name), new.get(name)) {
+            (Some(a), Some(b)) if a == b => continue,
+            (Some(a), Some(b)) => (ChangeKind::ToolChanged, diff_lines(&tool_lines(a), &tool_lines(b))),
+            (Some(a), None) => (
+                ChangeKind::ToolRemoved,
+                tool_lines(a).into_iter().map(DiffLine::Removed).collect(),
+            ),
+            (None, Some(b)) => (
+                ChangeKind::ToolAdded,
+                tool_lines(b).into_iter().map(DiffLine::Added).collect(),
+            ),
+            (None, None) => continue,
+        };
+        changes.push(Change {
+            subject: name.clone(),
+            kind,
+            diff,
+        });
+    }
+    changes
+}
+
+/// A tool definition as reviewable lines: descriptions as text, so a
+/// changed sentence shows up as one changed line, and schemas as JSON
+fn tool_lines(tool: &Value) -> Vec<String> {
+    let mut lines = Vec::new();
+    for field in &TOOL_FIELDS[1..] {
+        match tool.get(*field) {
+            None => {}
+            Some(Value::String(text)) => {
+                lines.push(format!("{}:", field));
+                lines.extend(text.lines().map(|l| format!("  {}", l)));
+            }
+            Some(value) => {
+                lines.push(format!("{}:", field));
+                lines.extend(pretty_lines(value).into_iter().map(|l| format!("  {}", l)));
+            }
+        }
+    }
+    lines
+}
+
+fn pretty_lines(value: &Value) -> Vec<String> {
+    serde_json::to_string_pretty(value)
+        .unwrap_or_default()
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+/// Line diff by longest common subsequence, with runs of unchanged lines
+/// trimmed to CONTEXT around each change. Empty if nothing changed.
+fn diff_lines(old: &[String], new: &[String]) -> Vec<DiffLine> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (old_mid, new_mid) = (&old[prefix..old.len() - suffix], &new[prefix..new.len() - suffix]);
+
+    let mut full: Vec<DiffLine> = old[..prefix].iter().cloned().map(DiffLine::Same).collect();
+    if old_mid.len() * new_mid.len() > MAX_DIFF_CELLS {
+        // Too big to align; list the changed block as removed then added
+        full.extend(old_mid.iter().cloned().map(DiffLine::Removed));
+        full.extend(new_mid.iter().cloned().map(DiffLine::Added));
+    } else {
+        full.extend(lcs_diff(old_mid, new_mid));
+    }
+    full.extend(old[old.len() - suffix..].iter().cloned().map(DiffLine::Same));
+    if full.iter().all(|l| matches!(l, DiffLine::Same(_))) {
+        return Vec::new();
+    }
+
+    let near_change = |k: usize| {
+        full[k.saturating_sub(CONTEXT)..(k + CONTEXT + 1).min(full.len())]
+            .iter()
+            .any(|l| !matches!(l, DiffLine::Same(_)))
+    };
+    let mut diff = Vec::new();
+    let mut skipped = 0;
+    for (k, line) in full.iter().enumerate() {
+        if near_change(k) {
+            if skipped > 0 {
+                diff.push(DiffLine::Skipped(skipped));
+                skipped = 0;
+            }
+            diff.push(line.clone());
+        } else {
+            skipped += 1;
+        }
+    }
+    if skipped > 0 {
+        diff.push(DiffLine::Skipped(skipped));
+    }
+    diff
+}
+
+fn lcs_diff(old: &[String], new: &[String]) -> Vec<DiffLine> {
+    let (n, m) = (old.len(), new.len());
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut full = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && old[i] == new[j] {
+            full.push(DiffLine::Same(old[i].clone()));
+            i += 1;
+            j += 1;
+        } else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            full.push(DiffLine::Removed(old[i].clone()));
+            i += 1;
+        } else {
+            full.push(DiffLine::Added(new[j].clone()));
+            j += 1;
+        }
+    }
+    full
+}
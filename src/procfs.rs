@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Resource usage of a process group, sampled from /proc
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GroupUsage {
+    pub peak_rss_bytes: u64,
+    pub cpu_time: Duration,
+}
+
+/// Background sampler that tracks peak RSS and CPU time of every process in
+/// a group. Short-lived processes that start and exit between two samples
+/// are missed, so the numbers are a lower bound.
+pub struct GroupSampler {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<Option<GroupUsage>>,
+}
+
+impl GroupSampler {
+    pub fn start(pgid: i32) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let flag = stop.clone();
+        let handle = std::thread::spawn(move || sample_until(pgid, &flag));
+        GroupSampler { stop, handle }
+    }
+
+    /// Take one last sample and return the totals, or None if the group
+    /// was already gone before the first sample
+    pub fn finish(self) -> Option<GroupUsage> {
+        self.stop.store(true, Ordering::Relaxed);
+        self.handle.join().ok().flatten()
+    }
+}
+
+fn sample_until(pgid: i32, stop: &AtomicBool) -> Option<GroupUsage> {
+    let page_size = sysconf(libc::_SC_PAGESIZE).unwrap_or(4096);
+    let ticks_per_sec = sysconf(libc::_SC_CLK_TCK).unwrap_or(100).max(1);
+
+    let mut peak_rss_pages = 0u64;
+    let mut seen_any = false;
+    // Latest utime+stime seen per pid — a pid's counters only grow
+    let mut cpu_ticks: HashMap<u32, u64> = HashMap::new();
+
+    loop {
+        let done = stop.load(Ordering::Relaxed);
+        let mut rss_pages = 0u64;
+        for (pid, stat) in group_stats(pgid) {
+            seen_any = true;
+            rss_pages += stat.rss_pages;
+            cpu_ticks.insert(pid, stat.utime + stat.stime);
+        }
+        peak_rss_pages = peak_rss_pages.max(rss_pages);
+        if done {
+            break;
+        }
+        std::thread::sleep(SAMPLE_INTERVAL);
+    }
+
+    if !seen_any {
+        return None;
+    }
+    let total_ticks: u64 = cpu_ticks.values().sum();
+    Some(GroupUsage {
+        peak_rss_bytes: peak_rss_pages * page_size,
+        cpu_time: Duration::from_millis(total_ticks * 1000 / ticks_per_sec),
+    })
+}
+
+/// Fields of /proc/<pid>/stat we care about
+#[derive(Debug, Clone)]
+pub struct ProcStat {
+    pub state: char,
+    pub pgrp: i32,
+    pub utime: u64,
+    pub stime: u64,
+    pub rss_pages: u64,
+}
+
+pub fn read_stat(pid: u32) -> Option<ProcStat> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // comm may contain spaces and parens, so split after the last ')'
+    let rest = &stat[stat.rfind(')')? + 1..];
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+    // rest[0] is field 3 (state) in proc(5) numbering
+    let field = |n: usize| fields.get(n - 3).copied();
+    Some(ProcStat {
+        state: field(3)?.chars().next()?,
+        pgrp: field(5)?.parse().ok()?,
+        utime: field(14)?.parse().ok()?,
+        stime: field(15)?.parse().ok()?,
+        rss_pages: field(24)?.parse().ok()?,
+    })
+}
+
+/// Live (non-zombie) members of a process group
+pub fn group_stats(pgid: i32) -> Vec<(u32, ProcStat)> {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok())
+        .filter_map(|pid| Some((pid, read_stat(pid)?)))
+        .filter(|(_, stat)| stat.pgrp == pgid && stat.state != 'Z' && stat.state != 'X')
+        .collect()
+}
+
+fn sysconf(name: libc::c_int) -> Option<u64> {
+    // SAFETY: sysconf only reads a configuration value
+    let v = unsafe { libc::sysconf(name) };
+    (v > 0).then_some(v as u64)
+}
@@ -0,0 +1,359 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::jsonrpc::{self, FrameDecoder};
+
+/// Hidden subcommand a wrapped config entry runs
+pub const RUN_SUBCOMMAND: &str = "proxy-run";
+
+/// One line of a traffic log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogEntry {
+    /// Unix timestamp in milliseconds
+    pub at_ms: u64,
+    pub from: Side,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<Value>,
+    /// stderr output, or a note from the proxy itself
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Client,
+    Server,
+    Stderr,
+    Proxy,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Where logs for a server go: ~/.local/share/mcpm/proxy/<server>/
+pub fn log_dir(server_name: &str) -> Option<PathBuf> {
+    let safe: String = server_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    Some(dirs::data_dir()?.join("mcpm/proxy").join(safe))
+}
+
+/// The command line that runs `command args` through the proxy
+pub fn wrap(log_dir: &Path, command: &str, args: &[String]) -> Result<(String, Vec<String>), String> {
+    let exe = std::env::current_exe().map_err(|e| format!("could not locate mcpm: {}", e))?;
+    let mut wrapped = vec![
+        RUN_SUBCOMMAND.to_string(),
+        "--log-dir".to_string(),
+        log_dir.display().to_string(),
+        "--".to_string(),
+        command.to_string(),
+    ];
+    wrapped.extend(args.iter().cloned());
+    Ok((exe.display().to_string(), wrapped))
+}
+
+/// The original command line of a wrapped entry, or None if it isn't wrapped
+pub fn unwrap(args: &[String]) -> Option<(String, Vec<String>)> {
+    if args.first().map(String::as_str) != Some(RUN_SUBCOMMAND) {
+        return None;
+    }
+    let sep = args.iter().position(|a| a == "--")?;
+    let command = args.get(sep + 1)?.clone();
+    Some((command, args[sep + 2..].to_vec()))
+}
+
+/// Logs, newest last
+pub fn list_logs(dir: &Path) -> Vec<PathBuf> {
+    let mut logs: Vec<PathBuf> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|x| x == "jsonl"))
+        .collect();
+    logs.sort();
+    logs
+}
+
+// ---------------------------------------------------------------------------
+// Recording
+// ---------------------------------------------------------------------------
+
+#[derive(Clone)]
+struct Recorder(Arc<Mutex<BufWriter<File>>>);
+
+impl Recorder {
+    fn write(&self, from: Side, message: Option<Value>, text: Option<String>) {
+        let entry = LogEntry {
+            at_ms: now_ms(),
+            from,
+            message,
+            text,
+        };
+        let Ok(line) = serde_json::to_string(&entry) else {
+            return;
+        };
+        if let Ok(mut out) = self.0.lock() {
+            // Flushed per line so a crash still leaves a usable log
+            let _ = writeln!(out, "{}", line);
+            let _ = out.flush();
+        }
+    }
+}
+
+/// Copy `input` to `output` byte for byte, logging each JSON-RPC message
+/// that passes through
+fn pump(mut input: impl Read, mut output: impl Write, from: Side, recorder: &Recorder) {
+    let mut decoder = FrameDecoder::default();
+    let mut buf = vec![0u8; 8192];
+    loop {
+        let n = match input.read(&mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => n,
+        };
+        if output.write_all(&buf[..n]).and_then(|_| output.flush()).is_err() {
+            break;
+        }
+        decoder.push(&buf[..n]);
+        while let Some(msg) = decoder.next_message() {
+            recorder.write(from, Some(msg), None);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+    std::fs::DirBuilder::new().recursive(true).mode(0o700).create(dir)?;
+    // An existing directory keeps its mode otherwise
+    std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))
+}
+
+#[cfg(not(unix))]
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)
+}
+
+#[cfg(unix)]
+fn create_private(path: &Path) -> std::io::Result<File> {
+    use std::os::unix::fs::OpenOptionsExt;
+    std::fs::OpenOptions::new().write(true).create_new(true).mode(0o600).open(path)
+}
+
+#[cfg(not(unix))]
+fn create_private(path: &Path) -> std::io::Result<File> {
+    File::create(path)
+}
+
+/// Run `command args`, forwarding stdio while logging the traffic to a new
+/// file in `log_dir`. Returns the server's exit code.
+pub fn run(log_dir: &Path, command: &str, args: &[String]) -> Result<i32, String> {
+    // Logs hold full tool arguments and results, tokens included
+    create_private_dir(log_dir).map_err(|e| format!("failed to create {}: {}", log_dir.display(), e))?;
+    let path = log_dir.join(format!("{}-{}.jsonl", now_ms(), std::process::id()));
+    let file = create_private(&path).map_err(|e| format!("failed to create {}: {}", path.display(), e))?;
+    let recorder = Recorder(Arc::new(Mutex::new(BufWriter::new(file))));
+
+    let mut child = Command::new(command)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| {
+            let msg = format!("failed to start {}: {}", command, e);
+            recorder.write(Side::Proxy, None, Some(msg.clone()));
+            msg
+        })?;
+    recorder.write(
+        Side::Proxy,
+        None,
+        Some(format!("started: {} {}", command, args.join(" ")).trim_end().to_string()),
+    );
+
+    if let Some(stdin) = child.stdin.take() {
+        let recorder = recorder.clone();
+        // Returning drops the child's stdin, passing the client's EOF on
+        std::thread::spawn(move || pump(std::io::stdin(), stdin, Side::Client, &recorder));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        let recorder = recorder.clone();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stderr).lines() {
+                let Ok(line) = line else { break };
+                eprintln!("{}", line);
+                recorder.write(Side::Stderr, None, Some(line));
+            }
+        });
+    }
+    if let Some(stdout) = child.stdout.take() {
+        pump(stdout, std::io::stdout(), Side::Server, &recorder);
+    }
+
+    let status = child.wait().map_err(|e| e.to_string())?;
+    recorder.write(Side::Proxy, None, Some(format!("exited: {}", status)));
+    Ok(status.code().unwrap_or(1))
+}
+
+// ---------------------------------------------------------------------------
+// Replay
+// ---------------------------------------------------------------------------
+
+pub fn read_log(path: &Path) -> Result<Vec<LogEntry>, String> {
+    let file = File::open(path).map_err(|e| format!("failed to open {}: {}", path.display(), e))?;
+    let mut entries = Vec::new();
+    for (n, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| e.to_string())?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        // A proxy killed mid-write can leave a torn last line
+        match serde_json::from_str(&line) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => eprintln!("{}:{}: skipped: {}", path.display(), n + 1, e),
+        }
+    }
+    Ok(entries)
+}
+
+/// Answers live requests from a recorded exchange
+struct Replayer {
+    entries: Vec<LogEntry>,
+    used: HashSet<usize>,
+    realtime: bool,
+}
+
+impl Replayer {
+    /// Index of the first unused recorded request for this method, preferring
+    /// one with identical params
+    fn find_request(&self, method: &str, params: &Value) -> Option<usize> {
+        let candidates: Vec<usize> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(i, e)| {
+                e.from == Side::Client
+                    && !self.used.contains(i)
+                    && e.message.as_ref().is_some_and(|m| {
+                        m["method"] == method && m.get("id").is_some()
+                    })
+            })
+            .map(|(i, _)| i)
+            .collect();
+        candidates
+            .iter()
+            .copied()
+            .find(|&i| self.entries[i].message.as_ref().is_some_and(|m| m["params"] == *params))
+            .or_else(|| candidates.first().copied())
+    }
+
+    /// What the server sent for the recorded request at `index`: anything it
+    /// initiated in between (progress, logging), then the response itself,
+    /// with the recorded id swapped for the live one
+    fn answer(&mut self, index: usize, live_id: &Value) -> Vec<(Duration, Output)> {
+        self.used.insert(index);
+        let request = &self.entries[index];
+        let recorded_id = request.message.as_ref().map(|m| m["id"].clone()).unwrap_or_default();
+        let started = request.at_ms;
+
+        let mut out = Vec::new();
+        for (i, entry) in self.entries.iter().enumerate().skip(index + 1) {
+            let delay = Duration::from_millis(entry.at_ms.saturating_sub(started));
+            match (entry.from, &entry.message, &entry.text) {
+                (Side::Server, Some(msg), _) if msg.get("method").is_none() && msg["id"] == recorded_id => {
+                    let mut msg = msg.clone();
+                    msg["id"] = live_id.clone();
+                    out.push((delay, Output::Message(msg)));
+                    self.used.insert(i);
+                    break;
+                }
+                (Side::Server, Some(msg), _) if msg.get("method").is_some() && !self.used.contains(&i) => {
+                    out.push((delay, Output::Message(msg.clone())));
+                    self.used.insert(i);
+                }
+                (Side::Stderr, _, Some(text)) if !self.used.contains(&i) => {
+                    out.push((delay, Output::Stderr(text.clone())));
+                    self.used.insert(i);
+                }
+                _ => {}
+            }
+        }
+        out
+    }
+}
+
+enum Output {
+    Message(Value),
+    Stderr(String),
+}
+
+/// Play back the server side of a log on stdio. Requests with no recorded
+/// counterpart get an error response and a note on stderr.
+pub fn replay(log: &Path, realtime: bool) -> Result<(), String> {
+    let entries = read_log(log)?;
+    let mut replayer = Replayer {
+        entries,
+        used: HashSet::new(),
+        realtime,
+    };
+
+    let mut stdin = std::io::stdin().lock();
+    let mut stdout = std::io::stdout().lock();
+    let mut decoder = FrameDecoder::default();
+    let mut buf = vec![0u8; 8192];
+    loop {
+        let n = stdin.read(&mut buf).map_err(|e| e.to_string())?;
+        if n == 0 {
+            return Ok(());
+        }
+        decoder.push(&buf[..n]);
+        while let Some(msg) = decoder.next_message() {
+            // Notifications and answers to server requests need no reply
+            let (Some(method), Some(id)) = (msg["method"].as_str(), msg.get("id")) else {
+                continue;
+            };
+            let outputs = match replayer.find_request(method, &msg["params"]) {
+                Some(index) => replayer.answer(index, id),
+                None => {
+                    eprintln!("mcpm replay: no recorded response for {}", method);
+                    let error = jsonrpc::error_response(
+                        id.clone(),
+                        -32603,
+                        &format!("mcpm replay: no recorded response for {}", method),
+                    );
+                    vec![(Duration::ZERO, Output::Message(error))]
+                }
+            };
+            let mut waited = Duration::ZERO;
+            for (delay, output) in outputs {
+                if replayer.realtime && delay > waited {
+                    std::thread::sleep(delay - waited);
+                    waited = delay;
+                }
+                match output {
+                    Output::Message(msg) => {
+                        stdout
+                            .write_all(jsonrpc::encode(&msg).as_bytes())
+                            .and_then(|_| stdout.flush())
+                            .map_err(|e| e.to_string())?;
+                    }
+                    Output::Stderr(text) => eprintln!("{}", text),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,443 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde_json::{Value, json};
+
+use crate::jsonrpc;
+
+/// Longest we wait for a legacy SSE server to announce its POST endpoint
+const ENDPOINT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Why a message didn't reach a remote server
+#[derive(Debug)]
+pub enum RemoteError {
+    /// HTTP 401; carries the server's `WWW-Authenticate` challenge
+    Unauthorized(Option<String>),
+    Timeout,
+    Failed(String),
+}
+
+impl std::fmt::Display for RemoteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RemoteError::Unauthorized(_) => write!(f, "unauthorized (HTTP 401)"),
+            RemoteError::Timeout => write!(f, "timeout"),
+            RemoteError::Failed(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// Client side of a remote MCP server: Streamable HTTP, or the older
+/// HTTP+SSE transport (`"type": "sse"`). Messages the server sends — responses
+/// and anything it initiates — are handed to `on_message` as they arrive.
+pub struct RemoteSession {
+    agent: ureq::Agent,
+    headers: Vec<(String, String)>,
+    kind: RemoteKind,
+    on_message: Arc<dyn Fn(Value) + Send + Sync>,
+    /// Tells a legacy SSE stream reader to stop at its next event
+    closed: Arc<AtomicBool>,
+}
+
+enum RemoteKind {
+    Streamable {
+        url: String,
+        session_id: Mutex<Option<String>>,
+    },
+    /// Messages arrive on the GET stream; requests are POSTed to the
+    /// endpoint the server announced
+    LegacySse { endpoint: String },
+}
+
+impl RemoteSession {
+    /// `timeout` bounds each HTTP request, including reading its response
+    pub fn streamable(
+        url: &str,
+        headers: &HashMap<String, String>,
+        timeout: Duration,
+        on_message: impl Fn(Value) + Send + Sync + 'static,
+    ) -> RemoteSession {
+        RemoteSession {
+            agent: agent(Some(timeout)),
+            headers: sorted(headers),
+            kind: RemoteKind::Streamable {
+                url: url.to_string(),
+                session_id: Mutex::new(None),
+            },
+            on_message: Arc::new(on_message),
+            closed: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Open the event stream and wait for the server's `endpoint` event.
+    /// `on_close` runs if the server ends the stream.
+    pub fn legacy_sse(
+        url: &str,
+        headers: &HashMap<String, String>,
+        timeout: Duration,
+        on_message: impl Fn(Value) + Send + Sync + 'static,
+        on_close: impl FnOnce() + Send + 'static,
+    ) -> Result<RemoteSession, RemoteError> {
+        let headers = sorted(headers);
+        // The stream may sit idle for as long as the server likes
+        let mut request = agent(None).get(url).set("Accept", "text/event-stream");
+        for (name, value) in &headers {
+            request = request.set(name, value);
+        }
+        let response = request.call().map_err(describe_error)?;
+
+        let on_message: Arc<dyn Fn(Value) + Send + Sync> = Arc::new(on_message);
+        let closed = Arc::new(AtomicBool::new(false));
+        let (endpoint_tx, endpoint_rx) = mpsc::channel();
+        let deliver = on_message.clone();
+        let stop = closed.clone();
+        std::thread::spawn(move || {
+            for event in SseEvents::new(response.into_reader()) {
+                if stop.load(Ordering::Relaxed) {
+                    return;
+                }
+                match event.name.as_deref() {
+                    Some("endpoint") => {
+                        let _ = endpoint_tx.send(event.data);
+                    }
+                    _ => {
+                        if let Ok(msg) = serde_json::from_str(&event.data) {
+                            deliver(msg);
+                        }
+                    }
+                }
+            }
+            if !stop.load(Ordering::Relaxed) {
+                on_close();
+            }
+        });
+
+        let endpoint = endpoint_rx.recv_timeout(ENDPOINT_TIMEOUT).map_err(|_| {
+            closed.store(true, Ordering::Relaxed);
+            RemoteError::Failed("server did not announce a message endpoint".to_string())
+        })?;
+        Ok(RemoteSession {
+            agent: agent(Some(timeout)),
+            headers,
+            kind: RemoteKind::LegacySse {
+                endpoint: resolve_url(url, endpoint.trim()),
+            },
+            on_message,
+            closed,
+        })
+    }
+
+    /// End the session: Streamable HTTP servers are sent the DELETE the spec
+    /// asks for; a legacy SSE stream is dropped
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::Relaxed);
+        if let RemoteKind::Streamable { url, session_id } = &self.kind
+            && let Some(id) = session_id.lock().ok().and_then(|s| s.clone())
+        {
+            let mut request = self.agent.delete(url).set("Mcp-Session-Id", &id);
+            for (name, value) in &self.headers {
+                request = request.set(name, value);
+            }
+            let _ = request.call();
+        }
+    }
+
+    /// Send one message. Whatever the server answers on this request is
+    /// passed to `on_message` before this returns.
+    pub fn send(&self, msg: &Value) -> Result<(), RemoteError> {
+        let url = match &self.kind {
+            RemoteKind::Streamable { url, .. } => url,
+            RemoteKind::LegacySse { endpoint } => endpoint,
+        };
+        let mut request = self
+            .agent
+            .post(url)
+            .set("Content-Type", "application/json")
+            .set("Accept", "application/json, text/event-stream");
+        for (name, value) in &self.headers {
+            request = request.set(name, value);
+        }
+        if let RemoteKind::Streamable { session_id, .. } = &self.kind
+            && let Some(id) = session_id.lock().ok().and_then(|s| s.clone())
+        {
+            request = request.set("Mcp-Session-Id", &id);
+        }
+
+        let response = request.send_string(&msg.to_string()).map_err(describe_error)?;
+        let RemoteKind::Streamable { session_id, .. } = &self.kind else {
+            // Legacy SSE answers on the event stream
+            return Ok(());
+        };
+        if let Some(id) = response.header("Mcp-Session-Id")
+            && let Ok(mut current) = session_id.lock()
+        {
+            *current = Some(id.to_string());
+        }
+        if response.status() == 202 {
+            return Ok(());
+        }
+
+        let content_type = response.content_type().to_string();
+        if content_type == "text/event-stream" {
+            // Read until our response turns up; anything else on the stream
+            // (progress, logging) is passed along on the way
+            let id = msg.get("id").cloned();
+            for event in SseEvents::new(response.into_reader()) {
+                let Ok(reply) = serde_json::from_str::<Value>(&event.data) else {
+                    continue;
+                };
+                let done = id.is_some() && reply.get("method").is_none() && reply.get("id") == id.as_ref();
+                (self.on_message)(reply);
+                if done {
+                    break;
+                }
+            }
+            return Ok(());
+        }
+
+        let body = response.into_string().map_err(io_error)?;
+        if body.trim().is_empty() {
+            return Ok(());
+        }
+        match serde_json::from_str::<Value>(&body) {
+            Ok(Value::Array(batch)) => batch.into_iter().for_each(|m| (self.on_message)(m)),
+            Ok(reply) => (self.on_message)(reply),
+            Err(_) => return Err(RemoteError::Failed(format!("invalid response: {}", truncate(&body, 200)))),
+        }
+        Ok(())
+    }
+}
+
+/// Request/response use of a remote server, the way `health::Session` is
+/// used for stdio ones
+pub struct RemoteClient {
+    session: RemoteSession,
+    replies: mpsc::Receiver<Value>,
+    next_id: u64,
+}
+
+impl RemoteClient {
+    pub fn connect(
+        url: &str,
+        headers: &HashMap<String, String>,
+        legacy_sse: bool,
+        timeout: Duration,
+    ) -> Result<RemoteClient, RemoteError> {
+        let (tx, replies) = mpsc::channel();
+        let deliver = move |msg| {
+            let _ = tx.send(msg);
+        };
+        let session = if legacy_sse {
+            RemoteSession::legacy_sse(url, headers, timeout, deliver, || {})?
+        } else {
+            RemoteSession::streamable(url, headers, timeout, deliver)
+        };
+        Ok(RemoteClient {
+            session,
+            replies,
+            next_id: 1,
+        })
+    }
+
+    /// Send a request and wait for its result
+    pub fn request(&mut self, method: &str, params: Value, timeout: Duration) -> Result<Value, RemoteError> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let deadline = Instant::now() + timeout;
+        self.session.send(&jsonrpc::request(id, method, params))?;
+        loop {
+            let wait = deadline.saturating_duration_since(Instant::now());
+            let msg = self.replies.recv_timeout(wait).map_err(|_| RemoteError::Timeout)?;
+            if msg.get("method").is_some() {
+                self.answer_server_message(&msg);
+                continue;
+            }
+            if msg["id"].as_u64() != Some(id) {
+                continue;
+            }
+            if let Some(err) = msg.get("error") {
+                let text = err["message"].as_str().unwrap_or("unknown error");
+                return Err(RemoteError::Failed(format!("server error: {}", text)));
+            }
+            return Ok(msg.get("result").cloned().unwrap_or(Value::Null));
+        }
+    }
+
+    /// Follow `nextCursor` through a paginated list method, collecting `key`
+    pub fn list_all(&mut self, method: &str, key: &str, timeout: Duration) -> Result<Vec<Value>, RemoteError> {
+        let mut items = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let params = match &cursor {
+                Some(c) => json!({ "cursor": c }),
+                None => json!({}),
+            };
+            let result = self.request(method, params, timeout)?;
+            if let Some(page) = result[key].as_array() {
+                items.extend(page.iter().cloned());
+            }
+            match result["nextCursor"].as_str() {
+                Some(next) if cursor.as_deref() != Some(next) => cursor = Some(next.to_string()),
+                _ => return Ok(items),
+            }
+        }
+    }
+
+    pub fn notify(&self, method: &str, params: Value) {
+        let _ = self.session.send(&jsonrpc::notification(method, params));
+    }
+
+    pub fn close(self) {
+        self.session.close();
+    }
+
+    /// Answer pings and refuse anything else the server asks, as
+    /// `health::Session` does
+    fn answer_server_message(&self, msg: &Value) {
+        let Some(id) = msg.get("id").cloned() else {
+            return;
+        };
+        let reply = if msg["method"] == "ping" {
+            jsonrpc::response(id, json!({}))
+        } else {
+            jsonrpc::error_response(id, -32601, "method not supported by mcpm")
+        };
+        let _ = self.session.send(&reply);
+    }
+}
+
+/// `None` leaves reads unbounded, for long-lived event streams
+fn agent(read_timeout: Option<Duration>) -> ureq::Agent {
+    let builder = ureq::AgentBuilder::new().timeout_connect(Duration::from_secs(10));
+    match read_timeout {
+        Some(timeout) => builder.timeout_read(timeout).build(),
+        None => builder.build(),
+    }
+}
+
+fn sorted(headers: &HashMap<String, String>) -> Vec<(String, String)> {
+    let mut headers: Vec<(String, String)> = headers.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+    headers.sort();
+    headers
+}
+
+fn describe_error(e: ureq::Error) -> RemoteError {
+    match e {
+        ureq::Error::Status(401, response) => {
+            RemoteError::Unauthorized(response.header("WWW-Authenticate").map(str::to_string))
+        }
+        ureq::Error::Status(code, response) => {
+            let body = response.into_string().unwrap_or_default();
+            RemoteError::Failed(if body.trim().is_empty() {
+                format!("HTTP {}", code)
+            } else {
+                format!("HTTP {}: {}", code, truncate(body.trim(), 200))
+            })
+        }
+        ureq::Error::Transport(t) => {
+            let timed_out = std::error::Error::source(&t)
+                .and_then(|e| e.downcast_ref::<std::io::Error>())
+                .is_some_and(|e| matches!(e.kind(), std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock));
+            if timed_out {
+                RemoteError::Timeout
+            } else {
+                RemoteError::Failed(t.to_string())
+            }
+        }
+    }
+}
+
+fn io_error(e: std::io::Error) -> RemoteError {
+    match e.kind() {
+        std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock => RemoteError::Timeout,
+        _ => RemoteError::Failed(e.to_string()),
+    }
+}
+
+fn truncate(s: &str, max: usize) -> String {
+    match s.char_indices().nth(max) {
+        Some((i, _)) => format!("{}…", &s[..i]),
+        None => s.to_string(),
+    }
+}
+
+/// Resolve the endpoint a legacy SSE server announces against its URL
+pub fn resolve_url(base: &str, reference: &str) -> String {
+    if reference.starts_with("http://") || reference.starts_with("https://") {
+        return reference.to_string();
+    }
+    let scheme_end = base.find("://").map(|i| i + 3).unwrap_or(0);
+    let origin_end = base[scheme_end..]
+        .find('/')
+        .map(|i| scheme_end + i)
+        .unwrap_or(base.len());
+    if reference.starts_with('/') {
+        return format!("{}{}", &base[..origin_end], reference);
+    }
+    let path = base[..base.find(['?', '#']).unwrap_or(base.len())].to_string();
+    let dir_end = path.rfind('/').filter(|&i| i >= origin_end).map(|i| i + 1);
+    match dir_end {
+        Some(end) => format!("{}{}", &path[..end], reference),
+        None => format!("{}/{}", &path[..origin_end], reference),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Server-sent events
+// ---------------------------------------------------------------------------
+
+pub struct SseEvent {
+    pub name: Option<String>,
+    pub data: String,
+}
+
+/// Iterator over the events in a `text/event-stream` body
+pub struct SseEvents<R> {
+    reader: BufReader<R>,
+}
+
+impl<R: Read> SseEvents<R> {
+    pub fn new(reader: R) -> Self {
+        SseEvents {
+            reader: BufReader::new(reader),
+        }
+    }
+}
+
+impl<R: Read> Iterator for SseEvents<R> {
+    type Item = SseEvent;
+
+    fn next(&mut self) -> Option<SseEvent> {
+        let mut name = None;
+        let mut data: Vec<String> = Vec::new();
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if self.reader.read_line(&mut line).ok()? == 0 {
+                return None;
+            }
+            let line = line.trim_end_matches(['\r', '\n']);
+            if line.is_empty() {
+                if data.is_empty() {
+                    name = None;
+                    continue;
+                }
+                return Some(SseEvent {
+                    name,
+                    data: data.join("\n"),
+                });
+            }
+            let (field, value) = line.split_once(':').unwrap_or((line, ""));
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            match field {
+                "event" => name = Some(value.to_string()),
+                "data" => data.push(value.to_string()),
+                _ => {} // comments, id, retry
+            }
+        }
+    }
+}
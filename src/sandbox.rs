@@ -0,0 +1,788 @@
+use std::path::PathBuf;
+
+use crate::settings::SandboxSettings;
+
+const DEFAULT_MEMORY_MB: u64 = 1024;
+const DEFAULT_CPU_SECS: u64 = 30;
+
+#[cfg(not(target_os = "linux"))]
+const UNSUPPORTED: &str = "sandboxed checks need Linux";
+
+/// Restrictions a sandboxed health check runs under: no network except the
+/// allowed hosts, a read-only filesystem with empty /tmp and /run, and
+/// limits on memory and CPU time
+#[derive(Debug, Clone)]
+pub struct Sandbox {
+    /// Reachable through an HTTP proxy; everything else has no route out
+    pub allow_hosts: Vec<String>,
+    pub memory_mb: u64,
+    pub cpu_secs: u64,
+    /// Paths that stay writable, bound in from outside
+    pub writable: Vec<PathBuf>,
+}
+
+impl Sandbox {
+    pub fn from_settings(settings: &SandboxSettings) -> Sandbox {
+        let home = dirs::home_dir();
+        Sandbox {
+            allow_hosts: settings.allow_hosts.clone(),
+            memory_mb: settings.memory_mb.unwrap_or(DEFAULT_MEMORY_MB),
+            cpu_secs: settings.cpu_secs.unwrap_or(DEFAULT_CPU_SECS),
+            writable: settings
+                .writable
+                .iter()
+                .map(|p| match (p.strip_prefix("~/"), &home) {
+                    (Some(rest), Some(home)) => home.join(rest),
+                    _ => PathBuf::from(p),
+                })
+                .collect(),
+        }
+    }
+
+    /// e.g. "no network, read-only filesystem, 1024 MB memory, 30s CPU"
+    pub fn describe(&self) -> String {
+        let network = if self.allow_hosts.is_empty() {
+            "no network".to_string()
+        } else {
+            format!("network only to {}", self.allow_hosts.join(", "))
+        };
+        format!(
+            "{}, read-only filesystem, {} MB memory, {}s CPU",
+            network, self.memory_mb, self.cpu_secs
+        )
+    }
+
+    /// The command line that runs `command` inside the sandbox
+    pub fn wrap(&self, command: &str, args: &[String]) -> Result<Wrapped, String> {
+        #[cfg(target_os = "linux")]
+        return linux::wrap(self, command, args);
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = (command, args);
+            Err(UNSUPPORTED.to_string())
+        }
+    }
+}
+
+/// A sandboxed command line. Keep it until the process exits: it owns the
+/// proxy that allowed hosts are reached through.
+pub struct Wrapped {
+    pub program: String,
+    pub args: Vec<String>,
+    #[cfg(target_os = "linux")]
+    _guard: linux::Guard,
+}
+
+/// What sandboxed checks run with on this machine, or why they can't run.
+/// Probed once per run.
+pub fn backend_name() -> Result<&'static str, String> {
+    #[cfg(target_os = "linux")]
+    return linux::backend_name();
+    #[cfg(not(target_os = "linux"))]
+    Err(UNSUPPORTED.to_string())
+}
+
+/// What `mcpm sandbox-exec` sets up before it runs the server
+pub struct Inner {
+    /// Create the user, mount and network namespaces here; bubblewrap has
+    /// already done so otherwise
+    pub unshare: bool,
+    pub writable: Vec<PathBuf>,
+    pub proxy_socket: Option<PathBuf>,
+    pub memory_mb: Option<u64>,
+    pub cpu_secs: Option<u64>,
+}
+
+/// Enter the sandbox and run `command`, returning its exit code. Must be
+/// called before mcpm starts any thread: a multithreaded process can't
+/// enter a new user namespace.
+pub fn exec(inner: &Inner, command: &str, args: &[String]) -> Result<i32, String> {
+    #[cfg(target_os = "linux")]
+    return linux::exec(inner, command, args);
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = (inner, command, args);
+        Err(UNSUPPORTED.to_string())
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::ffi::CString;
+    use std::io::{self, BufRead, BufReader, Read, Write};
+    use std::net::{Shutdown, TcpListener, TcpStream, ToSocketAddrs};
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::fs::DirBuilderExt;
+    use std::os::unix::process::CommandExt;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::{Path, PathBuf};
+    use std::process::{Command, Stdio};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, OnceLock};
+    use std::time::Duration;
+
+    use super::{Inner, Sandbox, Wrapped};
+
+    /// Longest the proxy waits to reach an allowed host
+    const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+    pub fn wrap(sandbox: &Sandbox, command: &str, args: &[String]) -> Result<Wrapped, String> {
+        let backend = backend()?;
+        if !on_path(command) {
+            return Err(format!("command not found: {}", command));
+        }
+        let exe = std::env::current_exe()
+            .map_err(|e| format!("can't find the mcpm executable: {}", e))?
+            .to_string_lossy()
+            .into_owned();
+        let guard = Guard::start(&sandbox.allow_hosts)?;
+
+        let mut inner = vec!["sandbox-exec".to_string()];
+        if let Some(socket) = &guard.socket {
+            inner.extend(["--proxy-socket".to_string(), socket.to_string_lossy().into_owned()]);
+        }
+        inner.extend([
+            "--memory-mb".to_string(),
+            sandbox.memory_mb.to_string(),
+            "--cpu-secs".to_string(),
+            sandbox.cpu_secs.to_string(),
+        ]);
+
+        let (program, mut wrapped) = match backend {
+            Backend::Bwrap(bwrap) => {
+                let mut bw: Vec<String> = BWRAP_LAYOUT.iter().map(|s| s.to_string()).collect();
+                // mcpm itself may live under /tmp, which is empty inside
+                bw.extend(["--ro-bind".to_string(), exe.clone(), exe.clone()]);
+                for path in sandbox.writable.iter().chain(&guard.dir).filter(|p| p.exists()) {
+                    let path = path.to_string_lossy().into_owned();
+                    bw.extend(["--bind".to_string(), path.clone(), path]);
+                }
+                bw.push("--".to_string());
+                bw.push(exe);
+                bw.extend(inner);
+                (bwrap, bw)
+            }
+            Backend::Namespaces => {
+                inner.push("--unshare".to_string());
+                for path in &sandbox.writable {
+                    inner.extend(["--writable".to_string(), path.to_string_lossy().into_owned()]);
+                }
+                (exe, inner)
+            }
+        };
+        wrapped.push("--".to_string());
+        wrapped.push(command.to_string());
+        wrapped.extend(args.iter().cloned());
+        Ok(Wrapped {
+            program,
+            args: wrapped,
+            _guard: guard,
+        })
+    }
+
+    // ---------------------------------------------------------------------------
+    // Backends
+    // ---------------------------------------------------------------------------
+
+    /// Read-only root; fresh /dev, /proc, /tmp and /run, so sockets like
+    /// /run/docker.sock, the session bus and the ssh agent stay out of reach
+    const BWRAP_LAYOUT: &[&str] = &[
+        "--die-with-parent",
+        "--unshare-user",
+        "--unshare-net",
+        "--unshare-ipc",
+        "--unshare-pid",
+        "--ro-bind",
+        "/",
+        "/",
+        "--dev",
+        "/dev",
+        "--proc",
+        "/proc",
+        "--tmpfs",
+        "/tmp",
+        "--tmpfs",
+        "/run",
+    ];
+
+    #[derive(Debug, Clone)]
+    enum Backend {
+        /// bubblewrap, at this path
+        Bwrap(String),
+        /// `mcpm sandbox-exec --unshare` sets up the namespaces itself
+        Namespaces,
+    }
+
+    pub fn backend_name() -> Result<&'static str, String> {
+        backend().map(|b| match b {
+            Backend::Bwrap(_) => "bubblewrap",
+            Backend::Namespaces => "namespaces",
+        })
+    }
+
+    fn backend() -> Result<Backend, String> {
+        static CACHE: OnceLock<Result<Backend, String>> = OnceLock::new();
+        CACHE.get_or_init(detect_backend).clone()
+    }
+
+    fn detect_backend() -> Result<Backend, String> {
+        // Both are tried for real: containers and some distributions block
+        // user namespaces even when the sysctls say they're allowed
+        if let Some(bwrap) = find_on_path("bwrap") {
+            let works = Command::new(&bwrap)
+                .args(BWRAP_LAYOUT)
+                .arg("true")
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .is_ok_and(|s| s.success());
+            if works {
+                return Ok(Backend::Bwrap(bwrap.to_string_lossy().into_owned()));
+            }
+        }
+        let exe = std::env::current_exe().map_err(|e| format!("can't find the mcpm executable: {}", e))?;
+        let probe = Command::new(exe)
+            .args(["sandbox-exec", "--unshare", "--", "true"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .output()
+            .map_err(|e| e.to_string())?;
+        if probe.status.success() {
+            Ok(Backend::Namespaces)
+        } else {
+            let reason = String::from_utf8_lossy(&probe.stderr).trim().to_string();
+            Err(format!("no sandbox available: install bubblewrap or allow unprivileged user namespaces ({})", reason))
+        }
+    }
+
+    fn find_on_path(name: &str) -> Option<PathBuf> {
+        std::env::split_paths(&std::env::var_os("PATH")?)
+            .map(|dir| dir.join(name))
+            .find(|p| p.is_file())
+    }
+
+    fn on_path(command: &str) -> bool {
+        if command.contains('/') {
+            Path::new(command).is_file()
+        } else {
+            find_on_path(command).is_some()
+        }
+    }
+
+    // ---------------------------------------------------------------------------
+    // Outside: the allowlist proxy
+    // ---------------------------------------------------------------------------
+
+    /// Keeps the allowlist proxy running; dropping it stops the proxy and
+    /// removes its socket
+    pub struct Guard {
+        dir: Option<PathBuf>,
+        socket: Option<PathBuf>,
+        stop: Arc<AtomicBool>,
+    }
+
+    impl Guard {
+        fn start(allow_hosts: &[String]) -> Result<Guard, String> {
+            let stop = Arc::new(AtomicBool::new(false));
+            if allow_hosts.is_empty() {
+                return Ok(Guard {
+                    dir: None,
+                    socket: None,
+                    stop,
+                });
+            }
+            // Bound into the sandbox, whose own /tmp is empty
+            let mut suffix = [0u8; 6];
+            getrandom::fill(&mut suffix).map_err(|e| e.to_string())?;
+            let name: String = suffix.iter().map(|b| format!("{:02x}", b)).collect();
+            let dir = std::env::temp_dir().join(format!("mcpm-sandbox-{}", name));
+            // Private, so other local users can't borrow the proxy
+            std::fs::DirBuilder::new()
+                .mode(0o700)
+                .create(&dir)
+                .map_err(|e| format!("failed to create {}: {}", dir.display(), e))?;
+            let socket = dir.join("proxy.sock");
+            let listener =
+                UnixListener::bind(&socket).map_err(|e| format!("failed to bind {}: {}", socket.display(), e))?;
+            listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+
+            let allow: Arc<Vec<String>> = Arc::new(allow_hosts.iter().map(|h| h.to_ascii_lowercase()).collect());
+            let stopped = stop.clone();
+            std::thread::spawn(move || {
+                while !stopped.load(Ordering::Relaxed) {
+                    match listener.accept() {
+                        Ok((stream, _)) => {
+                            let allow = allow.clone();
+                            std::thread::spawn(move || {
+                                let _ = stream.set_nonblocking(false);
+                                proxy_connection(stream, &allow);
+                            });
+                        }
+                        Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                            std::thread::sleep(Duration::from_millis(50))
+                        }
+                        Err(_) => break,
+                    }
+                }
+            });
+            Ok(Guard {
+                dir: Some(dir),
+                socket: Some(socket),
+                stop,
+            })
+        }
+    }
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            self.stop.store(true, Ordering::Relaxed);
+            if let Some(dir) = &self.dir {
+                let _ = std::fs::remove_dir_all(dir);
+            }
+        }
+    }
+
+    /// `example.com` allows that host, `*.example.com` its subdomains
+    fn is_allowed(host: &str, allow: &[String]) -> bool {
+        let host = host.to_ascii_lowercase();
+        allow.iter().any(|a| match a.strip_prefix("*.") {
+            Some(domain) => host.strip_suffix(domain).is_some_and(|rest| rest.ends_with('.')),
+            None => *a == host,
+        })
+    }
+
+    /// One HTTP proxy request: CONNECT for HTTPS, absolute-form for plain HTTP
+    fn proxy_connection(client: UnixStream, allow: &[String]) {
+        let Ok(mut writer) = client.try_clone() else {
+            return;
+        };
+        let mut reader = BufReader::new(client);
+        let mut head = String::new();
+        loop {
+            let mut line = String::new();
+            match reader.read_line(&mut line) {
+                Ok(0) | Err(_) => return,
+                Ok(_) => {}
+            }
+            let end = line == "\r\n" || line == "\n";
+            head.push_str(&line);
+            if end || head.len() > 16 * 1024 {
+                break;
+            }
+        }
+
+        let mut parts = head.split_whitespace();
+        let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+            return;
+        };
+        let connect = method.eq_ignore_ascii_case("CONNECT");
+        let authority = if connect {
+            target
+        } else {
+            match target.strip_prefix("http://") {
+                Some(rest) => rest.split('/').next().unwrap_or(rest),
+                None => return respond(&mut writer, "400 Bad Request", "only absolute http:// URLs are proxied"),
+            }
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((h, p)) if !h.ends_with(']') || h.starts_with('[') => (h, p.parse().unwrap_or(0)),
+            _ => (authority, 80),
+        };
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if !is_allowed(host, allow) {
+            let reason = format!("mcpm sandbox: {} is not in allow_hosts", host);
+            return respond(&mut writer, "403 Forbidden", &reason);
+        }
+        let upstream = (host, port)
+            .to_socket_addrs()
+            .ok()
+            .and_then(|mut addrs| addrs.find_map(|a| TcpStream::connect_timeout(&a, CONNECT_TIMEOUT).ok()));
+        let Some(mut upstream) = upstream else {
+            return respond(&mut writer, "502 Bad Gateway", &format!("can't reach {}", authority));
+        };
+
+        if connect {
+            if writer.write_all(b"HTTP/1.1 200 Connection established\r\n\r\n").is_err() {
+                return;
+            }
+        } else {
+            // Origin servers expect the path alone: GET /x, not GET http://host/x
+            let path = &target["http://".len() + authority.len()..];
+            let head = head.replacen(target, if path.is_empty() { "/" } else { path }, 1);
+            if upstream.write_all(head.as_bytes()).is_err() {
+                return;
+            }
+        }
+        // Bytes the client sent after the head
+        let buffered = reader.buffer().to_vec();
+        if !buffered.is_empty() && upstream.write_all(&buffered).is_err() {
+            return;
+        }
+        let Ok(upstream_reader) = upstream.try_clone() else {
+            return;
+        };
+        splice(reader.into_inner(), writer, upstream_reader, upstream);
+    }
+
+    fn respond(client: &mut UnixStream, status: &str, body: &str) {
+        let _ = write!(
+            client,
+            "HTTP/1.1 {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        );
+    }
+
+    /// Copy both ways until either side closes
+    fn splice<A, B>(mut a_read: A, mut a_write: A, mut b_read: B, mut b_write: B)
+    where
+        A: Read + Write + Send + 'static + ShutdownWrite,
+        B: Read + Write + Send + 'static + ShutdownWrite,
+    {
+        let forward = std::thread::spawn(move || {
+            let _ = io::copy(&mut a_read, &mut b_write);
+            b_write.shutdown_write();
+        });
+        let _ = io::copy(&mut b_read, &mut a_write);
+        a_write.shutdown_write();
+        let _ = forward.join();
+    }
+
+    trait ShutdownWrite {
+        fn shutdown_write(&self);
+    }
+
+    impl ShutdownWrite for TcpStream {
+        fn shutdown_write(&self) {
+            let _ = self.shutdown(Shutdown::Write);
+        }
+    }
+
+    impl ShutdownWrite for UnixStream {
+        fn shutdown_write(&self) {
+            let _ = self.shutdown(Shutdown::Write);
+        }
+    }
+
+    // ---------------------------------------------------------------------------
+    // Inside: `mcpm sandbox-exec`
+    // ---------------------------------------------------------------------------
+
+    pub fn exec(inner: &Inner, command: &str, args: &[String]) -> Result<i32, String> {
+        if inner.unshare {
+            let mut writable = inner.writable.clone();
+            writable.extend(inner.proxy_socket.as_ref().and_then(|s| s.parent()).map(Path::to_path_buf));
+            enter_namespaces(&writable)?;
+        }
+        if let Some(mb) = inner.memory_mb {
+            // Data rather than address space: V8 and the JVM reserve far
+            // more address space than they ever touch
+            set_limit(libc::RLIMIT_DATA, mb * 1024 * 1024, mb * 1024 * 1024)?;
+        }
+        if let Some(secs) = inner.cpu_secs {
+            // SIGXCPU at the soft limit, SIGKILL a second later
+            set_limit(libc::RLIMIT_CPU, secs, secs + 1)?;
+        }
+
+        let mut cmd = Command::new(command);
+        cmd.args(args);
+        if let Some(socket) = &inner.proxy_socket {
+            let port = forward_to_proxy(socket.clone())?;
+            let url = format!("http://127.0.0.1:{}", port);
+            for var in ["HTTP_PROXY", "HTTPS_PROXY", "http_proxy", "https_proxy"] {
+                cmd.env(var, &url);
+            }
+            cmd.env("NO_PROXY", "localhost,127.0.0.1").env("no_proxy", "localhost,127.0.0.1");
+            // Node's fetch only reads the variables above when asked to
+            cmd.env("NODE_USE_ENV_PROXY", "1");
+        }
+        if inner.unshare {
+            // Last, as this thread can't start threads afterwards: the
+            // server becomes pid 1 of a namespace whose /proc it mounts
+            // SAFETY: a plain syscall without pointer arguments
+            if unsafe { libc::unshare(libc::CLONE_NEWPID) } != 0 {
+                return Err(format!("unshare: {}", io::Error::last_os_error()));
+            }
+            // SAFETY: only async-signal-safe syscalls between fork and exec
+            unsafe {
+                cmd.pre_exec(|| {
+                    if libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL) != 0
+                        || libc::mount(
+                            c"proc".as_ptr(),
+                            c"/proc".as_ptr(),
+                            c"proc".as_ptr(),
+                            libc::MS_NOSUID | libc::MS_NODEV | libc::MS_NOEXEC,
+                            std::ptr::null(),
+                        ) != 0
+                    {
+                        return Err(io::Error::last_os_error());
+                    }
+                    Ok(())
+                });
+            }
+        }
+        let status = cmd.status().map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => format!("command not found: {}", command),
+            _ => e.to_string(),
+        })?;
+        use std::os::unix::process::ExitStatusExt;
+        Ok(status.code().or(status.signal().map(|s| 128 + s)).unwrap_or(1))
+    }
+
+    /// Listen on loopback inside the sandbox and pass every connection to the
+    /// proxy socket outside. Returns the port.
+    fn forward_to_proxy(socket: PathBuf) -> Result<u16, String> {
+        let listener = TcpListener::bind("127.0.0.1:0").map_err(|e| format!("failed to listen on loopback: {}", e))?;
+        let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let socket = socket.clone();
+                std::thread::spawn(move || {
+                    let Ok(proxy) = UnixStream::connect(&socket) else {
+                        return;
+                    };
+                    let (Ok(stream_write), Ok(proxy_write)) = (stream.try_clone(), proxy.try_clone()) else {
+                        return;
+                    };
+                    splice(stream, stream_write, proxy, proxy_write);
+                });
+            }
+        });
+        Ok(port)
+    }
+
+    // ---------------------------------------------------------------------------
+    // Namespaces and limits
+    // ---------------------------------------------------------------------------
+
+    /// Mounts that are replaced with fresh ones rather than made read-only
+    const REPLACED: &[&str] = &["/dev", "/proc", "/tmp", "/run"];
+
+    /// Device nodes bound into the fresh /dev
+    const DEVICES: &[&str] = &["null", "zero", "full", "random", "urandom", "tty"];
+
+    /// New user, mount, network and IPC namespaces laid out like the
+    /// bubblewrap backend: every mount read-only, fresh /dev, /tmp and /run,
+    /// and only the `writable` paths bound back in. The network namespace
+    /// starts with nothing but loopback.
+    fn enter_namespaces(writable: &[PathBuf]) -> Result<(), String> {
+        // SAFETY: plain syscalls without pointer arguments
+        let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+        let flags = libc::CLONE_NEWUSER | libc::CLONE_NEWNS | libc::CLONE_NEWNET | libc::CLONE_NEWIPC;
+        // SAFETY: as above
+        if unsafe { libc::unshare(flags) } != 0 {
+            return Err(format!("unshare: {}", io::Error::last_os_error()));
+        }
+        // Keep our own uid and gid, so file ownership looks the same inside
+        let _ = std::fs::write("/proc/self/setgroups", "deny");
+        std::fs::write("/proc/self/uid_map", format!("{} {} 1", uid, uid)).map_err(|e| format!("uid_map: {}", e))?;
+        std::fs::write("/proc/self/gid_map", format!("{} {} 1", gid, gid)).map_err(|e| format!("gid_map: {}", e))?;
+
+        // Don't let our mounts propagate back out
+        mount(None, Path::new("/"), libc::MS_REC | libc::MS_PRIVATE)?;
+
+        // Held open so they can be bound back once /dev and /tmp are covered
+        let open = |p: &Path| std::fs::File::open(p).map_err(|e| format!("{}: {}", p.display(), e));
+        let host_dev = open(Path::new("/dev"))?;
+        let keep: Vec<(&PathBuf, std::fs::File)> = writable
+            .iter()
+            .filter(|p| p.exists())
+            .map(|p| Ok((p, open(p)?)))
+            .collect::<Result<_, String>>()?;
+
+        let mountinfo = std::fs::read_to_string("/proc/self/mountinfo").map_err(|e| format!("mountinfo: {}", e))?;
+        for target in mountinfo.lines().filter_map(|l| l.split(' ').nth(4)).map(unescape) {
+            let target = Path::new(&target);
+            if REPLACED.iter().any(|r| target.starts_with(r)) {
+                continue;
+            }
+            mount(None, target, libc::MS_BIND | libc::MS_REMOUNT | libc::MS_RDONLY | locked_flags(target))?;
+        }
+
+        mount_tmpfs(Path::new("/dev"), "0755")?;
+        for name in DEVICES {
+            let host = fd_path(&host_dev).join(name);
+            if !host.exists() {
+                continue;
+            }
+            let target = Path::new("/dev").join(name);
+            std::fs::File::create(&target).map_err(|e| format!("{}: {}", target.display(), e))?;
+            mount(Some(&host), &target, libc::MS_BIND)?;
+        }
+        let shm = Path::new("/dev/shm");
+        std::fs::create_dir(shm).map_err(|e| format!("{}: {}", shm.display(), e))?;
+        mount_tmpfs(shm, "1777")?;
+        for (link, to) in [
+            ("fd", "/proc/self/fd"),
+            ("stdin", "/proc/self/fd/0"),
+            ("stdout", "/proc/self/fd/1"),
+            ("stderr", "/proc/self/fd/2"),
+        ] {
+            let _ = std::os::unix::fs::symlink(to, Path::new("/dev").join(link));
+        }
+        drop(host_dev);
+
+        for dir in ["/tmp", "/run"] {
+            if Path::new(dir).is_dir() {
+                mount_tmpfs(Path::new(dir), "1777")?;
+            }
+        }
+
+        for (path, file) in &keep {
+            if !path.exists() {
+                // Under a fresh tmpfs; everywhere else the path survives
+                let created = if file.metadata().is_ok_and(|m| m.is_dir()) {
+                    std::fs::create_dir_all(path)
+                } else {
+                    path.parent().map_or(Ok(()), std::fs::create_dir_all).and_then(|_| std::fs::File::create(path).map(drop))
+                };
+                created.map_err(|e| format!("{}: {}", path.display(), e))?;
+            }
+            mount(Some(&fd_path(file)), path, libc::MS_BIND | libc::MS_REC)?;
+            let flags = locked_flags(path) & !libc::MS_RDONLY;
+            mount(None, path, libc::MS_BIND | libc::MS_REMOUNT | flags)?;
+        }
+        loopback_up()
+    }
+
+    /// A path that reaches an open file even after its own path is covered
+    fn fd_path(file: &std::fs::File) -> PathBuf {
+        use std::os::fd::AsRawFd;
+        PathBuf::from(format!("/proc/self/fd/{}", file.as_raw_fd()))
+    }
+
+    /// Mount points in mountinfo escape space, tab, newline and backslash
+    fn unescape(field: &str) -> String {
+        let mut out = Vec::new();
+        let bytes = field.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            let octal = bytes.get(i + 1..i + 4).and_then(|d| std::str::from_utf8(d).ok()).and_then(|d| u8::from_str_radix(d, 8).ok());
+            match (bytes[i], octal) {
+                (b'\\', Some(b)) => {
+                    out.push(b);
+                    i += 4;
+                }
+                (b, _) => {
+                    out.push(b);
+                    i += 1;
+                }
+            }
+        }
+        String::from_utf8_lossy(&out).into_owned()
+    }
+
+    fn mount_tmpfs(target: &Path, mode: &str) -> Result<(), String> {
+        let target_c = CString::new(target.as_os_str().as_bytes()).map_err(|e| e.to_string())?;
+        let data = CString::new(format!("mode={}", mode)).map_err(|e| e.to_string())?;
+        // SAFETY: every pointer is a valid NUL-terminated string
+        let rc = unsafe {
+            libc::mount(
+                c"tmpfs".as_ptr(),
+                target_c.as_ptr(),
+                c"tmpfs".as_ptr(),
+                libc::MS_NOSUID | libc::MS_NODEV,
+                data.as_ptr().cast(),
+            )
+        };
+        if rc == 0 {
+            Ok(())
+        } else {
+            Err(format!("mount {}: {}", target.display(), io::Error::last_os_error()))
+        }
+    }
+
+    fn mount(source: Option<&Path>, target: &Path, flags: libc::c_ulong) -> Result<(), String> {
+        let c = |p: &Path| CString::new(p.as_os_str().as_bytes()).map_err(|e| e.to_string());
+        let source = source.map(c).transpose()?;
+        let target_c = c(target)?;
+        // SAFETY: every pointer is a valid NUL-terminated string or null
+        let rc = unsafe {
+            libc::mount(
+                source.as_ref().map_or(std::ptr::null(), |s| s.as_ptr()),
+                target_c.as_ptr(),
+                std::ptr::null(),
+                flags,
+                std::ptr::null(),
+            )
+        };
+        if rc == 0 {
+            Ok(())
+        } else {
+            Err(format!("mount {}: {}", target.display(), io::Error::last_os_error()))
+        }
+    }
+
+    /// Flags a remount inside a user namespace has to keep, or it's refused
+    fn locked_flags(path: &Path) -> libc::c_ulong {
+        let Ok(c) = CString::new(path.as_os_str().as_bytes()) else {
+            return 0;
+        };
+        // SAFETY: statvfs fills the zeroed struct; the path is NUL-terminated
+        let mut st: libc::statvfs = unsafe { std::mem::zeroed() };
+        if unsafe { libc::statvfs(c.as_ptr(), &mut st) } != 0 {
+            return 0;
+        }
+        [
+            (libc::ST_NOSUID, libc::MS_NOSUID),
+            (libc::ST_NODEV, libc::MS_NODEV),
+            (libc::ST_NOEXEC, libc::MS_NOEXEC),
+            (libc::ST_NOATIME, libc::MS_NOATIME),
+            (libc::ST_NODIRATIME, libc::MS_NODIRATIME),
+            (libc::ST_RELATIME, libc::MS_RELATIME),
+            (libc::ST_RDONLY, libc::MS_RDONLY),
+        ]
+        .iter()
+        .filter(|(st_flag, _)| st.f_flag & st_flag != 0)
+        .fold(0, |flags, (_, ms_flag)| flags | ms_flag)
+    }
+
+    /// A new network namespace's loopback starts down
+    fn loopback_up() -> Result<(), String> {
+        // SAFETY: a plain socket for the ioctls below, closed before returning
+        let fd = unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM, 0) };
+        if fd < 0 {
+            return Err(format!("socket: {}", io::Error::last_os_error()));
+        }
+        // SAFETY: ifreq is plain data; the name is NUL-terminated and the
+        // ioctls only read and write the struct
+        let rc = unsafe {
+            let mut req: libc::ifreq = std::mem::zeroed();
+            for (dst, src) in req.ifr_name.iter_mut().zip(b"lo\0") {
+                *dst = *src as libc::c_char;
+            }
+            let mut rc = libc::ioctl(fd, libc::SIOCGIFFLAGS as _, &mut req);
+            if rc == 0 {
+                req.ifr_ifru.ifru_flags |= (libc::IFF_UP | libc::IFF_RUNNING) as libc::c_short;
+                rc = libc::ioctl(fd, libc::SIOCSIFFLAGS as _, &req);
+            }
+            libc::close(fd);
+            rc
+        };
+        if rc == 0 {
+            Ok(())
+        } else {
+            Err(format!("bringing up loopback: {}", io::Error::last_os_error()))
+        }
+    }
+
+    #[cfg(target_env = "gnu")]
+    type Resource = libc::__rlimit_resource_t;
+    #[cfg(not(target_env = "gnu"))]
+    type Resource = libc::c_int;
+
+    fn set_limit(resource: Resource, soft: u64, hard: u64) -> Result<(), String> {
+        let limit = libc::rlimit {
+            rlim_cur: soft as libc::rlim_t,
+            rlim_max: hard as libc::rlim_t,
+        };
+        // SAFETY: setrlimit only reads the struct
+        if unsafe { libc::setrlimit(resource, &limit) } == 0 {
+            Ok(())
+        } else {
+            Err(format!("setrlimit: {}", io::Error::last_os_error()))
+        }
+    }
+}
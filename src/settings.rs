@@ -0,0 +1,101 @@
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+/// User settings from ~/.config/mcpm/config.json (platform config dir
+/// elsewhere). Every field is optional; a missing file means defaults.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Re-check stdio servers in the background every N seconds while the
+    /// TUI is open. Unset leaves monitoring off until toggled with `m`.
+    pub auto_check_interval_secs: Option<u64>,
+    /// Health checks allowed to run at once; the rest wait in a queue
+    pub max_concurrent_checks: Option<usize>,
+    /// Estimated tokens one server's tool definitions may take before it is
+    /// flagged as expensive
+    pub tool_token_budget: Option<usize>,
+    /// Restrictions for `mcpm check --sandbox`
+    pub sandbox: SandboxSettings,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct SandboxSettings {
+    /// Sandbox every health check, including the TUI's and `check_health`
+    pub enabled: bool,
+    /// Hosts still reachable through a proxy; `*.example.com` allows
+    /// subdomains. Empty means no network at all.
+    pub allow_hosts: Vec<String>,
+    /// Memory limit in MB (default 1024)
+    pub memory_mb: Option<u64>,
+    /// CPU time limit in seconds (default 30)
+    pub cpu_secs: Option<u64>,
+    /// Paths that stay writable, e.g. "~/.npm"; everything else is read-only
+    pub writable: Vec<String>,
+}
+
+/// Concurrent checks when not configured — enough to be quick, few enough
+/// that a screen of `npx` servers doesn't thrash the npm cache
+const DEFAULT_CHECK_LIMIT: usize = 4;
+
+/// Per-server token budget when not configured — a few dozen typical tools
+const DEFAULT_TOOL_TOKEN_BUDGET: usize = 5000;
+
+impl Settings {
+    pub fn path() -> Option<PathBuf> {
+        Some(dirs::config_dir()?.join("mcpm/config.json"))
+    }
+
+    pub fn check_limit(&self) -> usize {
+        self.max_concurrent_checks.unwrap_or(DEFAULT_CHECK_LIMIT).max(1)
+    }
+
+    pub fn tool_token_budget(&self) -> usize {
+        self.tool_token_budget.unwrap_or(DEFAULT_TOOL_TOKEN_BUDGET)
+    }
+
+    /// Load settings, falling back to defaults. A file that exists but
+    /// doesn't parse is reported as Err so the caller can surface it.
+    pub fn load() -> Result<Settings, String> {
+        let Some(path) = Self::path() else {
+            return Ok(Settings::default());
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text)
+                .map_err(|e| format!("{}: {}", path.display(), e)),
+            Err(_) => Ok(Settings::default()),
+        }
+    }
+}
+
+/// Per-project settings from ./.mcpm.json, meant to be committed alongside
+/// the project's MCP configs
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ProjectSettings {
+    pub lint: LintSettings,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct LintSettings {
+    /// Rule IDs or names `mcpm lint` skips, e.g. "MCP011" or "unknown-field"
+    pub disable: Vec<String>,
+}
+
+impl ProjectSettings {
+    pub fn path(cwd: &Path) -> PathBuf {
+        cwd.join(".mcpm.json")
+    }
+
+    /// Same fallback rules as `Settings::load`
+    pub fn load(cwd: &Path) -> Result<ProjectSettings, String> {
+        let path = Self::path(cwd);
+        match std::fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text)
+                .map_err(|e| format!("{}: {}", path.display(), e)),
+            Err(_) => Ok(ProjectSettings::default()),
+        }
+    }
+}
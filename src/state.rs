@@ -0,0 +1,294 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::pins::Pin;
+use crate::types::{HealthStatus, McpServer};
+
+/// Entries kept per server; older ones are dropped
+const MAX_HISTORY: usize = 50;
+/// How many recent checks flapping and regression detection look at
+const RECENT_WINDOW: usize = 10;
+/// Status changes within the window that count as flapping
+const FLAP_CHANGES: usize = 3;
+
+/// mcpm's own persistent state, stored as JSON under the user data dir.
+/// Keyed by server identity so it survives restarts and rescans.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct State {
+    #[serde(default)]
+    pub servers: BTreeMap<String, ServerState>,
+    /// `servers` as last read from or written to disk, to tell this
+    /// process's changes from other processes' on save
+    #[serde(skip)]
+    base: BTreeMap<String, ServerState>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServerState {
+    #[serde(default)]
+    pub history: Vec<HistoryEntry>,
+    /// Tool definitions from the last check that listed them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tools: Option<ToolSnapshot>,
+    /// Trusted tool definitions and config, to catch changes against
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pin: Option<Pin>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolSnapshot {
+    /// Unix timestamp (seconds)
+    pub at: u64,
+    pub tools: Vec<Value>,
+}
+
+/// One recorded health check
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Unix timestamp (seconds)
+    pub at: u64,
+    pub outcome: Outcome,
+    /// Status label at the time, e.g. "error: command not found: npx"
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    Healthy,
+    Timeout,
+    Error,
+    /// Reachable, but needs an OAuth token first
+    AuthRequired,
+}
+
+impl Outcome {
+    pub fn from_status(status: &HealthStatus) -> Option<Outcome> {
+        match status {
+            HealthStatus::Healthy { .. } => Some(Outcome::Healthy),
+            HealthStatus::Timeout => Some(Outcome::Timeout),
+            HealthStatus::Error(_) => Some(Outcome::Error),
+            HealthStatus::AuthRequired { .. } => Some(Outcome::AuthRequired),
+            HealthStatus::Unchecked | HealthStatus::Queued | HealthStatus::Checking => None,
+        }
+    }
+}
+
+/// Stable identity for a server across runs: its config file plus its name
+pub fn server_key(server: &McpServer) -> String {
+    format!("{}#{}", server.source_path, server.name)
+}
+
+pub fn unix_secs(t: SystemTime) -> u64 {
+    t.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+impl State {
+    /// ~/.local/share/mcpm/state.json (platform data dir elsewhere)
+    pub fn path() -> Option<PathBuf> {
+        Some(dirs::data_dir()?.join("mcpm/state.json"))
+    }
+
+    /// Load the state file, printing any warning to stderr
+    pub fn load() -> State {
+        let (state, warning) = Self::load_or_warn();
+        if let Some(w) = warning {
+            eprintln!("warning: {}", w);
+        }
+        state
+    }
+
+    /// Load the state file; a missing or unreadable file means empty state.
+    /// One that doesn't parse is moved aside to state.json.bad, so the next
+    /// save doesn't overwrite the history and pins in it, and the warning
+    /// says so.
+    pub fn load_or_warn() -> (State, Option<String>) {
+        let Some(path) = Self::path() else {
+            return (State::default(), None);
+        };
+        match read(&path) {
+            Ok(mut state) => {
+                state.base = state.servers.clone();
+                (state, None)
+            }
+            Err(e) => {
+                let bad = path.with_extension("json.bad");
+                let warning = match std::fs::rename(&path, &bad) {
+                    Ok(()) => format!(
+                        "{} is invalid ({}); moved it to {} and started fresh",
+                        path.display(),
+                        e,
+                        bad.display()
+                    ),
+                    Err(re) => format!(
+                        "{} is invalid ({}) and could not be moved aside: {}",
+                        path.display(),
+                        e,
+                        re
+                    ),
+                };
+                (State::default(), Some(warning))
+            }
+        }
+    }
+
+    /// Write the state back, merged with whatever other mcpm processes
+    /// saved since it was loaded: history is combined, and pins and tool
+    /// lists this process didn't change are taken from the file. Leaves the
+    /// merged state in `self`.
+    pub fn save(&mut self) -> Result<(), String> {
+        let path = Self::path().ok_or("could not determine data directory")?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("failed to create {}: {}", parent.display(), e))?;
+        }
+        let lock_path = path.with_extension("json.lock");
+        let lock = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .map_err(|e| format!("failed to open {}: {}", lock_path.display(), e))?;
+        lock.lock()
+            .map_err(|e| format!("failed to lock {}: {}", lock_path.display(), e))?;
+
+        // Never overwrite a file that went bad since loading; the next
+        // start moves it aside
+        let disk = read(&path).map_err(|e| format!("{} is invalid ({}); not overwriting it", path.display(), e))?;
+        self.merge(disk.servers);
+
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("failed to serialize state: {}", e))?;
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, json)
+            .map_err(|e| format!("failed to write {}: {}", tmp.display(), e))?;
+        std::fs::rename(&tmp, &path)
+            .map_err(|e| format!("failed to rename {}: {}", tmp.display(), e))?;
+        self.base = self.servers.clone();
+        Ok(())
+    }
+
+    /// Fold the file's current contents into this state, per server key
+    fn merge(&mut self, disk: BTreeMap<String, ServerState>) {
+        for (key, theirs) in disk {
+            let Some(ours) = self.servers.get_mut(&key) else {
+                self.servers.insert(key, theirs);
+                continue;
+            };
+            let base = self.base.get(&key);
+            if base.map(|b| &b.pin) == Some(&ours.pin) {
+                ours.pin = theirs.pin;
+            }
+            if base.map(|b| &b.tools) == Some(&ours.tools) {
+                ours.tools = theirs.tools;
+            }
+            for entry in theirs.history {
+                if !ours.history.contains(&entry) {
+                    ours.history.push(entry);
+                }
+            }
+            ours.history.sort_by_key(|e| e.at);
+            if ours.history.len() > MAX_HISTORY {
+                let excess = ours.history.len() - MAX_HISTORY;
+                ours.history.drain(..excess);
+            }
+        }
+    }
+
+    pub fn get(&self, server: &McpServer) -> Option<&ServerState> {
+        self.servers.get(&server_key(server))
+    }
+
+    /// Tool definitions last seen for this server, if any
+    pub fn tools(&self, server: &McpServer) -> Option<&[Value]> {
+        self.get(server)?.tools.as_ref().map(|t| t.tools.as_slice())
+    }
+
+    /// Remember the tool list a check fetched. The first one is pinned as
+    /// trusted.
+    pub fn record_tools(&mut self, server: &McpServer, tools: Vec<Value>, at: SystemTime) {
+        let entry = self.servers.entry(server_key(server)).or_default();
+        if entry.pin.is_none() {
+            entry.pin = Some(Pin::new(server, &tools, at));
+        }
+        entry.tools = Some(ToolSnapshot {
+            at: unix_secs(at),
+            tools,
+        });
+    }
+
+    /// Append a finished check to the server's history
+    pub fn record(&mut self, server: &McpServer, status: &HealthStatus, at: SystemTime) {
+        let Some(outcome) = Outcome::from_status(status) else {
+            return;
+        };
+        let entry = self.servers.entry(server_key(server)).or_default();
+        entry.history.push(HistoryEntry {
+            at: unix_secs(at),
+            outcome,
+            detail: status.label(),
+        });
+        if entry.history.len() > MAX_HISTORY {
+            let excess = entry.history.len() - MAX_HISTORY;
+            entry.history.drain(..excess);
+        }
+    }
+}
+
+/// Parse the state file; a missing one is empty state
+fn read(path: &Path) -> Result<State, String> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => serde_json::from_str(&text).map_err(|e| e.to_string()),
+        Err(_) => Ok(State::default()),
+    }
+}
+
+impl ServerState {
+    pub fn last(&self) -> Option<&HistoryEntry> {
+        self.history.last()
+    }
+
+    /// Timestamp of the most recent healthy check
+    pub fn last_healthy(&self) -> Option<u64> {
+        self.history
+            .iter()
+            .rev()
+            .find(|e| e.outcome == Outcome::Healthy)
+            .map(|e| e.at)
+    }
+
+    fn recent(&self) -> &[HistoryEntry] {
+        &self.history[self.history.len().saturating_sub(RECENT_WINDOW)..]
+    }
+
+    /// Number of healthy ↔ unhealthy transitions among recent checks
+    pub fn recent_changes(&self) -> usize {
+        self.recent()
+            .windows(2)
+            .filter(|w| (w[0].outcome == Outcome::Healthy) != (w[1].outcome == Outcome::Healthy))
+            .count()
+    }
+
+    pub fn is_flapping(&self) -> bool {
+        self.recent_changes() >= FLAP_CHANGES
+    }
+
+    /// Failing now, but healthy at some point in the recent window
+    pub fn regressed(&self) -> bool {
+        let recent = self.recent();
+        matches!(recent.last(), Some(e) if e.outcome != Outcome::Healthy)
+            && recent.iter().any(|e| e.outcome == Outcome::Healthy)
+    }
+
+    /// Outcomes of the last `n` checks, oldest first
+    pub fn sparkline(&self, n: usize) -> Vec<Outcome> {
+        self.history[self.history.len().saturating_sub(n)..]
+            .iter()
+            .map(|e| e.outcome)
+            .collect()
+    }
+}
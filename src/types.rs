@@ -1,6 +1,8 @@
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::time::Instant;
+use std::time::{Duration, SystemTime};
+
+use crate::launch::{LaunchProfile, PathSource, WorkDir};
 
 /// Which client configuration file a server was found in
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -30,6 +32,109 @@ impl ClientKind {
         }
     }
 
+    /// The application that loads this config. Several configs can feed
+    /// one application's session, e.g. Claude Code's global and project files.
+    pub fn app_name(&self) -> &'static str {
+        match self {
+            ClientKind::ClaudeCodeGlobal | ClientKind::ClaudeCodeProject | ClientKind::ClaudeCodePlugin => {
+                "Claude Code"
+            }
+            ClientKind::CursorGlobal | ClientKind::CursorProject => "Cursor",
+            ClientKind::VsCodeProject => "VS Code",
+            ClientKind::Windsurf => "Windsurf",
+            ClientKind::ClaudeDesktop => "Claude Desktop",
+        }
+    }
+
+    /// Tool name as the client hands it to the model. Characters outside
+    /// `[A-Za-z0-9_-]` are replaced with `_`, as the clients do.
+    pub fn qualified_tool_name(&self, server: &str, tool: &str) -> String {
+        let name = match self {
+            ClientKind::ClaudeCodeGlobal | ClientKind::ClaudeCodeProject | ClientKind::ClaudeCodePlugin => {
+                format!("mcp__{}__{}", server, tool)
+            }
+            ClientKind::VsCodeProject => format!("mcp_{}_{}", server, tool),
+            ClientKind::CursorGlobal | ClientKind::CursorProject => format!("{}_{}", server, tool),
+            ClientKind::Windsurf | ClientKind::ClaudeDesktop => tool.to_string(),
+        };
+        name.chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c } else { '_' })
+            .collect()
+    }
+
+    /// Longest qualified tool name the client accepts. Model APIs cap
+    /// function names at 64; Cursor warns above 60 for server + tool.
+    pub fn tool_name_limit(&self) -> usize {
+        match self {
+            ClientKind::CursorGlobal | ClientKind::CursorProject => 60,
+            _ => 64,
+        }
+    }
+
+    /// Most tools the client will load across all servers, where known.
+    /// Cursor drops tools past 40; VS Code caps a chat request at 128;
+    /// Windsurf's Cascade at 100.
+    pub fn tool_limit(&self) -> Option<usize> {
+        match self {
+            ClientKind::CursorGlobal | ClientKind::CursorProject => Some(40),
+            ClientKind::VsCodeProject => Some(128),
+            ClientKind::Windsurf => Some(100),
+            _ => None,
+        }
+    }
+
+    /// How this client starts its servers. Claude Code runs in a terminal
+    /// and inherits it; the GUI editors resolve PATH through a login shell;
+    /// Claude Desktop gets the bare desktop session.
+    pub fn launch_profile(&self) -> LaunchProfile {
+        let (path, cwd, full_env) = match self {
+            ClientKind::ClaudeCodeGlobal | ClientKind::ClaudeCodeProject | ClientKind::ClaudeCodePlugin => {
+                (PathSource::Inherit, WorkDir::Project, true)
+            }
+            ClientKind::VsCodeProject => (PathSource::LoginShell, WorkDir::Project, true),
+            ClientKind::CursorGlobal | ClientKind::CursorProject | ClientKind::Windsurf => {
+                (PathSource::LoginShell, WorkDir::Home, true)
+            }
+            ClientKind::ClaudeDesktop => (PathSource::Session, WorkDir::Root, false),
+        };
+        LaunchProfile { path, cwd, full_env }
+    }
+
+    /// Whether the client can connect to HTTP/SSE servers itself. Claude
+    /// Desktop's config file only launches local processes.
+    pub fn supports_remote(&self) -> bool {
+        !matches!(self, ClientKind::ClaudeDesktop)
+    }
+
+    /// Whether the client expands environment variable references in its
+    /// config when launching a server
+    pub fn expands_env(&self) -> bool {
+        self.env_reference("").is_some()
+    }
+
+    /// How the client's config refers to an environment variable, for
+    /// clients that expand them when launching a server. Windsurf and
+    /// Claude Desktop take values literally.
+    pub fn env_reference(&self, var: &str) -> Option<String> {
+        match self {
+            ClientKind::ClaudeCodeGlobal | ClientKind::ClaudeCodeProject | ClientKind::ClaudeCodePlugin => {
+                Some(format!("${{{}}}", var))
+            }
+            ClientKind::CursorGlobal | ClientKind::CursorProject | ClientKind::VsCodeProject => {
+                Some(format!("${{env:{}}}", var))
+            }
+            ClientKind::Windsurf | ClientKind::ClaudeDesktop => None,
+        }
+    }
+
+    /// Look a client up by its label, ignoring case
+    pub fn from_label(label: &str) -> Option<ClientKind> {
+        ClientKind::all()
+            .iter()
+            .find(|c| c.label().eq_ignore_ascii_case(label))
+            .cloned()
+    }
+
     /// Writable client variants
     pub fn writable() -> &'static [ClientKind] {
         &[
@@ -131,12 +236,19 @@ impl Transport {
     pub fn is_stdio(&self) -> bool {
         matches!(self, Transport::Stdio { .. })
     }
+
+    /// Whether mcpm can health-check this transport
+    pub fn is_checkable(&self) -> bool {
+        !matches!(self, Transport::Unknown)
+    }
 }
 
 /// Health check status for a server
 #[derive(Debug, Clone)]
 pub enum HealthStatus {
     Unchecked,
+    /// Waiting for a free check slot
+    Queued,
     Checking,
     Healthy {
         server_name: String,
@@ -144,22 +256,30 @@ pub enum HealthStatus {
     },
     Timeout,
     Error(String),
+    /// An HTTP server answered 401 and said where to get a token
+    AuthRequired {
+        issuer: String,
+        scopes: Vec<String>,
+    },
 }
 
 impl HealthStatus {
     pub fn symbol(&self) -> &'static str {
         match self {
             HealthStatus::Unchecked => "",
+            HealthStatus::Queued => "…",
             HealthStatus::Checking => "⟳",
             HealthStatus::Healthy { .. } => "●",
             HealthStatus::Timeout => "⚠",
             HealthStatus::Error(_) => "✗",
+            HealthStatus::AuthRequired { .. } => "◆",
         }
     }
 
     pub fn label(&self) -> String {
         match self {
             HealthStatus::Unchecked => "unchecked".to_string(),
+            HealthStatus::Queued => "queued".to_string(),
             HealthStatus::Checking => "checking...".to_string(),
             HealthStatus::Healthy {
                 server_name,
@@ -167,15 +287,140 @@ impl HealthStatus {
             } => format!("healthy ({} v{})", server_name, server_version),
             HealthStatus::Timeout => "timeout (5s)".to_string(),
             HealthStatus::Error(e) => format!("error: {}", e),
+            HealthStatus::AuthRequired { issuer, scopes } if scopes.is_empty() => {
+                format!("requires OAuth (issuer {})", issuer)
+            }
+            HealthStatus::AuthRequired { issuer, scopes } => {
+                format!("requires OAuth (issuer {}, scopes {})", issuer, scopes.join(" "))
+            }
+        }
+    }
+}
+
+/// How a health-checked server's process group went away afterwards
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cleanup {
+    /// Exited on its own once stdin was closed
+    Exited,
+    /// Needed SIGTERM
+    Terminated,
+    /// Needed SIGKILL
+    Killed,
+    /// Processes were still running after SIGKILL
+    Leaked,
+}
+
+impl Cleanup {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Cleanup::Exited => "exited on stdin close",
+            Cleanup::Terminated => "terminated (SIGTERM)",
+            Cleanup::Killed => "killed (SIGKILL)",
+            Cleanup::Leaked => "processes left running",
+        }
+    }
+}
+
+/// Startup latency and resource usage measured during a health check.
+/// Fields are None when that stage wasn't reached or isn't measurable here.
+#[derive(Debug, Clone, Default)]
+pub struct HealthMetrics {
+    /// Time for the OS to spawn the process
+    pub spawn: Option<Duration>,
+    /// Time from spawn until the first byte on stdout
+    pub first_byte: Option<Duration>,
+    /// `initialize` request → response round-trip
+    pub initialize: Option<Duration>,
+    /// `tools/list` round-trip (all pages)
+    pub tools_list: Option<Duration>,
+    /// Number of tools the server listed
+    pub tool_count: Option<usize>,
+    /// Peak resident memory of the whole process tree (Linux only)
+    pub peak_rss_bytes: Option<u64>,
+    /// User + system CPU time of the process tree (Linux only)
+    pub cpu_time: Option<Duration>,
+}
+
+impl HealthMetrics {
+    /// Label/value pairs for every measured field, in display order
+    pub fn parts(&self) -> Vec<(&'static str, String)> {
+        let mut parts = Vec::new();
+        if let Some(d) = self.spawn {
+            parts.push(("Spawn", format_duration(d)));
+        }
+        if let Some(d) = self.first_byte {
+            parts.push(("First byte", format_duration(d)));
+        }
+        if let Some(d) = self.initialize {
+            parts.push(("Initialize", format_duration(d)));
         }
+        if let Some(d) = self.tools_list {
+            parts.push(("tools/list", format_duration(d)));
+        }
+        if let Some(n) = self.tool_count {
+            parts.push(("Tools", n.to_string()));
+        }
+        if let Some(b) = self.peak_rss_bytes {
+            parts.push(("Peak RSS", format_bytes(b)));
+        }
+        if let Some(d) = self.cpu_time {
+            parts.push(("CPU time", format_duration(d)));
+        }
+        parts
+    }
+}
+
+pub fn format_duration(d: Duration) -> String {
+    let ms = d.as_millis();
+    if ms == 0 {
+        format!("{}µs", d.as_micros())
+    } else if ms < 1000 {
+        format!("{}ms", ms)
+    } else {
+        format!("{:.2}s", d.as_secs_f64())
+    }
+}
+
+/// Relative age of a past moment, e.g. "42s ago" or "3d ago"
+pub fn format_ago(secs: u64) -> String {
+    if secs < 60 {
+        format!("{}s ago", secs)
+    } else if secs < 3600 {
+        format!("{}m ago", secs / 60)
+    } else if secs < 86400 {
+        format!("{}h ago", secs / 3600)
+    } else {
+        format!("{}d ago", secs / 86400)
+    }
+}
+
+/// Age of a Unix timestamp relative to now
+pub fn format_ago_unix(at: u64) -> String {
+    let now = crate::state::unix_secs(SystemTime::now());
+    format_ago(now.saturating_sub(at))
+}
+
+pub fn format_bytes(b: u64) -> String {
+    const MB: f64 = 1024.0 * 1024.0;
+    if (b as f64) < MB {
+        format!("{:.0} KB", b as f64 / 1024.0)
+    } else {
+        format!("{:.1} MB", b as f64 / MB)
     }
 }
 
 /// Result from a background health check thread
 pub struct HealthResult {
     pub server_index: usize,
+    /// `state::server_key` of the checked server, to catch a reshuffled list
+    pub server_key: String,
     pub status: HealthStatus,
-    pub checked_at: Instant,
+    pub checked_at: SystemTime,
+    /// None when the process was never spawned
+    pub cleanup: Option<Cleanup>,
+    pub metrics: HealthMetrics,
+    /// Tool definitions from `tools/list`, when the server answered it
+    pub tools: Option<Vec<serde_json::Value>>,
 }
 
 /// A single MCP server entry as found in a config file
@@ -187,7 +432,9 @@ pub struct McpServer {
     pub transport: Transport,
     pub env: Option<HashMap<String, String>>,
     pub health: HealthStatus,
-    pub last_checked: Option<Instant>,
+    pub last_checked: Option<SystemTime>,
+    pub cleanup: Option<Cleanup>,
+    pub metrics: HealthMetrics,
 }
 
 /// All discovered data, ready for the UI
@@ -220,3 +467,12 @@ pub(crate) fn find_msix_claude_config() -> Option<PathBuf> {
     }
     None
 }
+
+/// Token estimate for display, e.g. "850" or "12.3k"
+pub fn format_tokens(n: usize) -> String {
+    if n < 1000 {
+        n.to_string()
+    } else {
+        format!("{:.1}k", n as f64 / 1000.0)
+    }
+}
@@ -1,5 +1,4 @@
 use std::collections::BTreeSet;
-use std::time::Instant;
 
 use ratatui::{
     layout::{Constraint, Layout, Rect},
@@ -10,9 +9,16 @@ use ratatui::{
 };
 
 use crate::app::App;
-use crate::types::{HealthStatus, Transport};
+use crate::inspector::{InspectStep, InspectTab, Inspector, ResultLine, item_name};
+use crate::pins::{Change, Verdict};
+use crate::secrets;
+use crate::state::{Outcome, ServerState};
+use crate::types::{Cleanup, HealthStatus, Transport, format_ago, format_ago_unix, format_tokens};
 use crate::wizard::*;
 
+/// Recent checks shown in the detail panel's history sparkline
+const SPARKLINE_LEN: usize = 20;
+
 pub fn render(f: &mut Frame, app: &mut App) {
     let area = f.area();
 
@@ -21,7 +27,8 @@ pub fn render(f: &mut Frame, app: &mut App) {
     let matrix_height = if app.result.active_clients.is_empty() {
         3
     } else {
-        (unique_names.len() + 3).min(14) as u16
+        // rows + header + context footer + borders
+        (unique_names.len() + 4).min(15) as u16
     };
 
     let vertical = Layout::vertical([
@@ -45,6 +52,7 @@ pub fn render(f: &mut Frame, app: &mut App) {
     // Modal overlays
     match &app.mode {
         Mode::AddWizard(wiz) => render_add_wizard(f, area, wiz),
+        Mode::Inspector(insp) => render_inspector(f, area, insp),
         Mode::RemoveConfirm(rm) => render_remove_confirm(f, area, rm),
         Mode::SyncSelect(sync) => render_sync_select(f, area, sync),
         Mode::Normal => {}
@@ -63,10 +71,14 @@ fn render_header(f: &mut Frame, area: Rect, app: &App) {
     } else {
         String::new()
     };
-    let checking = if app.checking_count > 0 {
-        format!(" [checking {}...]", app.checking_count)
-    } else {
-        String::new()
+    let checking = match (app.checking_count, app.queued_count()) {
+        (0, 0) => String::new(),
+        (running, 0) => format!(" [checking {}...]", running),
+        (running, queued) => format!(" [checking {}, {} queued]", running, queued),
+    };
+    let monitor = match app.auto_check {
+        Some(interval) => format!(" [monitor {}s]", interval.as_secs()),
+        None => String::new(),
     };
 
     let line = Line::from(vec![
@@ -81,11 +93,12 @@ fn render_header(f: &mut Frame, area: Rect, app: &App) {
             Style::default().fg(Color::DarkGray),
         ),
         Span::raw(format!(
-            " — {} server{}{}{}",
+            " — {} server{}{}{}{}",
             server_count,
             if server_count == 1 { "" } else { "s" },
             err_indicator,
             checking,
+            monitor,
         )),
     ]);
     f.render_widget(Paragraph::new(line), area);
@@ -104,14 +117,26 @@ fn render_status_bar(f: &mut Frame, area: Rect, app: &App) {
     } else {
         let keys = match &app.mode {
             Mode::Normal => {
-                " a:add  d:remove  s:sync  e:edit  u:undo  h:check  c:check-all  !:errors  r:refresh  q:quit"
+                " a:add  d:remove  s:sync  e:edit  u:undo  i:inspect  h:check  c:check-all  m:monitor  v:secrets  !:errors  r:refresh  q:quit"
             }
             Mode::AddWizard(wiz) => match wiz.step {
                 AddStep::TransportType => " j/k:select  enter:next  esc:cancel",
+                AddStep::SaveSecrets => " y:save to vault  n:keep in config  esc:cancel",
                 AddStep::Clients => " space:toggle  j/k:move  enter:next  esc:cancel",
                 AddStep::Confirm => " y:confirm  n:cancel  esc:cancel",
                 _ => " enter:next  esc:cancel",
             },
+            Mode::Inspector(insp) => match insp.step {
+                InspectStep::List => match insp.tab {
+                    InspectTab::Tools => " tab:next-tab  j/k:select  enter:call  esc:close",
+                    InspectTab::Resources => " tab:next-tab  j/k:select  enter:read  esc:close",
+                    InspectTab::Prompts => " tab:next-tab  j/k:select  enter:get  esc:close",
+                },
+                InspectStep::Form => " tab/↑↓:field  enter:call  esc:back",
+                InspectStep::Calling => " esc:stop waiting",
+                InspectStep::Result => " j/k:scroll  esc:back",
+                InspectStep::Connecting | InspectStep::Failed => " esc:close",
+            },
             Mode::RemoveConfirm(rm) => match rm.step {
                 RemoveStep::SelectClients => " space:toggle  j/k:move  enter:next  esc:cancel",
                 RemoveStep::Confirm => " y:confirm  n:cancel  esc:cancel",
@@ -181,9 +206,9 @@ fn render_server_list(f: &mut Frame, area: Rect, app: &mut App) {
 }
 
 fn render_detail(f: &mut Frame, area: Rect, app: &mut App) {
-    let lines = match app.selected_server() {
-        None => vec![Line::from("  No servers found. Press [a] to add one.")],
-        Some(s) => build_detail_lines(s),
+    let lines = match (app.selected_server(), app.pin_verdicts.get(app.selected)) {
+        (Some(s), Some(pin)) => build_detail_lines(s, app.state.get(s), pin, app.show_secrets),
+        _ => vec![Line::from("  No servers found. Press [a] to add one.")],
     };
     // Track content and visible height for scroll bounds
     app.detail_content_height = lines.len();
@@ -201,7 +226,12 @@ fn render_detail(f: &mut Frame, area: Rect, app: &mut App) {
     f.render_widget(para, area);
 }
 
-fn build_detail_lines(s: &crate::types::McpServer) -> Vec<Line<'static>> {
+fn build_detail_lines(
+    s: &crate::types::McpServer,
+    history: Option<&ServerState>,
+    pin: &Verdict,
+    show_secrets: bool,
+) -> Vec<Line<'static>> {
     let mut lines = vec![
         kv_line("Name", &s.name),
         kv_line("Client", s.client.label()),
@@ -211,20 +241,21 @@ fn build_detail_lines(s: &crate::types::McpServer) -> Vec<Line<'static>> {
 
     match &s.transport {
         Transport::Http { url, headers } => {
-            lines.push(kv_line("URL", url));
+            lines.push(kv_line("URL", &masked_url(url, show_secrets)));
             if let Some(h) = headers {
                 lines.push(section_line("Headers"));
                 for (k, v) in h {
-                    lines.push(indent_kv(k, v));
+                    lines.push(indent_kv(k, &masked(k, v, show_secrets)));
                 }
             }
         }
         Transport::Sse { url } => {
-            lines.push(kv_line("URL", url));
+            lines.push(kv_line("URL", &masked_url(url, show_secrets)));
         }
         Transport::Stdio { command, args } => {
             lines.push(kv_line("Command", command));
             if !args.is_empty() {
+                let args = if show_secrets { args.clone() } else { secrets::mask_args(args) };
                 lines.push(kv_line("Args", &args.join(" ")));
             }
         }
@@ -233,8 +264,10 @@ fn build_detail_lines(s: &crate::types::McpServer) -> Vec<Line<'static>> {
 
     if let Some(env) = &s.env {
         lines.push(section_line("Environment"));
-        for (k, _) in env {
-            lines.push(indent_kv(k, "***"));
+        // Always masked: a short password under a plain name looks like
+        // any other value
+        for (k, v) in env {
+            lines.push(indent_kv(k, if show_secrets { v } else { "***" }));
         }
     }
 
@@ -253,12 +286,84 @@ fn build_detail_lines(s: &crate::types::McpServer) -> Vec<Line<'static>> {
         ),
     ]));
 
-    let checked_text = match s.last_checked {
-        Some(t) => format_elapsed(t),
-        None => "never".to_string(),
+    let checked_text = match (s.last_checked, history.and_then(ServerState::last)) {
+        (Some(t), _) => format_ago(t.elapsed().map(|d| d.as_secs()).unwrap_or(0)),
+        (None, Some(prev)) => format!("{} ({})", format_ago_unix(prev.at), prev.detail),
+        (None, None) => "never".to_string(),
     };
     lines.push(kv_line("Checked", &checked_text));
 
+    if let Some(h) = history.filter(|h| !h.history.is_empty()) {
+        let healthy_text = match h.last_healthy() {
+            Some(at) => format_ago_unix(at),
+            None => "never".to_string(),
+        };
+        lines.push(kv_line("Last healthy", &healthy_text));
+
+        let mut spans = vec![Span::styled(
+            format!("  {:<12}", "History"),
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )];
+        spans.extend(h.sparkline(SPARKLINE_LEN).into_iter().map(|o| {
+            let (bar, color) = match o {
+                Outcome::Healthy => ("█", Color::Green),
+                Outcome::Timeout => ("▄", Color::Yellow),
+                Outcome::Error => ("▁", Color::Red),
+                Outcome::AuthRequired => ("▂", Color::Cyan),
+            };
+            Span::styled(bar, Style::default().fg(color))
+        }));
+        if h.is_flapping() {
+            spans.push(Span::styled("  flapping", Style::default().fg(Color::Yellow)));
+        } else if h.regressed() {
+            spans.push(Span::styled("  regressed", Style::default().fg(Color::Red)));
+        }
+        lines.push(Line::from(spans));
+    }
+
+    let pinned = match pin {
+        Verdict::Unpinned => None,
+        Verdict::Trusted => {
+            let at = history.and_then(|h| h.pin.as_ref()).map_or(0, |p| p.at);
+            Some((format!("✓ matches, pinned {}", format_ago_unix(at)), Color::Green))
+        }
+        Verdict::Changed(changes) => {
+            let summary: Vec<String> = changes.iter().map(Change::summary).collect();
+            Some((format!("⚠ {} (mcpm pins show {})", summary.join(", "), s.name), Color::Yellow))
+        }
+        Verdict::Rejected(_) => Some(("✗ runs definitions you rejected".to_string(), Color::Red)),
+    };
+    if let Some((text, color)) = pinned {
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("  {:<12}", "Pin"),
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(text, Style::default().fg(color)),
+        ]));
+    }
+
+    match s.cleanup {
+        Some(Cleanup::Leaked) => lines.push(Line::from(vec![
+            Span::styled(
+                format!("  {:<12}", "Shutdown"),
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                format!("⚠ {}", Cleanup::Leaked.label()),
+                Style::default().fg(Color::Red),
+            ),
+        ])),
+        Some(c) => lines.push(kv_line("Shutdown", c.label())),
+        None => {}
+    }
+
     if let HealthStatus::Healthy {
         server_name,
         server_version,
@@ -267,16 +372,30 @@ fn build_detail_lines(s: &crate::types::McpServer) -> Vec<Line<'static>> {
         lines.push(kv_line("Server", &format!("{} v{}", server_name, server_version)));
     }
 
-    if s.transport.is_stdio() && matches!(s.health, HealthStatus::Unchecked) {
+    let metrics = s.metrics.parts();
+    if !metrics.is_empty() {
+        lines.push(section_line("Startup"));
+        for (label, value) in &metrics {
+            lines.push(indent_kv(label, value));
+        }
+    }
+
+    if s.transport.is_checkable() && matches!(s.health, HealthStatus::Unchecked) {
         lines.push(Line::from(""));
         lines.push(Line::from(Span::styled(
             "  Press [h] to health check this server",
             Style::default().fg(Color::DarkGray),
         )));
-    } else if !s.transport.is_stdio() {
+    } else if matches!(s.health, HealthStatus::AuthRequired { .. }) {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            format!("  Run `mcpm login {}` to get a token", s.name),
+            Style::default().fg(Color::DarkGray),
+        )));
+    } else if !s.transport.is_checkable() {
         lines.push(Line::from(""));
         lines.push(Line::from(Span::styled(
-            "  Health checks only available for stdio servers",
+            "  Health checks unavailable for unknown transport",
             Style::default().fg(Color::DarkGray),
         )));
     }
@@ -360,6 +479,23 @@ fn render_add_wizard(f: &mut Frame, area: Rect, wiz: &AddWizard) {
                 Span::styled("█", Style::default().fg(Color::Cyan)),
             ]));
         }
+        AddStep::SaveSecrets => {
+            lines.push(Line::from(Span::styled(
+                "  These values look like secrets:",
+                Style::default().fg(Color::Yellow),
+            )));
+            lines.push(Line::from(""));
+            for key in wiz.secret_keys() {
+                lines.push(Line::from(Span::styled(
+                    format!("    {}", key),
+                    Style::default().fg(Color::Green),
+                )));
+            }
+            lines.push(Line::from(""));
+            lines.push(Line::from("  Save them to the mcpm vault and write ${secret:…}"));
+            lines.push(Line::from("  placeholders instead? Clients that expand env vars get a"));
+            lines.push(Line::from("  reference; the others still get the value."));
+        }
         AddStep::Clients => {
             lines.push(Line::from(Span::styled(
                 "  Install to:",
@@ -416,9 +552,14 @@ fn render_add_wizard(f: &mut Frame, area: Rect, wiz: &AddWizard) {
             }
             if !wiz.env_lines.is_empty() {
                 lines.push(Line::from(Span::raw(format!(
-                    "  Env:     {} var{}",
+                    "  Env:     {} var{}{}",
                     wiz.env_lines.len(),
-                    if wiz.env_lines.len() == 1 { "" } else { "s" }
+                    if wiz.env_lines.len() == 1 { "" } else { "s" },
+                    if wiz.save_secrets {
+                        format!(" ({} saved to the vault)", wiz.secret_keys().len())
+                    } else {
+                        String::new()
+                    }
                 ))));
             }
         }
@@ -437,6 +578,215 @@ fn render_add_wizard(f: &mut Frame, area: Rect, wiz: &AddWizard) {
     f.render_widget(para, popup);
 }
 
+// ---------------------------------------------------------------------------
+// Modal: Inspector
+// ---------------------------------------------------------------------------
+
+fn render_inspector(f: &mut Frame, area: Rect, insp: &Inspector) {
+    let popup = centered_rect(80, 80, area);
+    f.render_widget(Clear, popup);
+
+    let title = if insp.server_info.is_empty() {
+        format!(" Inspect \"{}\" ", insp.server_name)
+    } else {
+        format!(" Inspect \"{}\" — {} ", insp.server_name, insp.server_info)
+    };
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan));
+    // Lines that fit inside the borders, less the leading blank line
+    let visible = popup.height.saturating_sub(3) as usize;
+    let width = popup.width.saturating_sub(6) as usize;
+
+    let mut lines: Vec<Line> = vec![Line::from("")];
+
+    match insp.step {
+        InspectStep::Connecting => {
+            lines.push(Line::from(Span::styled(
+                "  Starting server...",
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+        InspectStep::Failed => {
+            lines.push(Line::from(Span::styled(
+                format!("  ✗ {}", insp.error.as_deref().unwrap_or("session failed")),
+                Style::default().fg(Color::Red),
+            )));
+        }
+        InspectStep::List => {
+            let tabs: Vec<Span> = InspectTab::ALL
+                .iter()
+                .flat_map(|&tab| {
+                    let label = format!(" {} ({}) ", tab.label(), insp.list(tab).items.len());
+                    let style = if tab == insp.tab {
+                        Style::default().fg(Color::Black).bg(Color::Cyan)
+                    } else {
+                        Style::default().fg(Color::DarkGray)
+                    };
+                    [Span::raw("  "), Span::styled(label, style)]
+                })
+                .collect();
+            lines.push(Line::from(tabs));
+            lines.push(Line::from(""));
+
+            let list = insp.list(insp.tab);
+            let empty = if list.loading {
+                Some("Loading...".to_string())
+            } else if let Some(note) = &list.note {
+                Some(note.clone())
+            } else if list.items.is_empty() {
+                Some(format!("Server has no {}", insp.tab.label().to_lowercase()))
+            } else {
+                None
+            };
+            if let Some(msg) = empty {
+                lines.push(Line::from(Span::styled(
+                    format!("  {}", msg),
+                    Style::default().fg(Color::DarkGray),
+                )));
+            }
+
+            let rows = visible.saturating_sub(2);
+            let skip = (insp.cursor + 1).saturating_sub(rows);
+            for (i, item) in list.items.iter().enumerate().skip(skip).take(rows) {
+                let detail = match insp.tab {
+                    InspectTab::Tools => {
+                        item["description"].as_str().unwrap_or("").lines().next().unwrap_or("").to_string()
+                    }
+                    InspectTab::Resources => match item["uriTemplate"].as_str() {
+                        Some(template) => format!("{}  (template)", template),
+                        None => format!(
+                            "{}  {}",
+                            item["uri"].as_str().unwrap_or(""),
+                            item["mimeType"].as_str().unwrap_or("")
+                        ),
+                    },
+                    InspectTab::Prompts => {
+                        let args: Vec<String> = item["arguments"]
+                            .as_array()
+                            .into_iter()
+                            .flatten()
+                            .map(|a| {
+                                let required = a["required"].as_bool().unwrap_or(false);
+                                format!("{}{}", a["name"].as_str().unwrap_or("?"), if required { "*" } else { "" })
+                            })
+                            .collect();
+                        let desc = item["description"].as_str().unwrap_or("").lines().next().unwrap_or("");
+                        if args.is_empty() {
+                            desc.to_string()
+                        } else {
+                            format!("({})  {}", args.join(", "), desc)
+                        }
+                    }
+                };
+                let selected = i == insp.cursor;
+                let cursor = if selected { "▸" } else { " " };
+                let style = if selected {
+                    Style::default().fg(Color::Cyan)
+                } else {
+                    Style::default()
+                };
+                lines.push(Line::from(vec![
+                    Span::styled(format!("  {} {:<24} ", cursor, truncate(item_name(item), 24)), style),
+                    Span::styled(
+                        truncate(&detail, width.saturating_sub(28).max(1)),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ]));
+            }
+        }
+        InspectStep::Form | InspectStep::Calling => {
+            let form = &insp.form;
+            lines.push(Line::from(Span::styled(
+                format!("  Arguments for {}:", form.target),
+                Style::default().fg(Color::Yellow),
+            )));
+            lines.push(Line::from(""));
+            if form.fields.is_empty() {
+                lines.push(Line::from(Span::styled(
+                    "  No arguments",
+                    Style::default().fg(Color::DarkGray),
+                )));
+            }
+            for (i, field) in form.fields.iter().enumerate() {
+                let selected = i == form.cursor && insp.step == InspectStep::Form;
+                let cursor = if selected { "▸" } else { " " };
+                let mut spans = vec![
+                    Span::styled(
+                        format!("  {} {}{}", cursor, field.name, if field.required { "*" } else { "" }),
+                        if selected {
+                            Style::default().fg(Color::Cyan)
+                        } else {
+                            Style::default()
+                        },
+                    ),
+                    Span::styled(
+                        format!(" ({}) ", field.kind.label()),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::styled(field.input.clone(), Style::default().fg(Color::White)),
+                ];
+                if selected {
+                    spans.push(Span::styled("█", Style::default().fg(Color::Cyan)));
+                }
+                lines.push(Line::from(spans));
+                let mut hint = field.description.lines().next().unwrap_or("").to_string();
+                if !field.choices.is_empty() {
+                    if !hint.is_empty() {
+                        hint.push_str(" — ");
+                    }
+                    hint.push_str(&format!("one of: {}", field.choices.join(", ")));
+                }
+                if !hint.is_empty() {
+                    lines.push(Line::from(Span::styled(
+                        format!("      {}", truncate(&hint, width.saturating_sub(4).max(1))),
+                        Style::default().fg(Color::DarkGray),
+                    )));
+                }
+            }
+            if insp.step == InspectStep::Calling {
+                lines.push(Line::from(""));
+                lines.push(Line::from(Span::styled(
+                    format!("  Waiting for {}...", form.target),
+                    Style::default().fg(Color::Yellow),
+                )));
+            }
+            if let Some(err) = &form.error {
+                lines.push(Line::from(""));
+                lines.push(Line::from(Span::styled(
+                    format!("  ⚠ {}", err),
+                    Style::default().fg(Color::Red),
+                )));
+            }
+        }
+        InspectStep::Result => {
+            lines.push(Line::from(Span::styled(
+                format!("  Result of {}:", insp.form.target),
+                Style::default().fg(Color::Yellow),
+            )));
+            lines.push(Line::from(""));
+            for line in insp.result_lines.iter().skip(insp.scroll).take(visible.saturating_sub(2)) {
+                lines.push(match line {
+                    ResultLine::Heading(t) => section_line(t),
+                    ResultLine::Text(t) => Line::from(format!("  {}", t)),
+                    ResultLine::Meta(t) => Line::from(Span::styled(
+                        format!("  {}", t),
+                        Style::default().fg(Color::Magenta),
+                    )),
+                    ResultLine::Error(t) => Line::from(Span::styled(
+                        format!("  ✗ {}", t),
+                        Style::default().fg(Color::Red),
+                    )),
+                });
+            }
+        }
+    }
+
+    let para = Paragraph::new(lines).block(block);
+    f.render_widget(para, popup);
+}
+
 // ---------------------------------------------------------------------------
 // Modal: Remove Confirm
 // ---------------------------------------------------------------------------
@@ -538,10 +888,14 @@ fn render_sync_select(f: &mut Frame, area: Rect, sync: &SyncSelect) {
             } else {
                 Style::default()
             };
-            lines.push(Line::from(Span::styled(
+            let mut spans = vec![Span::styled(
                 format!("  {} [{}] {}", cursor, check, client.label()),
                 style,
-            )));
+            )];
+            if sync.needs_bridge(client) {
+                spans.push(Span::styled(" via mcpm bridge", Style::default().fg(Color::DarkGray)));
+            }
+            lines.push(Line::from(spans));
         }
     }
 
@@ -550,7 +904,7 @@ fn render_sync_select(f: &mut Frame, area: Rect, sync: &SyncSelect) {
 }
 
 // ---------------------------------------------------------------------------
-// Matrix (unchanged)
+// Matrix
 // ---------------------------------------------------------------------------
 
 fn render_matrix(f: &mut Frame, area: Rect, app: &App) {
@@ -587,6 +941,9 @@ fn render_matrix(f: &mut Frame, area: Rect, app: &App) {
             .insert(&s.client);
     }
 
+    let report = &app.matrix;
+    let names = &report.names;
+
     let header_cells: Vec<Cell> = std::iter::once(Cell::from(""))
         .chain(clients.iter().map(|c| {
             Cell::from(c.label()).style(
@@ -595,6 +952,9 @@ fn render_matrix(f: &mut Frame, area: Rect, app: &App) {
                     .add_modifier(Modifier::BOLD),
             )
         }))
+        .chain(std::iter::once(
+            Cell::from("~Tokens").style(Style::default().fg(Color::DarkGray)),
+        ))
         .collect();
     let header = Row::new(header_cells);
 
@@ -602,34 +962,79 @@ fn render_matrix(f: &mut Frame, area: Rect, app: &App) {
         .iter()
         .map(|name| {
             let client_set = server_clients.get(name.as_str());
+            let tokens = match report.tokens.get(name).copied() {
+                Some(t) if t > report.budget => {
+                    Cell::from(format!("{}!", format_tokens(t))).style(Style::default().fg(Color::Red))
+                }
+                Some(t) => Cell::from(format_tokens(t)).style(Style::default().fg(Color::Gray)),
+                None => Cell::from("?").style(Style::default().fg(Color::DarkGray)),
+            };
             let cells: Vec<Cell> = std::iter::once(
                 Cell::from(truncate(name, 20)).style(Style::default().fg(Color::White)),
             )
             .chain(clients.iter().map(|c| {
-                if client_set.is_some_and(|cs| cs.contains(c)) {
+                if client_set.is_some_and(|cs| cs.contains(c)) && names.affects(name, c) {
+                    Cell::from(" ⚠").style(Style::default().fg(Color::Yellow))
+                } else if client_set.is_some_and(|cs| cs.contains(c)) {
                     Cell::from(" ✓").style(Style::default().fg(Color::Green))
                 } else {
                     Cell::from(" ·").style(Style::default().fg(Color::DarkGray))
                 }
             }))
+            .chain(std::iter::once(tokens))
             .collect();
             Row::new(cells)
         })
         .collect();
 
+    // Per-client context total; "+" marks servers whose tools are unknown
+    let footer_cells: Vec<Cell> = std::iter::once(
+        Cell::from("~context").style(Style::default().fg(Color::DarkGray)),
+    )
+    .chain(clients.iter().map(|c| match report.client(c) {
+        Some(cost) => Cell::from(format!(
+            " {}{}",
+            format_tokens(cost.tokens),
+            if cost.unknown.is_empty() { "" } else { "+" }
+        ))
+        .style(Style::default().fg(Color::Gray)),
+        None => Cell::from(""),
+    }))
+    .collect();
+    let footer = Row::new(footer_cells);
+
     let widths: Vec<Constraint> = std::iter::once(Constraint::Length(22))
         .chain(std::iter::repeat_n(
             Constraint::Length(11),
             clients.len(),
         ))
+        .chain(std::iter::once(Constraint::Length(8)))
         .collect();
 
-    let table = Table::new(rows, widths).header(header).block(
-        Block::default()
-            .title(" Client Matrix ")
-            .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::Cyan)),
-    );
+    let mut block = Block::default()
+        .title(" Client Matrix ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan));
+    let mut warnings = Vec::new();
+    if !names.collisions.is_empty() {
+        let n = names.collisions.len();
+        warnings.push(format!("{} tool name collision{}", n, if n == 1 { "" } else { "s" }));
+    }
+    if !names.too_long.is_empty() {
+        let n = names.too_long.len();
+        warnings.push(format!("{} tool name{} over client limit", n, if n == 1 { "" } else { "s" }));
+    }
+    for excess in &report.limits {
+        warnings.push(excess.summary());
+    }
+    if !warnings.is_empty() {
+        block = block.title_bottom(Line::from(Span::styled(
+            format!(" ⚠ {} — see mcpm doctor ", warnings.join(" · ")),
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+
+    let table = Table::new(rows, widths).header(header).footer(footer).block(block);
 
     f.render_widget(table, area);
 }
@@ -668,22 +1073,21 @@ fn render_error_overlay(f: &mut Frame, area: Rect, app: &App) {
 fn health_color(status: &HealthStatus) -> Color {
     match status {
         HealthStatus::Unchecked => Color::DarkGray,
+        HealthStatus::Queued => Color::DarkGray,
         HealthStatus::Checking => Color::Yellow,
         HealthStatus::Healthy { .. } => Color::Green,
         HealthStatus::Timeout => Color::Yellow,
         HealthStatus::Error(_) => Color::Red,
+        HealthStatus::AuthRequired { .. } => Color::Cyan,
     }
 }
 
-fn format_elapsed(since: Instant) -> String {
-    let secs = since.elapsed().as_secs();
-    if secs < 60 {
-        format!("{}s ago", secs)
-    } else if secs < 3600 {
-        format!("{}m ago", secs / 60)
-    } else {
-        format!("{}h ago", secs / 3600)
-    }
+fn masked(name: &str, value: &str, show_secrets: bool) -> String {
+    if show_secrets { value.to_string() } else { secrets::display(name, value) }
+}
+
+fn masked_url(url: &str, show_secrets: bool) -> String {
+    if show_secrets { url.to_string() } else { secrets::mask_url(url) }
 }
 
 fn kv_line(key: &str, value: &str) -> Line<'static> {
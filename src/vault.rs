@@ -0,0 +1,395 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chacha20poly1305::aead::Aead;
+use chacha20poly1305::{ChaCha20Poly1305, KeyInit, Nonce};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::types::ClientKind;
+
+/// Environment variable holding the vault passphrase, for the TUI, `mcpm mcp`
+/// and scripts that can't answer a prompt
+pub const PASSPHRASE_VAR: &str = "MCPM_VAULT_PASSPHRASE";
+
+/// Local secret store. Configs refer to entries as `${secret:name}`; the
+/// values only leave the vault when a config is materialized for a client
+/// that can't read them from its environment.
+pub struct Vault {
+    secrets: BTreeMap<String, String>,
+    /// Set when the file is (or is to be) encrypted
+    passphrase: Option<String>,
+}
+
+/// On-disk form: either plain `secrets` or an `encrypted` copy of them
+#[derive(Default, Serialize, Deserialize)]
+struct VaultFile {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    secrets: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    encrypted: Option<Sealed>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Sealed {
+    kdf: String,
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+impl Vault {
+    pub fn path() -> Option<PathBuf> {
+        Some(dirs::data_dir()?.join("mcpm/secrets.json"))
+    }
+
+    fn read_file() -> Result<VaultFile, String> {
+        let path = Self::path().ok_or("could not determine data directory")?;
+        match std::fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(VaultFile::default()),
+            Err(e) => Err(format!("failed to read {}: {}", path.display(), e)),
+        }
+    }
+
+    pub fn is_encrypted() -> bool {
+        Self::read_file().is_ok_and(|f| f.encrypted.is_some())
+    }
+
+    /// Open the vault; a missing file is an empty vault. An encrypted vault
+    /// needs `passphrase`.
+    pub fn open(passphrase: Option<&str>) -> Result<Vault, String> {
+        let file = Self::read_file()?;
+        let Some(sealed) = file.encrypted else {
+            return Ok(Vault {
+                secrets: file.secrets,
+                passphrase: None,
+            });
+        };
+        let passphrase = passphrase.ok_or_else(|| {
+            format!("the secret vault is encrypted; set {} to unlock it", PASSPHRASE_VAR)
+        })?;
+        Ok(Vault {
+            secrets: unseal(&sealed, passphrase)?,
+            passphrase: Some(passphrase.to_string()),
+        })
+    }
+
+    /// Open without prompting, taking the passphrase from the environment
+    pub fn open_from_env() -> Result<Vault, String> {
+        Self::open(std::env::var(PASSPHRASE_VAR).ok().as_deref())
+    }
+
+    /// Open from the terminal, asking for the passphrase if the vault is
+    /// encrypted and the environment doesn't have it
+    pub fn open_interactive() -> Result<Vault, String> {
+        if let Ok(passphrase) = std::env::var(PASSPHRASE_VAR) {
+            return Self::open(Some(&passphrase));
+        }
+        if !Self::is_encrypted() {
+            return Self::open(None);
+        }
+        let passphrase = rpassword::prompt_password("Vault passphrase: ")
+            .map_err(|e| format!("can't read the vault passphrase ({}); set {}", e, PASSPHRASE_VAR))?;
+        Self::open(Some(&passphrase))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.secrets.get(name).map(String::as_str)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &String> {
+        self.secrets.keys()
+    }
+
+    pub fn set(&mut self, name: &str, value: &str) {
+        self.secrets.insert(name.to_string(), value.to_string());
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.secrets.remove(name).is_some()
+    }
+
+    /// Encrypt with `passphrase` from the next save on, or store in plain
+    /// text with None
+    pub fn set_passphrase(&mut self, passphrase: Option<String>) {
+        self.passphrase = passphrase;
+    }
+
+    /// Name a new secret after `key`, or reuse the entry that already holds
+    /// `value`. Returns the name it is stored under.
+    pub fn store(&mut self, key: &str, value: &str, fallback_prefix: &str) -> String {
+        if let Some((name, _)) = self.secrets.iter().find(|(_, v)| *v == value) {
+            return name.clone();
+        }
+        let mut name = secret_name(key);
+        if self.secrets.contains_key(&name) {
+            name = secret_name(&format!("{}_{}", fallback_prefix, key));
+        }
+        let base = name.clone();
+        let mut n = 2;
+        while self.secrets.contains_key(&name) {
+            name = format!("{}_{}", base, n);
+            n += 1;
+        }
+        self.set(&name, value);
+        name
+    }
+
+    /// Write the vault readable by the owner only
+    pub fn save(&self) -> Result<(), String> {
+        let path = Self::path().ok_or("could not determine data directory")?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("failed to create directory {}: {}", parent.display(), e))?;
+        }
+        let file = match &self.passphrase {
+            Some(passphrase) => VaultFile {
+                secrets: BTreeMap::new(),
+                encrypted: Some(seal(&self.secrets, passphrase)?),
+            },
+            None => VaultFile {
+                secrets: self.secrets.clone(),
+                encrypted: None,
+            },
+        };
+        let text = serde_json::to_string_pretty(&file).map_err(|e| e.to_string())?;
+
+        let tmp = path.with_extension("json.tmp");
+        write_private(&tmp, text.as_bytes()).map_err(|e| format!("failed to write {}: {}", tmp.display(), e))?;
+        std::fs::rename(&tmp, &path)
+            .map_err(|e| format!("failed to rename {} to {}: {}", tmp.display(), path.display(), e))
+    }
+}
+
+#[cfg(unix)]
+fn write_private(path: &std::path::Path, bytes: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+    // Remove a leftover so the mode below applies to a fresh file
+    let _ = std::fs::remove_file(path);
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)?;
+    file.write_all(bytes)
+}
+
+#[cfg(not(unix))]
+fn write_private(path: &std::path::Path, bytes: &[u8]) -> std::io::Result<()> {
+    std::fs::write(path, bytes)
+}
+
+// ---------------------------------------------------------------------------
+// Encryption: Argon2id key derivation, ChaCha20-Poly1305
+// ---------------------------------------------------------------------------
+
+fn derive_key(passphrase: &str, salt: &[u8], params: Params) -> Result<[u8; 32], String> {
+    let mut key = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("key derivation failed: {}", e))?;
+    Ok(key)
+}
+
+fn seal(secrets: &BTreeMap<String, String>, passphrase: &str) -> Result<Sealed, String> {
+    let mut salt = [0u8; 16];
+    let mut nonce = [0u8; 12];
+    getrandom::fill(&mut salt).map_err(|e| e.to_string())?;
+    getrandom::fill(&mut nonce).map_err(|e| e.to_string())?;
+
+    let params = Params::default();
+    let key = derive_key(passphrase, &salt, params.clone())?;
+    let plaintext = serde_json::to_vec(secrets).map_err(|e| e.to_string())?;
+    let ciphertext = ChaCha20Poly1305::new(&key.into())
+        .encrypt(Nonce::from_slice(&nonce), plaintext.as_slice())
+        .map_err(|_| "encryption failed".to_string())?;
+
+    Ok(Sealed {
+        kdf: "argon2id".to_string(),
+        m_cost: params.m_cost(),
+        t_cost: params.t_cost(),
+        p_cost: params.p_cost(),
+        salt: BASE64.encode(salt),
+        nonce: BASE64.encode(nonce),
+        ciphertext: BASE64.encode(ciphertext),
+    })
+}
+
+fn unseal(sealed: &Sealed, passphrase: &str) -> Result<BTreeMap<String, String>, String> {
+    if sealed.kdf != "argon2id" {
+        return Err(format!("unsupported vault key derivation: {}", sealed.kdf));
+    }
+    let decode = |s: &str| BASE64.decode(s).map_err(|_| "the vault file is corrupt".to_string());
+    let salt = decode(&sealed.salt)?;
+    let nonce = decode(&sealed.nonce)?;
+    let ciphertext = decode(&sealed.ciphertext)?;
+    if nonce.len() != 12 {
+        return Err("the vault file is corrupt".to_string());
+    }
+
+    let params = Params::new(sealed.m_cost, sealed.t_cost, sealed.p_cost, Some(32)).map_err(|e| e.to_string())?;
+    let key = derive_key(passphrase, &salt, params)?;
+    let plaintext = ChaCha20Poly1305::new(&key.into())
+        .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+        .map_err(|_| "wrong passphrase for the secret vault".to_string())?;
+    serde_json::from_slice(&plaintext).map_err(|_| "the vault file is corrupt".to_string())
+}
+
+// ---------------------------------------------------------------------------
+// Placeholders
+// ---------------------------------------------------------------------------
+
+const PREFIX: &str = "${secret:";
+
+/// Normalize a key like GITHUB_TOKEN into a vault name: github_token
+pub fn secret_name(key: &str) -> String {
+    key.trim()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect()
+}
+
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+pub fn placeholder(name: &str) -> String {
+    format!("{}{}}}", PREFIX, name)
+}
+
+/// Environment variable a client that resolves env vars reads a secret from
+pub fn env_var(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect()
+}
+
+/// Names referenced by `${secret:name}` placeholders anywhere in `value`
+pub fn placeholders(value: &Value) -> Vec<String> {
+    let mut names = Vec::new();
+    visit_strings(value, &mut |s| {
+        let mut rest = s;
+        while let Some(i) = rest.find(PREFIX) {
+            let after = &rest[i + PREFIX.len()..];
+            let Some(end) = after.find('}') else { break };
+            if is_valid_name(&after[..end]) && !names.iter().any(|n| n == &after[..end]) {
+                names.push(after[..end].to_string());
+            }
+            rest = &after[end..];
+        }
+    });
+    names
+}
+
+fn visit_strings(value: &Value, f: &mut dyn FnMut(&str)) {
+    match value {
+        Value::String(s) => f(s),
+        Value::Array(items) => items.iter().for_each(|v| visit_strings(v, f)),
+        Value::Object(map) => map.values().for_each(|v| visit_strings(v, f)),
+        _ => {}
+    }
+}
+
+fn map_strings(value: &mut Value, f: &dyn Fn(&str) -> String) {
+    match value {
+        Value::String(s) => *s = f(s),
+        Value::Array(items) => items.iter_mut().for_each(|v| map_strings(v, f)),
+        Value::Object(map) => map.values_mut().for_each(|v| map_strings(v, f)),
+        _ => {}
+    }
+}
+
+/// A server entry as it should be written to `client`. Each `${secret:name}`
+/// becomes a reference to the NAME environment variable in clients that
+/// expand those, and the secret itself in clients that don't. When the vault
+/// is open, `env`/`headers` values that an earlier materialize produced are
+/// turned back into placeholders first, so syncing a materialized entry
+/// doesn't spread the token further. `${NAME}` references are only turned
+/// back for clients that expand them too: one may have been written by hand,
+/// and must not become a literal secret. `vault` is only an error if a
+/// secret is actually needed.
+pub fn materialize(client: &ClientKind, value: &Value, vault: &Result<Vault, String>) -> Result<Value, String> {
+    let mut value = value.clone();
+
+    if let Ok(vault) = vault {
+        // ${NAME} / ${env:NAME} references, re-written in this client's syntax
+        if client.env_reference("NAME").is_some() {
+            let mut known: Vec<(String, String)> = Vec::new();
+            for name in vault.secrets.keys() {
+                let var = env_var(name);
+                known.push((format!("${{{}}}", var), placeholder(name)));
+                known.push((format!("${{env:{}}}", var), placeholder(name)));
+            }
+            map_strings(&mut value, &|s| {
+                known
+                    .iter()
+                    .fold(s.to_string(), |s, (from, to)| s.replace(from.as_str(), to))
+            });
+        }
+        // Literal secrets only where materialize writes them: a whole `env`
+        // or `headers` value, or the token after `Bearer `. Anywhere else a
+        // match is more likely a coincidence than a copy.
+        let literal = |s: &str| {
+            vault
+                .secrets
+                .iter()
+                .find(|(_, secret)| secret.len() >= 8 && secret.as_str() == s)
+                .map(|(name, _)| placeholder(name))
+        };
+        for field in ["env", "headers"] {
+            let Some(map) = value.get_mut(field).and_then(Value::as_object_mut) else {
+                continue;
+            };
+            for v in map.values_mut() {
+                let Some(s) = v.as_str() else {
+                    continue;
+                };
+                let replaced = match s.split_once(' ') {
+                    Some((scheme, token)) if scheme.eq_ignore_ascii_case("bearer") => {
+                        literal(token).map(|p| format!("{} {}", scheme, p))
+                    }
+                    _ => literal(s),
+                };
+                if let Some(replaced) = replaced {
+                    *v = Value::String(replaced);
+                }
+            }
+        }
+    }
+
+    let names = placeholders(&value);
+    if names.is_empty() {
+        return Ok(value);
+    }
+
+    let references: Option<Vec<String>> = names.iter().map(|n| client.env_reference(&env_var(n))).collect();
+    let replacements: Vec<(String, String)> = match references {
+        Some(references) => names.iter().map(|n| placeholder(n)).zip(references).collect(),
+        None => {
+            let vault = vault.as_ref().map_err(String::clone)?;
+            names
+                .iter()
+                .map(|name| {
+                    let secret = vault.get(name).ok_or_else(|| {
+                        format!("no secret named \"{}\" (add it with `mcpm secret set {}`)", name, name)
+                    })?;
+                    Ok((placeholder(name), secret.to_string()))
+                })
+                .collect::<Result<_, String>>()?
+        }
+    };
+    map_strings(&mut value, &|s| {
+        replacements
+            .iter()
+            .fold(s.to_string(), |s, (from, to)| s.replace(from.as_str(), to))
+    });
+    Ok(value)
+}
@@ -1,24 +1,23 @@
 use std::collections::HashMap;
 
+use crate::inspector::Inspector;
+use crate::secrets;
 use crate::types::ClientKind;
 
 // ---------------------------------------------------------------------------
 // Mode — top-level modal state
 // ---------------------------------------------------------------------------
 
+#[derive(Default)]
 pub enum Mode {
+    #[default]
     Normal,
     AddWizard(AddWizard),
+    Inspector(Inspector),
     RemoveConfirm(RemoveConfirm),
     SyncSelect(SyncSelect),
 }
 
-impl Default for Mode {
-    fn default() -> Self {
-        Mode::Normal
-    }
-}
-
 // ---------------------------------------------------------------------------
 // Add Server Wizard
 // ---------------------------------------------------------------------------
@@ -32,6 +31,8 @@ pub struct AddWizard {
     pub url: String,
     pub env_lines: Vec<String>,
     pub env_input: String,
+    /// Move env values that look secret into the vault on confirm
+    pub save_secrets: bool,
     pub clients: Vec<(ClientKind, bool)>,
     pub cursor: usize,
     pub error: Option<String>,
@@ -45,6 +46,7 @@ pub enum AddStep {
     Args,
     Url,
     EnvVars,
+    SaveSecrets,
     Clients,
     Confirm,
 }
@@ -67,6 +69,7 @@ impl AddWizard {
             url: String::new(),
             env_lines: Vec::new(),
             env_input: String::new(),
+            save_secrets: false,
             clients,
             cursor: 0,
             error: None,
@@ -157,8 +160,12 @@ impl AddWizard {
             AddStep::EnvVars => {
                 if self.env_input.is_empty() {
                     // Empty line → done with env vars
-                    self.step = AddStep::Clients;
-                    self.cursor = 0;
+                    if self.secret_keys().is_empty() {
+                        self.step = AddStep::Clients;
+                        self.cursor = 0;
+                    } else {
+                        self.step = AddStep::SaveSecrets;
+                    }
                 } else {
                     // Validate KEY=VALUE format
                     if self.env_input.contains('=') {
@@ -170,6 +177,11 @@ impl AddWizard {
                     }
                 }
             }
+            AddStep::SaveSecrets => {
+                // y/n answered externally
+                self.step = AddStep::Clients;
+                self.cursor = 0;
+            }
             AddStep::Clients => {
                 let any_selected = self.clients.iter().any(|(_, sel)| *sel);
                 if !any_selected {
@@ -223,6 +235,18 @@ impl AddWizard {
             .collect()
     }
 
+    /// Env vars whose values look like tokens or passwords
+    pub fn secret_keys(&self) -> Vec<String> {
+        let mut keys: Vec<String> = self
+            .parsed_env()
+            .into_iter()
+            .filter(|(k, v)| secrets::classify(k, v).is_some())
+            .map(|(k, _)| k)
+            .collect();
+        keys.sort();
+        keys
+    }
+
     pub fn selected_clients(&self) -> Vec<ClientKind> {
         self.clients
             .iter()
@@ -239,6 +263,7 @@ impl AddWizard {
             AddStep::Args => "Arguments (space-separated)",
             AddStep::Url => "Server URL",
             AddStep::EnvVars => "Environment Variables",
+            AddStep::SaveSecrets => "Secrets",
             AddStep::Clients => "Install to Clients",
             AddStep::Confirm => "Confirm",
         }
@@ -323,6 +348,9 @@ impl RemoveConfirm {
 pub struct SyncSelect {
     pub server_name: String,
     pub server_value: serde_json::Value,
+    /// Stdio form of a remote server, via `mcpm bridge`, for clients that
+    /// can't connect to it directly
+    pub bridged_value: Option<serde_json::Value>,
     pub targets: Vec<(ClientKind, bool)>,
     pub cursor: usize,
 }
@@ -331,12 +359,14 @@ impl SyncSelect {
     pub fn new(
         server_name: String,
         server_value: serde_json::Value,
+        bridged_value: Option<serde_json::Value>,
         missing_clients: Vec<ClientKind>,
     ) -> Self {
         let targets = missing_clients.into_iter().map(|c| (c, false)).collect();
         SyncSelect {
             server_name,
             server_value,
+            bridged_value,
             targets,
             cursor: 0,
         }
@@ -360,6 +390,11 @@ impl SyncSelect {
         }
     }
 
+    /// Whether this client gets the bridged form rather than the server's own
+    pub fn needs_bridge(&self, client: &ClientKind) -> bool {
+        self.bridged_value.is_some() && !client.supports_remote()
+    }
+
     pub fn selected_clients(&self) -> Vec<ClientKind> {
         self.targets
             .iter()